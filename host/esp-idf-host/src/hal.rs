@@ -0,0 +1,124 @@
+//! Peripherals of the ESP32, which are not present on the host.
+
+pub mod gpio;
+pub mod i2c;
+pub mod rmt;
+pub mod spi;
+
+pub use crate::sys;
+
+pub mod adc {}
+
+pub mod delay {
+    use std::{thread, time::Duration};
+
+    /// Number of ticks, which waits without a timeout
+    pub const BLOCK: u32 = u32::MAX;
+
+    /// Number of ticks of the FreeRTOS scheduler, one tick takes a millisecond
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct TickType(pub u32);
+
+    impl TickType {
+        pub fn ticks(&self) -> u32 {
+            self.0
+        }
+    }
+
+    impl From<Duration> for TickType {
+        fn from(duration: Duration) -> Self {
+            TickType(duration.as_millis().min(u32::MAX as u128) as u32)
+        }
+    }
+
+    pub struct FreeRtos;
+
+    impl FreeRtos {
+        pub fn delay_ms(ms: u32) {
+            thread::sleep(Duration::from_millis(ms.into()));
+        }
+    }
+}
+
+pub mod peripheral {
+    pub trait Peripheral {
+        type P;
+    }
+}
+
+pub mod prelude {
+    pub use super::units::*;
+
+    use super::{i2c, rmt, spi};
+    use crate::sys::{EspError, ESP_ERR_NOT_SUPPORTED};
+
+    pub struct Peripherals {
+        pub i2c0: i2c::I2C0,
+        pub i2c1: i2c::I2C1,
+        pub spi2: spi::SPI2,
+        pub spi3: spi::SPI3,
+        pub rmt: rmt::RMT,
+    }
+
+    impl Peripherals {
+        /// Fails, the host has none of the peripherals of the ESP32.
+        pub fn take() -> Result<Self, EspError> {
+            Err(EspError::from(ESP_ERR_NOT_SUPPORTED).unwrap())
+        }
+    }
+}
+
+pub mod task {
+    use std::{
+        future::Future,
+        pin::pin,
+        sync::Arc,
+        task::{Context, Poll, Wake, Waker},
+        thread::{self, Thread},
+    };
+
+    /// Wakes the thread blocked on the future.
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Polls the future on the current thread, until it completes.
+    pub fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut context = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+}
+
+pub mod units {
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct Hertz(pub u32);
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct KiloHertz(pub u32);
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct MegaHertz(pub u32);
+
+    impl From<KiloHertz> for Hertz {
+        fn from(frequency: KiloHertz) -> Self {
+            Hertz(frequency.0 * 1_000)
+        }
+    }
+
+    impl From<MegaHertz> for Hertz {
+        fn from(frequency: MegaHertz) -> Self {
+            Hertz(frequency.0 * 1_000_000)
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Pins, which keep the level set by the firmware, and whose inputs never change.
+
+use std::{future, marker::PhantomData};
+
+use crate::sys::EspError;
+
+pub trait Pin: Send + 'static {
+    fn pin(&self) -> i32;
+}
+
+pub trait InputPin: Pin {}
+
+pub trait OutputPin: Pin {}
+
+pub trait IOPin: InputPin + OutputPin {}
+
+macro_rules! any_pin {
+    ($name:ident $(: $($mode:ident),*)?) => {
+        pub struct $name(i32);
+
+        impl $name {
+            pub unsafe fn new(pin: i32) -> Self {
+                $name(pin)
+            }
+        }
+
+        impl Pin for $name {
+            fn pin(&self) -> i32 {
+                self.0
+            }
+        }
+
+        $($(impl $mode for $name {})*)?
+    };
+}
+
+any_pin!(AnyIOPin: InputPin, OutputPin, IOPin);
+any_pin!(AnyInputPin: InputPin);
+any_pin!(AnyOutputPin: OutputPin);
+
+/// Pin 0, used to leave the optional pins of a bus unconnected
+pub struct Gpio0;
+
+impl Pin for Gpio0 {
+    fn pin(&self) -> i32 {
+        0
+    }
+}
+
+impl InputPin for Gpio0 {}
+impl OutputPin for Gpio0 {}
+impl IOPin for Gpio0 {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Low,
+    High,
+}
+
+impl From<bool> for Level {
+    fn from(high: bool) -> Self {
+        match high {
+            true => Level::High,
+            false => Level::Low,
+        }
+    }
+}
+
+impl From<Level> for bool {
+    fn from(level: Level) -> Self {
+        level == Level::High
+    }
+}
+
+impl std::ops::Not for Level {
+    type Output = Level;
+
+    fn not(self) -> Level {
+        match self {
+            Level::Low => Level::High,
+            Level::High => Level::Low,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pull {
+    Floating,
+    Up,
+    Down,
+    UpDown,
+}
+
+pub struct Input;
+
+pub struct Output;
+
+pub struct PinDriver<'d, T, MODE> {
+    pin: T,
+    level: Level,
+    mode: PhantomData<(&'d (), MODE)>,
+}
+
+impl<'d, T: InputPin> PinDriver<'d, T, Input> {
+    /// Returns an input, which stays low.
+    pub fn input(pin: T) -> Result<Self, EspError> {
+        Ok(PinDriver {
+            pin,
+            level: Level::Low,
+            mode: PhantomData,
+        })
+    }
+}
+
+impl<'d, T: OutputPin> PinDriver<'d, T, Output> {
+    pub fn output(pin: T) -> Result<Self, EspError> {
+        Ok(PinDriver {
+            pin,
+            level: Level::Low,
+            mode: PhantomData,
+        })
+    }
+}
+
+impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
+    pub fn pin(&self) -> i32 {
+        self.pin.pin()
+    }
+
+    pub fn set_pull(&mut self, _pull: Pull) -> Result<(), EspError> {
+        Ok(())
+    }
+
+    pub fn get_level(&self) -> Level {
+        self.level
+    }
+
+    pub fn is_high(&self) -> bool {
+        self.level == Level::High
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.level == Level::Low
+    }
+
+    pub fn is_set_high(&self) -> bool {
+        self.is_high()
+    }
+
+    pub fn is_set_low(&self) -> bool {
+        self.is_low()
+    }
+
+    pub fn set_level(&mut self, level: Level) -> Result<(), EspError> {
+        self.level = level;
+        Ok(())
+    }
+
+    pub fn set_high(&mut self) -> Result<(), EspError> {
+        self.set_level(Level::High)
+    }
+
+    pub fn set_low(&mut self) -> Result<(), EspError> {
+        self.set_level(Level::Low)
+    }
+
+    pub fn toggle(&mut self) -> Result<(), EspError> {
+        self.set_level(!self.level)
+    }
+
+    /// Never completes, the inputs do not change on the host.
+    pub async fn wait_for_high(&mut self) -> Result<(), EspError> {
+        future::pending().await
+    }
+
+    /// Never completes, the inputs do not change on the host.
+    pub async fn wait_for_low(&mut self) -> Result<(), EspError> {
+        future::pending().await
+    }
+
+    /// Never completes, the inputs do not change on the host.
+    pub async fn wait_for_rising_edge(&mut self) -> Result<(), EspError> {
+        future::pending().await
+    }
+
+    /// Never completes, the inputs do not change on the host.
+    pub async fn wait_for_falling_edge(&mut self) -> Result<(), EspError> {
+        future::pending().await
+    }
+
+    /// Never completes, the inputs do not change on the host.
+    pub async fn wait_for_any_edge(&mut self) -> Result<(), EspError> {
+        future::pending().await
+    }
+}
+
+impl<'d, T: Pin, MODE> embedded_hal::digital::ErrorType for PinDriver<'d, T, MODE> {
+    type Error = EspError;
+}
+
+impl<'d, T: OutputPin> embedded_hal::digital::OutputPin for PinDriver<'d, T, Output> {
+    fn set_low(&mut self) -> Result<(), EspError> {
+        self.set_level(Level::Low)
+    }
+
+    fn set_high(&mut self) -> Result<(), EspError> {
+        self.set_level(Level::High)
+    }
+}
@@ -0,0 +1,11 @@
+//! Stand-in for the parts of `esp-idf-svc`, which the firmware uses,
+//! so that the firmware logic can be built and unit tested on the development machine.
+//!
+//! The storage is kept in memory, the peripherals cannot be taken
+//! and the buses report errors, as there is no hardware behind them.
+
+pub mod hal;
+pub mod log;
+pub mod nvs;
+pub mod sys;
+pub mod timer {}
@@ -0,0 +1,32 @@
+use log::LevelFilter;
+
+use crate::sys::EspError;
+
+/// Logger, which would print the log of the firmware to the serial console.
+pub struct EspLogger {
+    _private: (),
+}
+
+impl EspLogger {
+    pub const fn new() -> Self {
+        EspLogger { _private: () }
+    }
+
+    /// Does nothing, the tests do not need a logger.
+    pub fn initialize_default() {}
+
+    /// Does nothing, there are no levels of the ESP-IDF log to set.
+    pub fn set_target_level(
+        &self,
+        _target: impl AsRef<str>,
+        _level_filter: LevelFilter,
+    ) -> Result<(), EspError> {
+        Ok(())
+    }
+}
+
+impl Default for EspLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
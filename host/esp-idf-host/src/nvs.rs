@@ -0,0 +1,208 @@
+//! Non-volatile storage kept in memory, shared by all handles opened on one partition.
+
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use crate::sys::{
+    EspError, ESP_ERR_NVS_INVALID_LENGTH, ESP_ERR_NVS_KEY_TOO_LONG, ESP_ERR_NVS_NOT_ENOUGH_SPACE,
+};
+
+/// Longest key or namespace name accepted by NVS
+const MAX_KEY_LEN: usize = 15;
+
+/// Longest blob or string accepted by NVS, including the string terminator
+const MAX_VALUE_LEN: usize = 4000;
+
+/// Value stored under a key, readable only with the type it was written with
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    Raw(Vec<u8>),
+    Str(String),
+}
+
+/// Values of all namespaces, by the namespace and the key
+type Entries = HashMap<(String, String), Value>;
+
+pub struct NvsDefault;
+
+pub struct EspNvsPartition<T> {
+    entries: Arc<Mutex<Entries>>,
+    partition: PhantomData<T>,
+}
+
+pub type EspDefaultNvsPartition = EspNvsPartition<NvsDefault>;
+
+impl<T> Clone for EspNvsPartition<T> {
+    fn clone(&self) -> Self {
+        EspNvsPartition {
+            entries: self.entries.clone(),
+            partition: PhantomData,
+        }
+    }
+}
+
+impl EspNvsPartition<NvsDefault> {
+    /// Returns a new empty partition, every call starts with no stored values.
+    pub fn take() -> Result<Self, EspError> {
+        Ok(EspNvsPartition {
+            entries: Arc::default(),
+            partition: PhantomData,
+        })
+    }
+}
+
+/// Handle of one namespace of the partition
+pub struct EspNvs<T> {
+    partition: EspNvsPartition<T>,
+    namespace: String,
+}
+
+pub type EspDefaultNvs = EspNvs<NvsDefault>;
+
+/// Returns the error NVS reports for the code.
+fn error(code: i32) -> EspError {
+    EspError::from(code).unwrap()
+}
+
+/// Checks that the key or namespace is not too long for NVS.
+fn check_key(name: &str) -> Result<(), EspError> {
+    match name.len() > MAX_KEY_LEN {
+        true => Err(error(ESP_ERR_NVS_KEY_TOO_LONG)),
+        false => Ok(()),
+    }
+}
+
+macro_rules! number_accessors {
+    ($($get:ident, $set:ident: $type:ty = $variant:ident;)*) => {
+        $(
+            pub fn $get(&self, name: &str) -> Result<Option<$type>, EspError> {
+                Ok(match self.get(name)? {
+                    Some(Value::$variant(value)) => Some(value),
+                    _ => None,
+                })
+            }
+
+            pub fn $set(&mut self, name: &str, value: $type) -> Result<(), EspError> {
+                self.set(name, Value::$variant(value))
+            }
+        )*
+    };
+}
+
+impl<T> EspNvs<T> {
+    pub fn new(
+        partition: EspNvsPartition<T>,
+        namespace: &str,
+        _read_write: bool,
+    ) -> Result<Self, EspError> {
+        check_key(namespace)?;
+        Ok(EspNvs {
+            partition,
+            namespace: namespace.to_string(),
+        })
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.partition.entries.lock().unwrap()
+    }
+
+    fn entry_key(&self, name: &str) -> Result<(String, String), EspError> {
+        check_key(name)?;
+        Ok((self.namespace.clone(), name.to_string()))
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Value>, EspError> {
+        let key = self.entry_key(name)?;
+        Ok(self.entries().get(&key).cloned())
+    }
+
+    fn set(&mut self, name: &str, value: Value) -> Result<(), EspError> {
+        let key = self.entry_key(name)?;
+        self.entries().insert(key, value);
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> Result<bool, EspError> {
+        Ok(self.get(name)?.is_some())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<bool, EspError> {
+        let key = self.entry_key(name)?;
+        Ok(self.entries().remove(&key).is_some())
+    }
+
+    number_accessors! {
+        get_u8, set_u8: u8 = U8;
+        get_i8, set_i8: i8 = I8;
+        get_u16, set_u16: u16 = U16;
+        get_u32, set_u32: u32 = U32;
+        get_u64, set_u64: u64 = U64;
+    }
+
+    pub fn blob_len(&self, name: &str) -> Result<Option<usize>, EspError> {
+        Ok(match self.get(name)? {
+            Some(Value::Raw(blob)) => Some(blob.len()),
+            _ => None,
+        })
+    }
+
+    /// Reads the blob into the buffer, fails if the buffer is shorter than the blob.
+    pub fn get_raw<'a>(&self, name: &str, buf: &'a mut [u8]) -> Result<Option<&'a [u8]>, EspError> {
+        match self.get(name)? {
+            Some(Value::Raw(blob)) if blob.len() > buf.len() => {
+                Err(error(ESP_ERR_NVS_INVALID_LENGTH))
+            }
+            Some(Value::Raw(blob)) => {
+                buf[..blob.len()].copy_from_slice(&blob);
+                Ok(Some(&buf[..blob.len()]))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn set_raw(&mut self, name: &str, buf: &[u8]) -> Result<bool, EspError> {
+        if buf.len() > MAX_VALUE_LEN {
+            return Err(error(ESP_ERR_NVS_NOT_ENOUGH_SPACE));
+        }
+        self.set(name, Value::Raw(buf.to_vec()))?;
+        Ok(true)
+    }
+
+    /// Returns the length of the string, including its terminator, as NVS does.
+    pub fn str_len(&self, name: &str) -> Result<Option<usize>, EspError> {
+        Ok(match self.get(name)? {
+            Some(Value::Str(string)) => Some(string.len() + 1),
+            _ => None,
+        })
+    }
+
+    /// Reads the string into the buffer, which must also fit the string terminator.
+    pub fn get_str<'a>(&self, name: &str, buf: &'a mut [u8]) -> Result<Option<&'a str>, EspError> {
+        match self.get(name)? {
+            Some(Value::Str(string)) if string.len() + 1 > buf.len() => {
+                Err(error(ESP_ERR_NVS_INVALID_LENGTH))
+            }
+            Some(Value::Str(string)) => {
+                buf[..string.len()].copy_from_slice(string.as_bytes());
+                buf[string.len()] = 0;
+                Ok(Some(std::str::from_utf8(&buf[..string.len()]).unwrap()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn set_str(&mut self, name: &str, value: &str) -> Result<(), EspError> {
+        if value.len() + 1 > MAX_VALUE_LEN {
+            return Err(error(ESP_ERR_NVS_NOT_ENOUGH_SPACE));
+        }
+        self.set(name, Value::Str(value.to_string()))
+    }
+}
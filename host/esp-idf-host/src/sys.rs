@@ -0,0 +1,203 @@
+//! Functions of ESP-IDF called directly by the firmware, with the same names and types
+//! as in the generated bindings.
+#![allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
+#![allow(clippy::missing_safety_doc)] // the functions are unsafe only to match the bindings
+
+use std::{
+    ffi::c_void,
+    fmt::{self, Display, Formatter},
+    num::NonZeroI32,
+    ptr,
+    sync::OnceLock,
+    time::Instant,
+};
+
+pub type esp_err_t = i32;
+
+pub const ESP_OK: esp_err_t = 0;
+pub const ESP_FAIL: esp_err_t = -1;
+pub const ESP_ERR_NOT_SUPPORTED: esp_err_t = 0x106;
+pub const ESP_ERR_NVS_NOT_ENOUGH_SPACE: esp_err_t = 0x1105;
+pub const ESP_ERR_NVS_KEY_TOO_LONG: esp_err_t = 0x1109;
+pub const ESP_ERR_NVS_INVALID_LENGTH: esp_err_t = 0x110c;
+
+/// Error code returned by ESP-IDF, never `ESP_OK`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EspError(NonZeroI32);
+
+impl EspError {
+    /// Returns the error of the code, `None` for `ESP_OK`.
+    pub fn from(code: esp_err_t) -> Option<Self> {
+        NonZeroI32::new(code).map(EspError)
+    }
+
+    /// Returns the error of a code, which is known not to be `ESP_OK`.
+    pub fn from_non_zero(code: NonZeroI32) -> Self {
+        EspError(code)
+    }
+
+    /// Converts the code returned by a function to a result.
+    pub fn convert(code: esp_err_t) -> Result<(), EspError> {
+        match EspError::from(code) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    pub fn code(&self) -> esp_err_t {
+        self.0.get()
+    }
+}
+
+impl Display for EspError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ESP-IDF error {:#x}", self.code())
+    }
+}
+
+impl std::error::Error for EspError {}
+
+impl embedded_hal::digital::Error for EspError {
+    fn kind(&self) -> embedded_hal::digital::ErrorKind {
+        embedded_hal::digital::ErrorKind::Other
+    }
+}
+
+impl embedded_hal::i2c::Error for EspError {
+    fn kind(&self) -> embedded_hal::i2c::ErrorKind {
+        embedded_hal::i2c::ErrorKind::Other
+    }
+}
+
+impl embedded_hal::spi::Error for EspError {
+    fn kind(&self) -> embedded_hal::spi::ErrorKind {
+        embedded_hal::spi::ErrorKind::Other
+    }
+}
+
+pub fn link_patches() {}
+
+/// Returns the microseconds since the first call, which stands in for the boot.
+pub unsafe fn esp_timer_get_time() -> i64 {
+    static BOOT: OnceLock<Instant> = OnceLock::new();
+    BOOT.get_or_init(Instant::now).elapsed().as_micros() as i64
+}
+
+pub unsafe fn esp_restart() -> ! {
+    panic!("the firmware requested a restart")
+}
+
+pub unsafe fn esp_deep_sleep_start() -> ! {
+    panic!("the firmware requested a deep sleep")
+}
+
+pub unsafe fn esp_sleep_enable_ext0_wakeup(_gpio_num: i32, _level: i32) -> esp_err_t {
+    ESP_ERR_NOT_SUPPORTED
+}
+
+pub unsafe fn rtc_gpio_is_valid_gpio(_gpio_num: i32) -> bool {
+    false
+}
+
+pub unsafe fn gpio_set_level(_gpio_num: i32, _level: u32) -> esp_err_t {
+    ESP_OK
+}
+
+pub unsafe fn gpio_hold_en(_gpio_num: i32) -> esp_err_t {
+    ESP_OK
+}
+
+pub unsafe fn gpio_deep_sleep_hold_en() {}
+
+pub unsafe fn i2c_set_timeout(_i2c_num: i32, _timeout: i32) -> esp_err_t {
+    ESP_OK
+}
+
+pub unsafe fn esp_task_wdt_add(_task_handle: *mut c_void) -> esp_err_t {
+    ESP_OK
+}
+
+pub unsafe fn esp_task_wdt_delete(_task_handle: *mut c_void) -> esp_err_t {
+    ESP_OK
+}
+
+pub unsafe fn esp_task_wdt_reset() -> esp_err_t {
+    ESP_OK
+}
+
+pub unsafe fn xTaskGetCurrentTaskHandle() -> *mut c_void {
+    ptr::null_mut()
+}
+
+pub const MALLOC_CAP_DEFAULT: u32 = 1 << 12;
+
+pub unsafe fn esp_get_free_heap_size() -> u32 {
+    0
+}
+
+pub unsafe fn esp_get_minimum_free_heap_size() -> u32 {
+    0
+}
+
+pub unsafe fn heap_caps_get_largest_free_block(_caps: u32) -> usize {
+    0
+}
+
+pub unsafe fn uxTaskGetNumberOfTasks() -> u32 {
+    0
+}
+
+pub type adc_unit_t = u32;
+pub type adc_channel_t = u32;
+pub type adc_atten_t = u32;
+pub type adc_bitwidth_t = u32;
+
+pub const adc_atten_t_ADC_ATTEN_DB_11: adc_atten_t = 3;
+pub const adc_bitwidth_t_ADC_BITWIDTH_DEFAULT: adc_bitwidth_t = 0;
+
+pub struct adc_oneshot_unit_ctx_t;
+pub type adc_oneshot_unit_handle_t = *mut adc_oneshot_unit_ctx_t;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct adc_oneshot_unit_init_cfg_t {
+    pub unit_id: adc_unit_t,
+    pub clk_src: u32,
+    pub ulp_mode: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct adc_oneshot_chan_cfg_t {
+    pub atten: adc_atten_t,
+    pub bitwidth: adc_bitwidth_t,
+}
+
+pub unsafe fn adc_oneshot_io_to_channel(
+    _io_num: i32,
+    _unit_id: *mut adc_unit_t,
+    _channel: *mut adc_channel_t,
+) -> esp_err_t {
+    ESP_ERR_NOT_SUPPORTED
+}
+
+pub unsafe fn adc_oneshot_new_unit(
+    _init_config: *const adc_oneshot_unit_init_cfg_t,
+    _ret_unit: *mut adc_oneshot_unit_handle_t,
+) -> esp_err_t {
+    ESP_ERR_NOT_SUPPORTED
+}
+
+pub unsafe fn adc_oneshot_config_channel(
+    _handle: adc_oneshot_unit_handle_t,
+    _channel: adc_channel_t,
+    _config: *const adc_oneshot_chan_cfg_t,
+) -> esp_err_t {
+    ESP_ERR_NOT_SUPPORTED
+}
+
+pub unsafe fn adc_oneshot_read(
+    _handle: adc_oneshot_unit_handle_t,
+    _channel: adc_channel_t,
+    _out_raw: *mut i32,
+) -> esp_err_t {
+    ESP_ERR_NOT_SUPPORTED
+}
@@ -0,0 +1,66 @@
+use esp_idf_svc::sys::{
+    esp_get_free_heap_size, esp_get_minimum_free_heap_size, heap_caps_get_largest_free_block,
+    uxTaskGetNumberOfTasks, MALLOC_CAP_DEFAULT,
+};
+use std::time::{Duration, Instant};
+
+use crate::{AppState, InputEvent, Screen};
+
+/// How often the statistics on the about and RDS screens are refreshed
+const ABOUT_REFRESH: Duration = Duration::from_secs(1);
+
+/// Snapshot of the heap and task statistics.
+pub struct MemoryStats {
+    /// Currently free heap, in bytes
+    pub free_heap: u32,
+    /// Largest heap block, which can still be allocated, in bytes
+    pub largest_free_block: usize,
+    /// Lowest free heap since boot, in bytes
+    pub min_free_heap: u32,
+    /// Number of FreeRTOS tasks
+    pub tasks: u32,
+}
+
+impl MemoryStats {
+    /// Reads the current statistics from the system.
+    pub fn read() -> Self {
+        // these only read counters of the allocator and the scheduler
+        unsafe {
+            MemoryStats {
+                free_heap: esp_get_free_heap_size(),
+                largest_free_block: heap_caps_get_largest_free_block(MALLOC_CAP_DEFAULT),
+                min_free_heap: esp_get_minimum_free_heap_size(),
+                tasks: uxTaskGetNumberOfTasks(),
+            }
+        }
+    }
+}
+
+impl AppState {
+    /// Returns whether the about or RDS statistics screen is shown,
+    /// and its statistics should be redrawn.
+    pub fn update_about(&mut self) -> bool {
+        let shown = matches!(self.screen, Screen::About | Screen::RdsStats);
+        if !shown || self.about_refreshed.elapsed() < ABOUT_REFRESH {
+            return false;
+        }
+
+        self.about_refreshed = Instant::now();
+        true
+    }
+
+    /// Processes user input on the about screen.
+    pub fn process_about_input(&mut self, event: InputEvent) {
+        use InputEvent as I;
+        match event {
+            // return back to the home screen
+            I::LongPress | I::VeryLongPress => self.screen = Screen::Home,
+
+            // ignore all other user inputs, there is nothing to scroll through
+            I::ScrollDown | I::ScrollUp | I::ShortPress | I::DoublePress | I::Hold => (),
+
+            // events from radio are processed before reaching the screen
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use esp_idf_svc::sys::{
+    adc_atten_t_ADC_ATTEN_DB_11, adc_bitwidth_t_ADC_BITWIDTH_DEFAULT, adc_channel_t,
+    adc_oneshot_chan_cfg_t, adc_oneshot_config_channel, adc_oneshot_io_to_channel,
+    adc_oneshot_new_unit, adc_oneshot_read, adc_oneshot_unit_handle_t, adc_oneshot_unit_init_cfg_t,
+    adc_unit_t, EspError,
+};
+use std::{ptr, sync::mpsc::Sender, thread, time::Duration};
+
+use crate::{display::BRIGHTNESS_LEVELS, error::Error, InputEvent};
+
+/// Time between two readings of the light sensor
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Highest reading of the 12-bit ADC
+const ADC_MAX: u32 = 4095;
+
+/// Weight of a new reading in the average, as a power of two, a larger one smooths more
+const SMOOTHING_SHIFT: u32 = 3;
+
+/// Distance, by which the average has to cross into the range of another level,
+/// before the level changes
+const LEVEL_HYSTERESIS: u32 = 80;
+
+/// Turns readings of the light sensor into brightness levels of the display.
+///
+/// Readings are averaged, so that a passing shadow does not change the level,
+/// and a level is left only once the average is well inside the range of another,
+/// so that light near a boundary does not make the display flicker.
+pub struct AmbientFilter {
+    /// Average of the readings, scaled up by `SMOOTHING_SHIFT` bits, `None` before the first one
+    average: Option<u32>,
+    /// Level last reported, an index into `BRIGHTNESS_LEVELS`
+    level: u8,
+}
+
+impl AmbientFilter {
+    pub fn new() -> Self {
+        AmbientFilter {
+            average: None,
+            level: 0,
+        }
+    }
+
+    /// Notes a new reading, returns the brightness level whenever it changes.
+    pub fn update(&mut self, raw: u32) -> Option<u8> {
+        let raw = raw.min(ADC_MAX);
+        let average = match self.average {
+            Some(average) => average - (average >> SMOOTHING_SHIFT) + raw,
+            None => raw << SMOOTHING_SHIFT,
+        };
+        let first = self.average.is_none();
+        self.average = Some(average);
+
+        let value = average >> SMOOTHING_SHIFT;
+        let range = (ADC_MAX + 1) / BRIGHTNESS_LEVELS.len() as u32;
+        let level = (value / range).min(BRIGHTNESS_LEVELS.len() as u32 - 1) as u8;
+
+        let current = self.level as u32;
+        let changed = match level as u32 {
+            _ if first => true,
+            level if level > current => value >= level * range + LEVEL_HYSTERESIS,
+            level if level < current => value + LEVEL_HYSTERESIS < current * range,
+            _ => false,
+        };
+        if !changed {
+            return None;
+        }
+
+        self.level = level;
+        Some(level)
+    }
+}
+
+/// Spawns a new thread, which reads the light sensor on the pin with the given number
+/// and sends `AmbientLight` events with the brightness level fitting the room.
+///
+/// The sensor is expected to be a voltage divider, e.g. a photoresistor to 3.3 V
+/// and a fixed resistor to ground, whose voltage rises with the light.
+/// The ADC is driven directly, because the ADC driver of the HAL needs the pin
+/// as its own type, while pins here are created from their numbers.
+pub fn spawn_ambient_thread(pin: i32, event_sender: Sender<InputEvent>) -> Result<(), Error> {
+    let mut unit: adc_unit_t = 0;
+    let mut channel: adc_channel_t = 0;
+    let mut handle: adc_oneshot_unit_handle_t = ptr::null_mut();
+
+    EspError::convert(unsafe { adc_oneshot_io_to_channel(pin, &mut unit, &mut channel) })?;
+    let unit_config = adc_oneshot_unit_init_cfg_t {
+        unit_id: unit,
+        ..Default::default()
+    };
+    EspError::convert(unsafe { adc_oneshot_new_unit(&unit_config, &mut handle) })?;
+    let channel_config = adc_oneshot_chan_cfg_t {
+        atten: adc_atten_t_ADC_ATTEN_DB_11,
+        bitwidth: adc_bitwidth_t_ADC_BITWIDTH_DEFAULT,
+    };
+    EspError::convert(unsafe { adc_oneshot_config_channel(handle, channel, &channel_config) })?;
+
+    // the handle is only a pointer, but the unit is used by this thread alone
+    let handle = handle as usize;
+    thread::spawn(move || {
+        let handle = handle as adc_oneshot_unit_handle_t;
+        let mut filter = AmbientFilter::new();
+        loop {
+            let mut raw = 0;
+            match EspError::convert(unsafe { adc_oneshot_read(handle, channel, &mut raw) }) {
+                Ok(()) => {
+                    if let Some(level) = filter.update(raw as u32) {
+                        event_sender.send(InputEvent::AmbientLight(level)).unwrap();
+                    }
+                }
+                Err(err) => log::warn!("ambient: reading the light sensor failed: {err}"),
+            }
+            thread::sleep(SAMPLE_INTERVAL);
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use esp_idf_svc::sys::{
+    esp_deep_sleep_start, esp_sleep_enable_ext0_wakeup, rtc_gpio_is_valid_gpio,
+};
+use std::{sync::mpsc::Sender, time::Duration};
+
+use crate::{panic::turn_off_amp, pins::PIN_CONFIG, tuner::request, AppState, OutputCommand};
+
+/// Selectable limits of the time on without user input, in minutes, zero disables the auto-off
+pub const AUTO_OFF_LIMITS: [u8; 5] = [0, 30, 60, 120, 240];
+
+/// How long before powering off the warning is shown, any input then restarts the limit
+const AUTO_OFF_WARNING: Duration = Duration::from_secs(60);
+
+impl AppState {
+    /// Returns the time left until the radio powers off, if the auto-off is enabled,
+    /// the limit runs from the boot or the last user input.
+    pub fn auto_off_remaining(&self) -> Option<Duration> {
+        let limit = Duration::from_secs(self.settings.auto_off as u64 * 60);
+        (!limit.is_zero()).then(|| limit.saturating_sub(self.last_input.elapsed()))
+    }
+
+    /// Returns the warning shown during the last minute before powering off.
+    pub fn auto_off_countdown(&self) -> Option<String> {
+        self.auto_off_remaining()
+            .filter(|&remaining| remaining <= AUTO_OFF_WARNING)
+            .map(|remaining| format!("Off in {} s", remaining.as_secs()))
+    }
+
+    /// Returns whether the limit ran out, and the radio should power off.
+    pub fn auto_off_due(&self) -> bool {
+        self.auto_off_remaining()
+            .is_some_and(|remaining| remaining.is_zero())
+    }
+
+    /// Returns whether the warning changed and should be redrawn.
+    pub fn update_auto_off(&mut self) -> bool {
+        let countdown = self.auto_off_countdown();
+        if countdown == self.auto_off_countdown_shown {
+            return false;
+        }
+        self.auto_off_countdown_shown = countdown;
+        true
+    }
+}
+
+/// Mutes the tuner, turns off the amplifier and puts the chip into deep sleep.
+///
+/// The encoder button wakes the radio up, when its pin can wake the chip,
+/// otherwise only a reset does, either way the radio then boots again.
+pub fn power_off(command: &Sender<OutputCommand>) -> ! {
+    if let Err(err) = request(command, OutputCommand::SetVolume(0)) {
+        log::warn!("Muting before power off failed: {err}");
+    }
+    turn_off_amp();
+
+    // only RTC pins can wake the chip from deep sleep
+    let button = PIN_CONFIG.button;
+    if unsafe { rtc_gpio_is_valid_gpio(button) } {
+        unsafe { esp_sleep_enable_ext0_wakeup(button, 0) };
+    }
+
+    log::info!("Powering off");
+    unsafe { esp_deep_sleep_start() }
+}
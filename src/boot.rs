@@ -0,0 +1,20 @@
+use crate::error::Error;
+
+/// Logs the result of a single initialization step, so that a failed
+/// peripheral can be identified from the log during bring-up.
+///
+/// Startup cannot continue after a failed step, so the error is returned
+/// for the caller to give up with.
+pub fn boot_step<T, E: Into<Error>>(step: &str, result: Result<T, E>) -> Result<T, Error> {
+    match result {
+        Ok(value) => {
+            log::info!("boot: {step} ok");
+            Ok(value)
+        }
+        Err(err) => {
+            let err = err.into();
+            log::error!("boot: {step} failed: {err}");
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::sync::mpsc::Sender;
+
+use crate::{
+    frequency::{channel_spacing_khz, Frequency},
+    storage::METER_RANGE_KEY,
+    tuner::send_command,
+    AppState, InputEvent, OutputCommand, Screen,
+};
+
+/// Range of RSSI shown by the signal meter, until it is calibrated, from empty to full
+pub const DEFAULT_METER_RANGE: (u8, u8) = (0, 60);
+
+/// Smallest distance between the noise floor and the strong station, which still
+/// spreads the meter over a useful range
+const MIN_METER_SPAN: u8 = 10;
+
+/// Steps of the calibration of the signal meter, in the order the user goes through them.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationStep {
+    /// The user tunes a strong station
+    Strong,
+    /// The tuner measures the strong station
+    MeasuringStrong,
+    /// The user tunes an empty channel, with the RSSI measured on the strong station
+    Noise(u8),
+    /// The tuner measures the noise floor, with the RSSI measured on the strong station
+    MeasuringNoise(u8),
+}
+
+/// Running calibration of the signal meter.
+pub struct Calibration {
+    /// Station tuned before the calibration, it is tuned again afterwards
+    from: Frequency,
+    step: CalibrationStep,
+}
+
+impl AppState {
+    /// Opens the calibration screen, which walks the user through measuring
+    /// a strong station and the noise floor.
+    pub fn start_calibration(&mut self) {
+        self.calibration = Some(Calibration {
+            from: self.frequency,
+            step: CalibrationStep::Strong,
+        });
+        self.screen = Screen::Calibration;
+    }
+
+    /// Ends the running calibration and tunes the station tuned before it.
+    pub fn cancel_calibration(&mut self, command: &Sender<OutputCommand>) {
+        let Some(calibration) = self.calibration.take() else {
+            return;
+        };
+        self.frequency = calibration.from;
+        send_command(command, OutputCommand::SetFrequency(self.frequency));
+    }
+
+    /// Returns the instructions for the current step, on two lines.
+    pub fn calibration_lines(&self) -> (&'static str, &'static str) {
+        match self
+            .calibration
+            .as_ref()
+            .map(|calibration| calibration.step)
+        {
+            Some(CalibrationStep::Strong) | None => ("1/2 Tune a strong", "station, then press"),
+            Some(CalibrationStep::MeasuringStrong) => ("1/2 Measuring...", ""),
+            Some(CalibrationStep::Noise(_)) => ("2/2 Tune an empty", "channel, then press"),
+            Some(CalibrationStep::MeasuringNoise(_)) => ("2/2 Measuring...", ""),
+        }
+    }
+
+    /// Processes user input on the calibration screen.
+    pub fn process_calibration_input(
+        &mut self,
+        event: InputEvent,
+        command: &Sender<OutputCommand>,
+    ) {
+        let Some(calibration) = self.calibration.as_mut() else {
+            return;
+        };
+
+        use CalibrationStep as S;
+        use InputEvent as I;
+        match (calibration.step, event) {
+            // the encoder tunes, while no measurement runs
+            (S::Strong | S::Noise(_), I::ScrollUp) => {
+                self.frequency = self.frequency.stepped_up(channel_spacing_khz());
+                send_command(command, OutputCommand::SetFrequency(self.frequency));
+            }
+            (S::Strong | S::Noise(_), I::ScrollDown) => {
+                self.frequency = self.frequency.stepped_down(channel_spacing_khz());
+                send_command(command, OutputCommand::SetFrequency(self.frequency));
+            }
+
+            // measure the tuned frequency
+            (S::Strong, I::ShortPress) => {
+                calibration.step = S::MeasuringStrong;
+                send_command(command, OutputCommand::MeasureRssi);
+            }
+            (S::Noise(strong), I::ShortPress) => {
+                calibration.step = S::MeasuringNoise(strong);
+                send_command(command, OutputCommand::MeasureRssi);
+            }
+
+            // return back to the home screen, leaving the meter as it was
+            (_, I::LongPress | I::VeryLongPress) => {
+                self.cancel_calibration(command);
+                self.screen = Screen::Home;
+            }
+
+            // ignore all other user inputs
+            (_, I::ScrollDown | I::ScrollUp | I::ShortPress | I::DoublePress | I::Hold) => (),
+
+            // events from radio are processed before reaching the screen
+            _ => unreachable!(),
+        }
+    }
+
+    /// Takes the RSSI measured for the current step, after the noise floor
+    /// the new range is stored and the meter uses it from then on.
+    pub fn calibration_measured(&mut self, rssi: u8, command: &Sender<OutputCommand>) {
+        let Some(calibration) = self.calibration.as_mut() else {
+            return;
+        };
+
+        match calibration.step {
+            CalibrationStep::MeasuringStrong => calibration.step = CalibrationStep::Noise(rssi),
+            CalibrationStep::MeasuringNoise(strong)
+                if strong < rssi.saturating_add(MIN_METER_SPAN) =>
+            {
+                // the station was not strong enough, or the channel not empty
+                calibration.step = CalibrationStep::Strong;
+                self.notify("Too close, again");
+            }
+            CalibrationStep::MeasuringNoise(strong) => {
+                self.meter_range = (rssi, strong);
+                self.pending_writes
+                    .stage(METER_RANGE_KEY, vec![rssi, strong]);
+                self.cancel_calibration(command);
+                self.screen = Screen::Settings;
+                self.notify(format!("Meter {rssi}-{strong}"));
+            }
+            // a measurement, which was not asked for by this calibration
+            CalibrationStep::Strong | CalibrationStep::Noise(_) => (),
+        }
+    }
+
+    /// Returns how full the signal meter is, from 0 at the noise floor
+    /// to 1 at the strong station of the calibration.
+    pub fn meter_fraction(&self) -> f32 {
+        let (floor, ceiling) = self.meter_range;
+        let span = ceiling.saturating_sub(floor).max(1);
+        self.rssi.saturating_sub(floor).min(span) as f32 / span as f32
+    }
+}
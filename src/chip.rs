@@ -0,0 +1,488 @@
+use embedded_hal::i2c::{ErrorType, I2c, Operation};
+use esp_idf_svc::sys::{i2c_set_timeout, ESP_OK};
+use rda5807m::{Address, Rda5708m};
+use std::{cell::RefCell, fmt::Debug, rc::Rc, thread, time::Duration};
+
+use crate::{
+    frequency::{Band, Spacing},
+    Deemphasis,
+};
+
+/// Status flags of the tuner chip, which the tuner thread uses.
+pub struct ChipStatus {
+    /// Seek or tune is complete
+    pub stc: bool,
+    /// Seek failed to find a station
+    pub sf: bool,
+    /// Stereo pilot is received
+    pub st: bool,
+    /// Decoder is synchronized to the RDS stream
+    pub rdss: bool,
+}
+
+/// Operations of the tuner chip used by the tuner thread,
+/// so that the thread can also be driven by a scripted fake.
+pub trait TunerChip {
+    type Error: Debug;
+
+    fn start(&mut self) -> Result<(), Self::Error>;
+    fn set_seek_threshold(&mut self, threshold: u8) -> Result<(), Self::Error>;
+    fn set_frequency(&mut self, freq: u32) -> Result<(), Self::Error>;
+    fn get_frequency(&mut self) -> Result<u32, Self::Error>;
+    fn set_volume(&mut self, volume: u8) -> Result<(), Self::Error>;
+    fn seek_up(&mut self, wrap: bool) -> Result<(), Self::Error>;
+    fn seek_down(&mut self, wrap: bool) -> Result<(), Self::Error>;
+    /// Stops the running seek and tunes the given frequency instead.
+    fn stop_seek(&mut self, freq: u32) -> Result<(), Self::Error>;
+    fn enable_rds(&mut self) -> Result<(), Self::Error>;
+    fn disable_rds(&mut self) -> Result<(), Self::Error>;
+    /// Forces mono output, or allows stereo again.
+    fn set_mono(&mut self, mono: bool) -> Result<(), Self::Error>;
+    /// Sets the band, which frequencies are tuned in from the next tune on.
+    fn set_band(&mut self, band: Band) -> Result<(), Self::Error>;
+    /// Sets the channel grid, which frequencies are rounded to from the next tune on.
+    fn set_spacing(&mut self, spacing: Spacing) -> Result<(), Self::Error>;
+    fn set_deemphasis(&mut self, deemphasis: Deemphasis) -> Result<(), Self::Error>;
+    fn get_status(&mut self) -> Result<ChipStatus, Self::Error>;
+    fn get_rssi(&mut self) -> Result<u8, Self::Error>;
+    fn get_rds_registers(&mut self) -> Result<[u16; 4], Self::Error>;
+    fn get_block_errors(&mut self) -> Result<(u8, u8), Self::Error>;
+
+    /// Returns whether the bus was reconfigured after a failure since the last call,
+    /// the chip may have then missed a write, so its state should be read back.
+    fn take_bus_recovered(&mut self) -> bool {
+        false
+    }
+}
+
+/// I2C address, at which the RDA5807M gives random access to its registers,
+/// the chip answers there regardless of the address, which the driver is set to use
+const RANDOM_ACCESS_ADDRESS: u8 = 0x11;
+
+/// Control register of the RDA5807M
+const CONTROL_REGISTER: u8 = 0x02;
+
+/// Bit of the control register, which forces mono output
+const MONO_BIT: u16 = 1 << 13;
+
+/// Register of the RDA5807M with the tuned channel, the band and the channel spacing
+const CHANNEL_REGISTER: u8 = 0x03;
+
+/// Bit of the channel register, which starts tuning the channel
+const TUNE_BIT: u16 = 1 << 4;
+
+/// Bits of the channel register, which select the band and the channel spacing
+const BAND_PLAN_BITS: u16 = 0b1111;
+
+/// Register of the RDA5807M with the de-emphasis bit
+const DEEMPHASIS_REGISTER: u8 = 0x04;
+
+/// Bit of the de-emphasis register, which selects 50 us instead of 75 us
+const DEEMPHASIS_BIT: u16 = 1 << 11;
+
+/// Status register of the RDA5807M, which holds the tuned channel
+const READ_CHANNEL_REGISTER: u8 = 0x0A;
+
+/// Bits of the status register with the tuned channel
+const READ_CHANNEL_BITS: u16 = 0x3FF;
+
+/// I2C bus shared by the tuner driver and the register accesses, which the driver
+/// does not expose, both on the tuner thread.
+pub struct SharedBus<I2C>(Rc<RefCell<I2C>>);
+
+impl<I2C> Clone for SharedBus<I2C> {
+    fn clone(&self) -> Self {
+        SharedBus(self.0.clone())
+    }
+}
+
+impl<I2C: I2c> ErrorType for SharedBus<I2C> {
+    type Error = I2C::Error;
+}
+
+impl<I2C: I2c> I2c for SharedBus<I2C> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.0.borrow_mut().transaction(address, operations)
+    }
+
+    fn write(&mut self, address: u8, write: &[u8]) -> Result<(), Self::Error> {
+        self.0.borrow_mut().write(address, write)
+    }
+
+    fn read(&mut self, address: u8, read: &mut [u8]) -> Result<(), Self::Error> {
+        self.0.borrow_mut().read(address, read)
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.0.borrow_mut().write_read(address, write, read)
+    }
+}
+
+/// Errors of the RDA5807M, of its driver, or of a register written past the driver.
+#[derive(Debug)]
+pub enum Rda5807Error<E> {
+    Driver(rda5807m::Error<E>),
+    Bus(E),
+}
+
+/// RDA5807M behind its driver, with the mono bit, the band, the channel spacing
+/// and the de-emphasis, which the driver does not expose, written directly to the registers.
+///
+/// The driver writes the registers from its own copy, in which these bits are at their
+/// defaults, so they are written again after every write of the driver. Channels are
+/// tuned and read back past the driver as well, so that they are counted in the set band.
+pub struct Rda5807<I2C> {
+    driver: Rda5708m<SharedBus<I2C>>,
+    bus: SharedBus<I2C>,
+    /// whether mono output is forced
+    mono: bool,
+    band: Band,
+    spacing: Spacing,
+    deemphasis: Deemphasis,
+    /// whether the mono bit is to be written, once the running seek completes,
+    /// the control register holds the seek bit until then
+    mono_after_seek: bool,
+}
+
+impl<I2C: I2c> Rda5807<I2C> {
+    /// Creates the driver on the bus at the given address.
+    pub fn new(i2c: I2C, address: Address) -> Self {
+        let bus = SharedBus(Rc::new(RefCell::new(i2c)));
+        Rda5807 {
+            driver: Rda5708m::new(bus.clone(), address),
+            bus,
+            mono: false,
+            band: Band::Wide,
+            spacing: Spacing::Khz100,
+            deemphasis: Deemphasis::Us75,
+            mono_after_seek: false,
+        }
+    }
+
+    fn read_register(&mut self, register: u8) -> Result<u16, Rda5807Error<I2C::Error>> {
+        let mut value = [0; 2];
+        self.bus
+            .0
+            .borrow_mut()
+            .write_read(RANDOM_ACCESS_ADDRESS, &[register], &mut value)
+            .map_err(Rda5807Error::Bus)?;
+        Ok(u16::from_be_bytes(value))
+    }
+
+    fn write_register(&mut self, register: u8, value: u16) -> Result<(), Rda5807Error<I2C::Error>> {
+        let [high, low] = value.to_be_bytes();
+        self.bus
+            .0
+            .borrow_mut()
+            .write(RANDOM_ACCESS_ADDRESS, &[register, high, low])
+            .map_err(Rda5807Error::Bus)
+    }
+
+    /// Replaces the masked bits of the register, keeping its other bits.
+    fn update_register(
+        &mut self,
+        register: u8,
+        mask: u16,
+        bits: u16,
+    ) -> Result<(), Rda5807Error<I2C::Error>> {
+        let value = self.read_register(register)?;
+        self.write_register(register, value & !mask | bits & mask)
+    }
+
+    /// Sets or clears the mono bit in the control register, keeping its other bits.
+    fn write_mono_bit(&mut self) -> Result<(), Rda5807Error<I2C::Error>> {
+        let bits = if self.mono { MONO_BIT } else { 0 };
+        self.update_register(CONTROL_REGISTER, MONO_BIT, bits)
+    }
+
+    /// Writes the band and the channel spacing, without tuning the channel again.
+    fn write_band_plan(&mut self) -> Result<(), Rda5807Error<I2C::Error>> {
+        let bits = (self.band as u16) << 2 | self.spacing as u16;
+        self.update_register(CHANNEL_REGISTER, BAND_PLAN_BITS | TUNE_BIT, bits)
+    }
+
+    fn write_deemphasis_bit(&mut self) -> Result<(), Rda5807Error<I2C::Error>> {
+        let bits = match self.deemphasis {
+            Deemphasis::Us50 => DEEMPHASIS_BIT,
+            Deemphasis::Us75 => 0,
+        };
+        self.update_register(DEEMPHASIS_REGISTER, DEEMPHASIS_BIT, bits)
+    }
+
+    /// Runs a write of the driver, then writes the bits, which it does not know about, again.
+    fn driver_write(
+        &mut self,
+        write: impl FnOnce(&mut Rda5708m<SharedBus<I2C>>) -> Result<(), rda5807m::Error<I2C::Error>>,
+    ) -> Result<(), Rda5807Error<I2C::Error>> {
+        write(&mut self.driver).map_err(Rda5807Error::Driver)?;
+        self.mono_after_seek = false;
+        self.write_band_plan()?;
+        self.write_deemphasis_bit()?;
+        match self.mono {
+            true => self.write_mono_bit(),
+            false => Ok(()),
+        }
+    }
+
+    /// Starts a seek with the driver, a forced mono is written again, once it completes.
+    fn driver_seek(
+        &mut self,
+        seek: impl FnOnce(&mut Rda5708m<SharedBus<I2C>>) -> Result<(), rda5807m::Error<I2C::Error>>,
+    ) -> Result<(), Rda5807Error<I2C::Error>> {
+        seek(&mut self.driver).map_err(Rda5807Error::Driver)?;
+        self.mono_after_seek = self.mono;
+        Ok(())
+    }
+}
+
+impl<I2C: I2c> TunerChip for Rda5807<I2C> {
+    type Error = Rda5807Error<I2C::Error>;
+
+    fn start(&mut self) -> Result<(), Self::Error> {
+        self.driver_write(|driver| driver.start())
+    }
+
+    fn set_seek_threshold(&mut self, threshold: u8) -> Result<(), Self::Error> {
+        self.driver_write(|driver| driver.set_seek_threshold(threshold))
+    }
+
+    fn set_frequency(&mut self, freq: u32) -> Result<(), Self::Error> {
+        let (start, end) = self.band.range_khz();
+        let spacing = self.spacing.khz();
+        let channel = (freq.clamp(start, end) - start + spacing / 2) / spacing;
+
+        let bits = (self.band as u16) << 2 | self.spacing as u16;
+        self.write_register(CHANNEL_REGISTER, (channel as u16) << 6 | TUNE_BIT | bits)
+    }
+
+    fn get_frequency(&mut self) -> Result<u32, Self::Error> {
+        let channel = (self.read_register(READ_CHANNEL_REGISTER)? & READ_CHANNEL_BITS) as u32;
+        Ok(self.band.range_khz().0 + channel * self.spacing.khz())
+    }
+
+    fn set_volume(&mut self, volume: u8) -> Result<(), Self::Error> {
+        self.driver_write(|driver| driver.set_volume(volume))
+    }
+
+    fn seek_up(&mut self, wrap: bool) -> Result<(), Self::Error> {
+        self.driver_seek(|driver| driver.seek_up(wrap))
+    }
+
+    fn seek_down(&mut self, wrap: bool) -> Result<(), Self::Error> {
+        self.driver_seek(|driver| driver.seek_down(wrap))
+    }
+
+    fn stop_seek(&mut self, freq: u32) -> Result<(), Self::Error> {
+        // the driver does not expose the seek bit, its tune writes
+        // the control registers anew, which ends the seek,
+        // then the channel is tuned again in the set band
+        self.driver_write(|driver| driver.set_frequency(freq))?;
+        self.set_frequency(freq)
+    }
+
+    fn enable_rds(&mut self) -> Result<(), Self::Error> {
+        self.driver_write(|driver| driver.enable_rds())
+    }
+
+    fn disable_rds(&mut self) -> Result<(), Self::Error> {
+        self.driver_write(|driver| driver.disable_rds())
+    }
+
+    fn set_mono(&mut self, mono: bool) -> Result<(), Self::Error> {
+        self.mono = mono;
+        self.write_mono_bit()
+    }
+
+    fn set_band(&mut self, band: Band) -> Result<(), Self::Error> {
+        self.band = band;
+        self.write_band_plan()
+    }
+
+    fn set_spacing(&mut self, spacing: Spacing) -> Result<(), Self::Error> {
+        self.spacing = spacing;
+        self.write_band_plan()
+    }
+
+    fn set_deemphasis(&mut self, deemphasis: Deemphasis) -> Result<(), Self::Error> {
+        self.deemphasis = deemphasis;
+        self.write_deemphasis_bit()
+    }
+
+    fn get_status(&mut self) -> Result<ChipStatus, Self::Error> {
+        let status = self.driver.get_status().map_err(Rda5807Error::Driver)?;
+        if status.stc && self.mono_after_seek {
+            self.mono_after_seek = false;
+            self.write_mono_bit()?;
+        }
+
+        Ok(ChipStatus {
+            stc: status.stc,
+            sf: status.sf,
+            st: status.st,
+            rdss: status.rdss,
+        })
+    }
+
+    fn get_rssi(&mut self) -> Result<u8, Self::Error> {
+        self.driver.get_rssi().map_err(Rda5807Error::Driver)
+    }
+
+    fn get_rds_registers(&mut self) -> Result<[u16; 4], Self::Error> {
+        self.driver
+            .get_rds_registers()
+            .map_err(Rda5807Error::Driver)
+    }
+
+    fn get_block_errors(&mut self) -> Result<(u8, u8), Self::Error> {
+        self.driver.get_block_errors().map_err(Rda5807Error::Driver)
+    }
+}
+
+/// Longest timeout of a single I2C transaction supported by the ESP32 I2C peripheral
+pub const MAX_I2C_TIMEOUT: Duration = Duration::from_micros(13_000);
+
+/// Tuner chip on a bus with a hardware timeout, which is raised when transactions fail.
+///
+/// Every failed operation is retried once, after doubling the timeout if it is not yet
+/// at `MAX_I2C_TIMEOUT`, so that modules on long wires, which stretch the clock,
+/// keep working, and sporadic failures do not stop the radio.
+pub struct AdaptiveTimeout<C> {
+    chip: C,
+    /// number of the I2C port, which the chip is connected to
+    port: i32,
+    timeout: Duration,
+    /// whether the timeout was raised, since `take_bus_recovered` was last called
+    recovered: bool,
+}
+
+impl<C: TunerChip> AdaptiveTimeout<C> {
+    /// Wraps the chip on the I2C port, which is configured with the given timeout.
+    pub fn new(chip: C, port: i32, timeout: Duration) -> Self {
+        AdaptiveTimeout {
+            chip,
+            port,
+            timeout,
+            recovered: false,
+        }
+    }
+
+    /// Runs the operation, on failure raises the timeout and runs it once more.
+    fn retry<T>(
+        &mut self,
+        mut operation: impl FnMut(&mut C) -> Result<T, C::Error>,
+    ) -> Result<T, C::Error> {
+        let err = match operation(&mut self.chip) {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+
+        if self.timeout < MAX_I2C_TIMEOUT {
+            let timeout = (self.timeout * 2).min(MAX_I2C_TIMEOUT);
+
+            // the timeout is given in cycles of the 80 MHz APB clock
+            let cycles = (timeout.as_nanos() * 2 / 25) as i32;
+            if unsafe { i2c_set_timeout(self.port, cycles) } == ESP_OK {
+                log::warn!(
+                    "tuner: I2C failed ({err:?}), timeout raised to {} us",
+                    timeout.as_micros()
+                );
+                self.timeout = timeout;
+                self.recovered = true;
+            }
+        } else {
+            log::warn!("tuner: I2C failed ({err:?}), retrying");
+        }
+
+        thread::sleep(Duration::from_millis(1));
+        operation(&mut self.chip)
+    }
+}
+
+impl<C: TunerChip> TunerChip for AdaptiveTimeout<C> {
+    type Error = C::Error;
+
+    fn start(&mut self) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.start())
+    }
+
+    fn set_seek_threshold(&mut self, threshold: u8) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.set_seek_threshold(threshold))
+    }
+
+    fn set_frequency(&mut self, freq: u32) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.set_frequency(freq))
+    }
+
+    fn get_frequency(&mut self) -> Result<u32, Self::Error> {
+        self.retry(|chip| chip.get_frequency())
+    }
+
+    fn set_volume(&mut self, volume: u8) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.set_volume(volume))
+    }
+
+    fn seek_up(&mut self, wrap: bool) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.seek_up(wrap))
+    }
+
+    fn seek_down(&mut self, wrap: bool) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.seek_down(wrap))
+    }
+
+    fn stop_seek(&mut self, freq: u32) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.stop_seek(freq))
+    }
+
+    fn enable_rds(&mut self) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.enable_rds())
+    }
+
+    fn disable_rds(&mut self) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.disable_rds())
+    }
+
+    fn set_mono(&mut self, mono: bool) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.set_mono(mono))
+    }
+
+    fn set_band(&mut self, band: Band) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.set_band(band))
+    }
+
+    fn set_spacing(&mut self, spacing: Spacing) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.set_spacing(spacing))
+    }
+
+    fn set_deemphasis(&mut self, deemphasis: Deemphasis) -> Result<(), Self::Error> {
+        self.retry(|chip| chip.set_deemphasis(deemphasis))
+    }
+
+    fn get_status(&mut self) -> Result<ChipStatus, Self::Error> {
+        self.retry(|chip| chip.get_status())
+    }
+
+    fn get_rssi(&mut self) -> Result<u8, Self::Error> {
+        self.retry(|chip| chip.get_rssi())
+    }
+
+    fn get_rds_registers(&mut self) -> Result<[u16; 4], Self::Error> {
+        self.retry(|chip| chip.get_rds_registers())
+    }
+
+    fn get_block_errors(&mut self) -> Result<(u8, u8), Self::Error> {
+        self.retry(|chip| chip.get_block_errors())
+    }
+
+    fn take_bus_recovered(&mut self) -> bool {
+        std::mem::take(&mut self.recovered)
+    }
+}
@@ -0,0 +1,85 @@
+use esp_idf_svc::hal::gpio::PinDriver;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        OnceLock,
+    },
+    thread::{self, Thread},
+    time::Duration,
+};
+
+use crate::{error::Error, pins::output_pin};
+
+/// Selectable lengths of the confirmation of a landed seek, zero turns it off
+pub const SEEK_CONFIRM_DURATIONS: [Duration; 4] = [
+    Duration::ZERO,
+    Duration::from_millis(50),
+    Duration::from_millis(150),
+    Duration::from_millis(300),
+];
+
+/// Length of the confirmation in milliseconds, set from settings by the event loop
+static CONFIRM_MS: AtomicU32 = AtomicU32::new(0);
+
+/// Set on a landed seek, until the LED thread shows the confirmation
+static LED_CONFIRM_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Set on a landed seek, until the confirmation thread pulses the pin
+static PIN_CONFIRM_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Thread driving the confirmation pin, woken up for every confirmation
+static CONFIRM_THREAD: OnceLock<Thread> = OnceLock::new();
+
+/// Changes the length of the confirmation, a zero duration turns it off.
+pub fn set_seek_confirm(duration: Duration) {
+    CONFIRM_MS.store(duration.as_millis() as u32, Ordering::Relaxed);
+}
+
+fn confirm_duration() -> Duration {
+    Duration::from_millis(CONFIRM_MS.load(Ordering::Relaxed) as u64)
+}
+
+/// Confirms, that a seek landed on a station, by a flash of the status LED
+/// and a pulse on the confirmation pin, unless confirmations are off.
+pub fn confirm_seek() {
+    if confirm_duration().is_zero() {
+        return;
+    }
+
+    LED_CONFIRM_PENDING.store(true, Ordering::Relaxed);
+    if let Some(thread) = CONFIRM_THREAD.get() {
+        PIN_CONFIRM_PENDING.store(true, Ordering::Relaxed);
+        thread.unpark();
+    }
+}
+
+/// Returns the length of a pending LED flash, called by the LED thread between patterns.
+pub fn take_led_confirm() -> Option<Duration> {
+    LED_CONFIRM_PENDING
+        .swap(false, Ordering::Relaxed)
+        .then(confirm_duration)
+}
+
+/// Spawns a new thread, which drives the pin with the given number high
+/// for the length of each confirmation, e.g. for a buzzer.
+pub fn spawn_confirm_thread(pin: i32) -> Result<(), Error> {
+    let mut output = PinDriver::output(output_pin(pin)).map_err(Error::Gpio)?;
+    output.set_low().map_err(Error::Gpio)?;
+
+    let handle = thread::spawn(move || loop {
+        // parking may also return spuriously
+        thread::park();
+        if !PIN_CONFIRM_PENDING.swap(false, Ordering::Relaxed) {
+            continue;
+        }
+
+        let duration = confirm_duration();
+        if !duration.is_zero() {
+            output.set_high().unwrap();
+            thread::sleep(duration);
+            output.set_low().unwrap();
+        }
+    });
+    CONFIRM_THREAD.set(handle.thread().clone()).unwrap();
+    Ok(())
+}
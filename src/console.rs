@@ -0,0 +1,206 @@
+use std::{
+    io::Read,
+    sync::mpsc::{channel, Sender},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    frequency::Frequency,
+    replay::parse_event_log,
+    snapshot::current_snapshot,
+    trace::dump_events,
+    tuner::request,
+    verbosity::{set_event_verbosity, Verbosity},
+    InputEvent, OutputCommand,
+};
+
+/// Whether commands for probing the tuner, which bypass the normal limits of the GUI,
+/// are available, only debug builds have them, so that normal operation stays grid-aligned
+const DEBUG_COMMANDS: bool = cfg!(debug_assertions);
+
+/// Spawns a new thread, which reads commands from the serial console line by line,
+/// executes them and prints their results.
+pub fn spawn_console(command_sender: Sender<OutputCommand>, event_sender: Sender<InputEvent>) {
+    thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut line = Vec::new();
+        let mut byte = [0];
+
+        // events pasted after `replay`, collected until `end`
+        let mut replay_log: Option<String> = None;
+
+        loop {
+            // stdin of ESP-IDF does not block, so it is polled
+            match stdin.read(&mut byte) {
+                Ok(1) => match byte[0] {
+                    b'\r' | b'\n' => {
+                        if !line.is_empty() {
+                            let command = String::from_utf8_lossy(&line).into_owned();
+                            match (replay_log.as_mut(), command.trim()) {
+                                (Some(_), "end") => {
+                                    let log = replay_log.take().unwrap();
+                                    println!("{}", replay(&log, &event_sender));
+                                }
+                                (Some(log), _) => {
+                                    log.push_str(&command);
+                                    log.push('\n');
+                                }
+                                (None, "replay") if DEBUG_COMMANDS => {
+                                    replay_log = Some(String::new());
+                                    println!("paste the events, one per line, then end");
+                                }
+                                (None, _) => {
+                                    println!(
+                                        "{}",
+                                        execute(&command, &command_sender, &event_sender)
+                                    )
+                                }
+                            }
+                            line.clear();
+                        }
+                    }
+                    byte => line.push(byte),
+                },
+                _ => thread::sleep(Duration::from_millis(20)),
+            }
+        }
+    });
+}
+
+/// Sends the events of a pasted log to the event loop, which replays them with `replay_events`,
+/// so that a reported glitch can be reproduced on the device, returns the text to be printed.
+///
+/// The log is in the format of `events dump`, without the times.
+fn replay(log: &str, event_sender: &Sender<InputEvent>) -> String {
+    let events = match parse_event_log(log) {
+        Ok(events) => events,
+        Err(line) => return format!("line {line} is not an event, nothing replayed"),
+    };
+
+    let count = events.len();
+    match event_sender.send(InputEvent::Replay(events)) {
+        Ok(()) => format!("replayed {count} events"),
+        Err(_) => "event loop is not running".to_string(),
+    }
+}
+
+/// Executes a single console command, returns the text to be printed.
+fn execute(
+    line: &str,
+    command_sender: &Sender<OutputCommand>,
+    event_sender: &Sender<InputEvent>,
+) -> String {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+
+    match words.as_slice() {
+        ["help"] => {
+            let mut help = "help - list commands".to_string();
+            help += "\nrds dump on|off - log every received RDS group";
+            help += "\nstatus - print the current state of the radio";
+            help += "\nevents off|info|debug - log user inputs, or all events";
+            help += "\nevents dump - print the last events, with seconds since boot";
+            help += "\nresync - read the tuner state back into the UI";
+            if DEBUG_COMMANDS {
+                help += "\ntune <kHz> - tune an exact frequency, off the grid and band";
+                help += "\ntune ch<N> - tune a channel of the grid";
+                help += "\nreplay - process the pasted events, one per line, until end";
+            }
+            help
+        }
+
+        ["tune", freq] if DEBUG_COMMANDS => {
+            let parsed = match freq.strip_prefix("ch") {
+                Some(channel) => channel.parse().map(Frequency::from_channel),
+                None => freq.parse().map(Frequency::from_khz),
+            };
+            let Ok(freq) = parsed else {
+                return format!("invalid frequency: {freq}");
+            };
+
+            if let Err(err) = request(command_sender, OutputCommand::SetFrequency(freq)) {
+                return format!("tune failed: {err}");
+            }
+
+            // the chip rounds the frequency to its channel spacing
+            let (reply, actual) = channel();
+            command_sender
+                .send(OutputCommand::GetFrequency(reply))
+                .unwrap();
+            match actual.recv() {
+                Ok(actual) => {
+                    event_sender
+                        .send(InputEvent::ChangeFrequency(actual))
+                        .unwrap();
+                    format!(
+                        "requested {} kHz, tuner reports {} kHz",
+                        freq.as_khz(),
+                        actual.as_khz()
+                    )
+                }
+                Err(_) => "tuner thread is not running".to_string(),
+            }
+        }
+
+        ["rds", "dump", state @ ("on" | "off")] => {
+            let enabled = *state == "on";
+            match request(command_sender, OutputCommand::SetRdsDump(enabled)) {
+                Ok(()) => format!("RDS dump {state}"),
+                Err(err) => format!("RDS dump failed: {err}"),
+            }
+        }
+
+        ["events", level @ ("off" | "info" | "debug")] => {
+            set_event_verbosity(match *level {
+                "off" => Verbosity::Off,
+                "info" => Verbosity::Info,
+                _ => Verbosity::Debug,
+            });
+            format!("event log {level}")
+        }
+
+        ["events", "dump"] => match dump_events() {
+            trace if trace.is_empty() => "no events yet".to_string(),
+            trace => trace,
+        },
+
+        ["resync"] => match request(command_sender, OutputCommand::Resync) {
+            Ok(()) => "resynced with the tuner".to_string(),
+            Err(err) => format!("resync failed: {err}"),
+        },
+
+        ["status"] => {
+            let Some(state) = current_snapshot() else {
+                return "state not published yet".to_string();
+            };
+
+            let mut status = format!(
+                "{:.1} MHz, RSSI {}, volume {}, screen {:?}",
+                state.frequency.as_mhz(),
+                state.rssi,
+                state.volume,
+                state.screen,
+            );
+            for (flag, name) in [
+                (state.stereo, "stereo"),
+                (state.signal_lost, "signal lost"),
+                (state.seeking, "seeking"),
+                (state.locked, "locked"),
+            ] {
+                if flag {
+                    status += &format!(", {name}");
+                }
+            }
+            if let Some(remaining) = state.sleep_remaining {
+                status += &format!(", sleep in {} s", remaining.as_secs());
+            }
+            status += &format!(
+                "\nname \"{}\", info \"{}\"",
+                state.station_name, state.station_info
+            );
+            status
+        }
+
+        _ => format!("unknown command: {line}, try help"),
+    }
+}
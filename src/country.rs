@@ -0,0 +1,108 @@
+/// Countries of the European Broadcasting Area (IEC 62106, annex D), one row for each
+/// Extended Country Code from 0xE0, indexed by the country code 1-F of the PI code
+/// minus one, empty where no country is assigned
+const COUNTRIES: [[&str; 15]; 5] = [
+    [
+        "DE", "DZ", "AD", "IL", "IT", "BE", "RU", "PS", "AL", "AT", "HU", "MT", "DE", "", "EG",
+    ],
+    [
+        "GR", "CY", "SM", "CH", "JO", "FI", "LU", "BG", "DK", "GI", "IQ", "GB", "LY", "RO", "FR",
+    ],
+    [
+        "MA", "CZ", "PL", "VA", "SK", "SY", "TN", "", "LI", "IS", "MC", "LT", "RS", "ES", "NO",
+    ],
+    [
+        "ME", "IE", "TR", "MK", "", "", "", "NL", "LV", "LB", "AZ", "HR", "KZ", "SE", "BY",
+    ],
+    [
+        "MD", "EE", "KG", "", "", "UA", "XK", "PT", "SI", "AM", "UZ", "GE", "", "TM", "BA",
+    ],
+];
+
+/// Extended Country Code of the first row of `COUNTRIES`
+const FIRST_ECC: u8 = 0xE0;
+
+/// Number of candidate countries shown, while the Extended Country Code is not known
+const MAX_CANDIDATES: usize = 3;
+
+/// Returns the countries, which use the country code of the PI code, all of them
+/// when the Extended Country Code is not known, otherwise at most one.
+fn countries(pi: u16, ecc: Option<u8>) -> Vec<&'static str> {
+    let code = (pi >> 12) as usize;
+    if code == 0 {
+        return Vec::new();
+    }
+
+    let rows = match ecc {
+        Some(ecc) => match ecc.checked_sub(FIRST_ECC) {
+            Some(row) => COUNTRIES.get(row as usize..=row as usize).unwrap_or(&[]),
+            None => &[],
+        },
+        None => &COUNTRIES[..],
+    };
+
+    let mut countries: Vec<_> = rows
+        .iter()
+        .map(|row| row[code - 1])
+        .filter(|country| !country.is_empty())
+        .collect();
+    countries.dedup();
+    countries
+}
+
+/// Returns the country of the station with the PI code, e.g. `CZ`, or `?` when it is unknown.
+///
+/// Without the Extended Country Code, the country code alone is shared by several
+/// countries, the first few of them are listed, e.g. `DE/GR/MA+`.
+pub fn pi_country(pi: u16, ecc: Option<u8>) -> String {
+    let countries = countries(pi, ecc);
+    match countries.len() {
+        0 => "?".to_string(),
+        count if count > MAX_CANDIDATES => countries[..MAX_CANDIDATES].join("/") + "+",
+        _ => countries.join("/"),
+    }
+}
+
+/// Returns the area covered by the station with the PI code, e.g. `national`.
+pub fn pi_coverage(pi: u16) -> String {
+    match (pi >> 8) & 0xF {
+        0 => "local".to_string(),
+        1 => "international".to_string(),
+        2 => "national".to_string(),
+        3 => "supra-regional".to_string(),
+        region => format!("regional {}", region - 3),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extended_country_code_selects_country() {
+        assert_eq!(pi_country(0x2204, Some(0xE2)), "CZ");
+        assert_eq!(pi_country(0xC201, Some(0xE1)), "GB");
+    }
+
+    #[test]
+    fn candidates_are_listed_without_extended_country_code() {
+        assert_eq!(pi_country(0x1000, None), "DE/GR/MA+");
+        assert_eq!(pi_country(0x2204, None), "DZ/CY/CZ+");
+    }
+
+    #[test]
+    fn unknown_codes_give_question_mark() {
+        assert_eq!(pi_country(0x0204, None), "?");
+        assert_eq!(pi_country(0x2204, Some(0xA0)), "?");
+        assert_eq!(pi_country(0x2204, Some(0xF0)), "?");
+        // no country is assigned to this code in the row
+        assert_eq!(pi_country(0xE000, Some(0xE0)), "?");
+    }
+
+    #[test]
+    fn coverage_is_decoded() {
+        assert_eq!(pi_coverage(0x2004), "local");
+        assert_eq!(pi_coverage(0x2204), "national");
+        assert_eq!(pi_coverage(0x2504), "regional 2");
+    }
+}
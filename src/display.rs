@@ -6,20 +6,53 @@ use esp_idf_svc::hal::{
 };
 use ssd1306::{
     mode::{BufferedGraphicsMode, DisplayConfig},
-    prelude::{DisplayRotation, SPIInterface, WriteOnlyDataCommand},
+    prelude::{Brightness, DisplayRotation, SPIInterface, WriteOnlyDataCommand},
     size::DisplaySize128x64,
     Ssd1306,
 };
 
+use crate::error::Error;
+
+/// How many times the display reset and initialization is attempted
+const DISPLAY_INIT_ATTEMPTS: u32 = 3;
+
+/// Selectable brightness levels of the display, from the dimmest
+pub const BRIGHTNESS_LEVELS: [Brightness; 5] = [
+    Brightness::DIMMEST,
+    Brightness::DIM,
+    Brightness::NORMAL,
+    Brightness::BRIGHT,
+    Brightness::BRIGHTEST,
+];
+
 /// Setup the SSD1306 display connected through SPI and clear the screen.
+///
+/// The brightness is set right after initialization, before anything is shown,
+/// so that the screen does not flash at the default brightness.
+///
+/// If the display fails to initialize after several attempts, the error is logged
+/// and `None` is returned, so that the radio can continue without a display.
+/// Note that the display interface is write-only, so only errors of the bus
+/// itself can be detected. Failing to set up the bus and pins is returned as an error.
+#[allow(clippy::type_complexity)] // the interface type cannot be named outside of this function
 pub fn setup_display(
     spi: SPI3,
     sclk: impl InputPin + OutputPin,
     sdo: impl InputPin + OutputPin,
     dc: impl OutputPin,
     reset: impl OutputPin,
-) -> Box<
-    Ssd1306<impl WriteOnlyDataCommand, DisplaySize128x64, BufferedGraphicsMode<DisplaySize128x64>>,
+    brightness: Brightness,
+) -> Result<
+    Option<
+        Box<
+            Ssd1306<
+                impl WriteOnlyDataCommand,
+                DisplaySize128x64,
+                BufferedGraphicsMode<DisplaySize128x64>,
+            >,
+        >,
+    >,
+    Error,
 > {
     let spi_driver = SpiDriver::new(
         spi,
@@ -28,12 +61,13 @@ pub fn setup_display(
         None as Option<Gpio0>,
         &SpiDriverConfig::default(),
     )
-    .unwrap();
+    .map_err(Error::Spi)?;
 
     let spi_device_driver =
-        SpiDeviceDriver::new(spi_driver, None as Option<Gpio0>, &SpiConfig::default()).unwrap();
+        SpiDeviceDriver::new(spi_driver, None as Option<Gpio0>, &SpiConfig::default())
+            .map_err(Error::Spi)?;
 
-    let data_command = PinDriver::output(dc).unwrap();
+    let data_command = PinDriver::output(dc).map_err(Error::Gpio)?;
 
     let interface = SPIInterface::new(spi_device_driver, data_command);
 
@@ -44,19 +78,42 @@ pub fn setup_display(
             .into_buffered_graphics_mode(),
     );
 
-    let mut display_reset = PinDriver::output(reset).unwrap();
+    let mut display_reset = PinDriver::output(reset).map_err(Error::Gpio)?;
+
+    // some panels do not come up on a flaky power-up,
+    // so the whole reset sequence is retried a few times
+    let mut initialized = false;
+    for attempt in 1..=DISPLAY_INIT_ATTEMPTS {
+        // display must be reset before initialization
+        display_reset.set_low().map_err(Error::Gpio)?;
+        std::thread::sleep(Duration::from_millis(100));
+        display_reset.set_high().map_err(Error::Gpio)?;
 
-    // display must be reset before initialization
-    display_reset.set_low().unwrap();
-    std::thread::sleep(Duration::from_millis(100));
-    display_reset.set_high().unwrap();
+        let result = display
+            .init()
+            .and_then(|_| display.set_brightness(brightness))
+            .and_then(|_| display.flush());
+        match result {
+            Ok(()) => {
+                log::info!("boot: display initialized ({attempt}/{DISPLAY_INIT_ATTEMPTS})");
+                initialized = true;
+                break;
+            }
+            Err(err) => log::warn!(
+                "Display initialization failed ({attempt}/{DISPLAY_INIT_ATTEMPTS}): {}",
+                Error::display(err)
+            ),
+        }
+    }
 
     // pin must not be dropped, the pin will otherwise be set
     // back to output => floating, and the display will reset itself
     std::mem::forget(display_reset);
 
-    display.init().unwrap();
-    display.flush().unwrap();
+    if !initialized {
+        log::error!("Display could not be initialized, running without display");
+        return Ok(None);
+    }
 
-    display
+    Ok(Some(display))
 }
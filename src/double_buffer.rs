@@ -0,0 +1,129 @@
+use esp_idf_svc::{
+    nvs::{EspNvs, NvsDefault},
+    sys::EspError,
+};
+
+/// Longest value, which can be kept in two copies, the copies add a sequence number
+/// and a checksum to it
+const MAX_VALUE_LEN: usize = 8;
+
+/// Length of the sequence number in front of the value
+const SEQUENCE_LEN: usize = 4;
+
+/// Returns the CRC-8 of the bytes, with the polynomial x^8 + x^2 + x + 1.
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ byte, |crc, _| match crc & 0x80 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x07,
+        })
+    })
+}
+
+/// Reads the copy under the key, returns its sequence number and value,
+/// if the copy is complete and its checksum matches.
+fn read_copy(nvs: &EspNvs<NvsDefault>, key: &str) -> Option<(u32, Vec<u8>)> {
+    let mut buffer = [0; SEQUENCE_LEN + MAX_VALUE_LEN + 1];
+    let copy = nvs.get_raw(key, &mut buffer).ok()??;
+    let (&crc, data) = copy.split_last()?;
+    if data.len() < SEQUENCE_LEN || crc8(data) != crc {
+        return None;
+    }
+
+    let (sequence, value) = data.split_at(SEQUENCE_LEN);
+    let sequence = u32::from_le_bytes([sequence[0], sequence[1], sequence[2], sequence[3]]);
+    Some((sequence, value.to_vec()))
+}
+
+/// Returns the index of the copy with the newest valid value, with the copy.
+fn newest_copy(nvs: &EspNvs<NvsDefault>, keys: [&str; 2]) -> Option<(usize, (u32, Vec<u8>))> {
+    match (read_copy(nvs, keys[0]), read_copy(nvs, keys[1])) {
+        // the sequence numbers are compared so that they may wrap around
+        (Some(a), Some(b)) if (b.0.wrapping_sub(a.0) as i32) > 0 => Some((1, b)),
+        (Some(a), _) => Some((0, a)),
+        (None, Some(b)) => Some((1, b)),
+        (None, None) => None,
+    }
+}
+
+/// Reads the value kept in two copies under the keys, the newer one of the copies,
+/// which are intact, so that a write interrupted by a power loss still leaves
+/// the value written before it.
+pub fn read_double_buffered(nvs: &EspNvs<NvsDefault>, keys: [&str; 2]) -> Option<Vec<u8>> {
+    newest_copy(nvs, keys).map(|(_, (_, value))| value)
+}
+
+/// Writes the value over the older of its two copies, with the next sequence number.
+pub fn write_double_buffered(
+    nvs: &mut EspNvs<NvsDefault>,
+    keys: [&str; 2],
+    value: &[u8],
+) -> Result<(), EspError> {
+    let (key, sequence) = match newest_copy(nvs, keys) {
+        Some((newest, (sequence, _))) => (keys[1 - newest], sequence.wrapping_add(1)),
+        None => (keys[0], 0),
+    };
+
+    let mut copy = sequence.to_le_bytes().to_vec();
+    copy.extend_from_slice(value);
+    copy.push(crc8(&copy));
+    nvs.set_raw(key, &copy).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use esp_idf_svc::nvs::EspDefaultNvsPartition;
+
+    use super::*;
+
+    const KEYS: [&str; 2] = ["test_a", "test_b"];
+
+    fn nvs() -> EspNvs<NvsDefault> {
+        EspNvs::new(EspDefaultNvsPartition::take().unwrap(), "test", true).unwrap()
+    }
+
+    #[test]
+    fn crc8_matches_reference() {
+        // check value of CRC-8/SMBUS
+        assert_eq!(crc8(b"123456789"), 0xF4);
+        assert_eq!(crc8(&[]), 0);
+    }
+
+    #[test]
+    fn newest_value_is_read() {
+        let mut nvs = nvs();
+        assert_eq!(read_double_buffered(&nvs, KEYS), None);
+
+        for value in 0..5u8 {
+            write_double_buffered(&mut nvs, KEYS, &[value; 3]).unwrap();
+            assert_eq!(read_double_buffered(&nvs, KEYS), Some(vec![value; 3]));
+        }
+    }
+
+    #[test]
+    fn corrupted_copy_leaves_previous_value() {
+        let mut nvs = nvs();
+        write_double_buffered(&mut nvs, KEYS, &[1]).unwrap();
+        write_double_buffered(&mut nvs, KEYS, &[2]).unwrap();
+
+        // the newer copy is damaged, as if the write was interrupted
+        let mut buffer = [0; 16];
+        let mut copy = nvs.get_raw(KEYS[1], &mut buffer).unwrap().unwrap().to_vec();
+        copy[SEQUENCE_LEN] ^= 0xFF;
+        nvs.set_raw(KEYS[1], &copy).unwrap();
+
+        assert_eq!(read_double_buffered(&nvs, KEYS), Some(vec![1]));
+    }
+
+    #[test]
+    fn sequence_number_wraps_around() {
+        let mut nvs = nvs();
+        let mut copy = u32::MAX.to_le_bytes().to_vec();
+        copy.push(7);
+        copy.push(crc8(&copy));
+        nvs.set_raw(KEYS[0], &copy).unwrap();
+
+        write_double_buffered(&mut nvs, KEYS, &[8]).unwrap();
+        assert_eq!(read_double_buffered(&nvs, KEYS), Some(vec![8]));
+    }
+}
@@ -0,0 +1,79 @@
+use esp_idf_svc::sys::EspError;
+use std::{
+    fmt::{self, Debug, Display},
+    sync::mpsc::{RecvError, SendError},
+};
+
+/// Errors of the peripherals and threads of the radio.
+///
+/// Drivers of the tuner and the display are generic over their bus,
+/// so their errors are kept only as the text of their debug output.
+#[derive(Debug)]
+pub enum Error {
+    /// I2C bus to the tuner, or the tuner itself
+    I2c(String),
+    /// No device answered at the configured I2C address of the tuner, it is likely not wired
+    TunerNotFound(String),
+    /// SPI bus to the display
+    Spi(EspError),
+    /// Nonvolatile storage
+    Nvs(EspError),
+    /// Driving or reading a GPIO pin
+    Gpio(EspError),
+    /// Display driver
+    Display(String),
+    /// The thread at the other end of a channel stopped
+    Channel,
+    /// A setting of the firmware is out of the range, which the hardware supports
+    InvalidConfig(String),
+    /// Any other ESP-IDF service, e.g. taking the peripherals
+    Esp(EspError),
+}
+
+impl Error {
+    /// Wraps an error of the tuner driver.
+    pub fn i2c(err: impl Debug) -> Self {
+        Error::I2c(format!("{err:?}"))
+    }
+
+    /// Wraps an error of the display driver.
+    pub fn display(err: impl Debug) -> Self {
+        Error::Display(format!("{err:?}"))
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::I2c(err) => write!(f, "tuner I2C error: {err}"),
+            Error::TunerNotFound(address) => write!(f, "tuner not found at {address}"),
+            Error::Spi(err) => write!(f, "display SPI error: {err}"),
+            Error::Nvs(err) => write!(f, "NVS error: {err}"),
+            Error::Gpio(err) => write!(f, "GPIO error: {err}"),
+            Error::Display(err) => write!(f, "display error: {err}"),
+            Error::Channel => write!(f, "thread at the other end of a channel stopped"),
+            Error::InvalidConfig(err) => write!(f, "invalid configuration: {err}"),
+            Error::Esp(err) => write!(f, "ESP-IDF error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<EspError> for Error {
+    fn from(err: EspError) -> Self {
+        Error::Esp(err)
+    }
+}
+
+impl<T> From<SendError<T>> for Error {
+    fn from(_: SendError<T>) -> Self {
+        Error::Channel
+    }
+}
+
+impl From<RecvError> for Error {
+    fn from(_: RecvError) -> Self {
+        Error::Channel
+    }
+}
@@ -0,0 +1,242 @@
+use std::{collections::VecDeque, convert::Infallible};
+
+use crate::{
+    chip::{ChipStatus, TunerChip},
+    frequency::{Band, Spacing},
+    Deemphasis,
+};
+
+/// Number of status reads a seek takes by default, before it sets the STC flag
+const DEFAULT_SEEK_POLLS: u32 = 3;
+
+/// Seek started on the fake chip, which has not completed yet.
+struct PendingSeek {
+    /// Frequency, at which the seek stops
+    target: u32,
+    /// Whether the seek ends without finding a station
+    fails: bool,
+    /// Number of status reads left, until the seek completes
+    polls_left: u32,
+}
+
+/// Tuner chip, which answers with scripted values instead of talking to hardware.
+///
+/// Seeks sweep towards the next of the given stations and complete after `seek_polls`
+/// status reads, as the chip clears the STC flag until then, a tune completes immediately.
+/// The RSSI and RDS groups are returned in the scripted order, the last RSSI repeats.
+pub struct FakeChip {
+    /// Frequencies of stations, which seeks stop on, in increasing order
+    pub stations: Vec<u32>,
+    /// Scripted RSSI readings
+    pub rssi: VecDeque<u8>,
+    /// Scripted RDS groups, RDS is synchronized while there are some left
+    pub rds_groups: VecDeque<[u16; 4]>,
+    /// Whether a stereo pilot is reported
+    pub stereo: bool,
+    /// Number of status reads a seek takes
+    pub seek_polls: u32,
+
+    pub frequency: u32,
+    pub volume: u8,
+    /// Every volume written, in order
+    pub volume_writes: Vec<u8>,
+    pub seek_threshold: u8,
+    pub rds_enabled: bool,
+    pub mono: bool,
+    /// Band, whose ends failed seeks stop at
+    pub band: Band,
+    /// Channel grid, which seeks sweep through
+    pub spacing: Spacing,
+    pub deemphasis: Deemphasis,
+    /// Whether the last seek found no station
+    pub seek_failed: bool,
+    /// Number of seeks started
+    pub seeks: u32,
+
+    seek: Option<PendingSeek>,
+}
+
+impl FakeChip {
+    pub fn new(stations: Vec<u32>) -> Self {
+        FakeChip {
+            stations,
+            rssi: VecDeque::new(),
+            rds_groups: VecDeque::new(),
+            stereo: false,
+            seek_polls: DEFAULT_SEEK_POLLS,
+            frequency: Band::Wide.range_khz().0,
+            volume: 0,
+            volume_writes: Vec::new(),
+            seek_threshold: 0,
+            rds_enabled: false,
+            mono: false,
+            band: Band::Wide,
+            spacing: Spacing::Khz100,
+            deemphasis: Deemphasis::Us75,
+            seek_failed: false,
+            seeks: 0,
+            seek: None,
+        }
+    }
+
+    /// Whether a seek is running
+    pub fn is_seeking(&self) -> bool {
+        self.seek.is_some()
+    }
+
+    /// Starts a seek towards the next station in the given direction,
+    /// wrapping around the band if allowed.
+    fn seek(&mut self, up: bool, wrap: bool) {
+        let next = if up {
+            let above = self.stations.iter().find(|&&freq| freq > self.frequency);
+            above.or(self.stations.first().filter(|_| wrap))
+        } else {
+            let below = self
+                .stations
+                .iter()
+                .rev()
+                .find(|&&freq| freq < self.frequency);
+            below.or(self.stations.last().filter(|_| wrap))
+        };
+
+        let (start, end) = self.band.range_khz();
+        let target = match (next, up) {
+            (Some(&freq), _) => freq,
+            // a failed seek stops at the band end, unless it wrapped around
+            (None, true) if !wrap => end,
+            (None, false) if !wrap => start,
+            (None, _) => self.frequency,
+        };
+
+        self.seeks += 1;
+        self.seek_failed = false;
+        self.seek = Some(PendingSeek {
+            target,
+            fails: next.is_none(),
+            polls_left: self.seek_polls.max(1),
+        });
+    }
+
+    /// Moves the running seek one status read further, the frequency sweeps
+    /// through the channels towards the target, until the seek completes.
+    fn advance_seek(&mut self) {
+        let Some(seek) = self.seek.as_mut() else {
+            return;
+        };
+
+        seek.polls_left -= 1;
+        if seek.polls_left == 0 {
+            self.frequency = seek.target;
+            self.seek_failed = seek.fails;
+            self.seek = None;
+            return;
+        }
+
+        let step = (seek.target as i64 - self.frequency as i64) / (seek.polls_left as i64 + 1);
+        let spacing = self.spacing.khz() as i64;
+        self.frequency = (self.frequency as i64 + step / spacing * spacing) as u32;
+    }
+}
+
+impl TunerChip for FakeChip {
+    type Error = Infallible;
+
+    fn start(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_seek_threshold(&mut self, threshold: u8) -> Result<(), Self::Error> {
+        self.seek_threshold = threshold;
+        Ok(())
+    }
+
+    /// Tunes the frequency, which stops a running seek.
+    fn set_frequency(&mut self, freq: u32) -> Result<(), Self::Error> {
+        self.seek = None;
+        self.frequency = freq;
+        self.seek_failed = false;
+        Ok(())
+    }
+
+    fn get_frequency(&mut self) -> Result<u32, Self::Error> {
+        Ok(self.frequency)
+    }
+
+    fn set_volume(&mut self, volume: u8) -> Result<(), Self::Error> {
+        self.volume = volume;
+        self.volume_writes.push(volume);
+        Ok(())
+    }
+
+    fn seek_up(&mut self, wrap: bool) -> Result<(), Self::Error> {
+        self.seek(true, wrap);
+        Ok(())
+    }
+
+    fn seek_down(&mut self, wrap: bool) -> Result<(), Self::Error> {
+        self.seek(false, wrap);
+        Ok(())
+    }
+
+    fn stop_seek(&mut self, freq: u32) -> Result<(), Self::Error> {
+        self.set_frequency(freq)
+    }
+
+    fn enable_rds(&mut self) -> Result<(), Self::Error> {
+        self.rds_enabled = true;
+        Ok(())
+    }
+
+    fn disable_rds(&mut self) -> Result<(), Self::Error> {
+        self.rds_enabled = false;
+        Ok(())
+    }
+
+    fn set_mono(&mut self, mono: bool) -> Result<(), Self::Error> {
+        self.mono = mono;
+        Ok(())
+    }
+
+    fn set_band(&mut self, band: Band) -> Result<(), Self::Error> {
+        self.band = band;
+        Ok(())
+    }
+
+    fn set_spacing(&mut self, spacing: Spacing) -> Result<(), Self::Error> {
+        self.spacing = spacing;
+        Ok(())
+    }
+
+    fn set_deemphasis(&mut self, deemphasis: Deemphasis) -> Result<(), Self::Error> {
+        self.deemphasis = deemphasis;
+        Ok(())
+    }
+
+    /// Reports the seek as complete only once it has taken its status reads.
+    fn get_status(&mut self) -> Result<ChipStatus, Self::Error> {
+        self.advance_seek();
+        Ok(ChipStatus {
+            stc: self.seek.is_none(),
+            sf: self.seek_failed,
+            st: self.stereo && !self.mono,
+            rdss: self.rds_enabled && !self.rds_groups.is_empty(),
+        })
+    }
+
+    fn get_rssi(&mut self) -> Result<u8, Self::Error> {
+        let rssi = match self.rssi.len() {
+            0 => 0,
+            1 => self.rssi[0],
+            _ => self.rssi.pop_front().unwrap(),
+        };
+        Ok(rssi)
+    }
+
+    fn get_rds_registers(&mut self) -> Result<[u16; 4], Self::Error> {
+        Ok(self.rds_groups.pop_front().unwrap_or_default())
+    }
+
+    fn get_block_errors(&mut self) -> Result<(u8, u8), Self::Error> {
+        Ok((0, 0))
+    }
+}
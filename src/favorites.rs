@@ -0,0 +1,91 @@
+use esp_idf_svc::nvs::{EspNvs, NvsDefault};
+use std::sync::mpsc::Sender;
+
+use crate::{
+    frequency::Frequency,
+    storage::{save_frequencies, FAVORITES_KEY, MAX_STORED_FREQUENCIES},
+    AppState, InputEvent, OutputCommand, Screen,
+};
+
+impl AppState {
+    /// Whether the currently tuned station is a favorite.
+    pub fn is_favorite(&self) -> bool {
+        self.favorites.binary_search(&self.frequency).is_ok()
+    }
+
+    /// Adds the frequency to favorites, keeping them sorted.
+    fn add_favorite(&mut self, freq: Frequency, nvs: &mut EspNvs<NvsDefault>) {
+        if self.favorites.len() >= MAX_STORED_FREQUENCIES {
+            self.notify("Favorites full");
+            return;
+        }
+
+        if let Err(index) = self.favorites.binary_search(&freq) {
+            self.favorites.insert(index, freq);
+            if let Err(err) = save_frequencies(nvs, FAVORITES_KEY, &self.favorites) {
+                self.favorites.remove(index);
+                self.save_failed(err);
+            }
+        }
+    }
+
+    /// Removes the frequency from favorites.
+    fn remove_favorite(&mut self, freq: Frequency, nvs: &mut EspNvs<NvsDefault>) {
+        if let Ok(index) = self.favorites.binary_search(&freq) {
+            self.favorites.remove(index);
+            if let Err(err) = save_frequencies(nvs, FAVORITES_KEY, &self.favorites) {
+                self.favorites.insert(index, freq);
+                self.save_failed(err);
+            }
+        }
+    }
+
+    /// Adds the currently tuned station to favorites, or removes it if it already is one.
+    pub fn toggle_favorite(&mut self, nvs: &mut EspNvs<NvsDefault>) {
+        if self.is_favorite() {
+            self.remove_favorite(self.frequency, nvs);
+        } else {
+            self.add_favorite(self.frequency, nvs);
+        }
+    }
+
+    /// Processes user input on the favorites screen.
+    pub fn process_favorites_input(
+        &mut self,
+        event: InputEvent,
+        command: &Sender<OutputCommand>,
+        nvs: &mut EspNvs<NvsDefault>,
+    ) {
+        let count = self.favorites.len();
+
+        use InputEvent as I;
+        match event {
+            // scrolling through favorites in a loop
+            I::ScrollDown if count > 0 => {
+                self.favorites_cursor = (self.favorites_cursor + count - 1) % count
+            }
+            I::ScrollUp if count > 0 => self.favorites_cursor = (self.favorites_cursor + 1) % count,
+
+            // tune the selected favorite and return to the home screen
+            I::ShortPress if count > 0 => {
+                self.tune_station(self.favorites[self.favorites_cursor], command);
+                self.screen = Screen::Home;
+            }
+
+            // remove the selected favorite
+            I::DoublePress if count > 0 => {
+                self.remove_favorite(self.favorites[self.favorites_cursor], nvs);
+                self.favorites_cursor = self.favorites_cursor.min(count.saturating_sub(2));
+            }
+
+            // return back to the home screen
+            I::LongPress | I::VeryLongPress => self.screen = Screen::Home,
+
+            // ignore all other user inputs, there is nothing to scroll through
+            I::ScrollDown | I::ScrollUp | I::ShortPress | I::DoublePress | I::Hold => (),
+
+            // events from radio are processed before reaching the screen
+            _ => unreachable!(),
+        }
+    }
+}
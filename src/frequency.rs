@@ -0,0 +1,224 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Lowest frequency, which any band of the tuner covers
+pub const BAND_START_KHZ: u32 = 76_000;
+/// Highest frequency, which any band of the tuner covers
+pub const BAND_END_KHZ: u32 = 108_000;
+
+/// Bands of the tuner, numbered as in the BAND bits of the RDA5807M.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Band {
+    /// 87 to 108 MHz, used in most of the world
+    Western = 0,
+    /// 76 to 91 MHz, used in Japan
+    Japan = 1,
+    /// 76 to 108 MHz, covers both of the others
+    Wide = 2,
+}
+
+impl Band {
+    /// lowest and highest frequency of the band, in kHz
+    pub const fn range_khz(self) -> (u32, u32) {
+        match self {
+            Band::Western => (87_000, BAND_END_KHZ),
+            Band::Japan => (BAND_START_KHZ, 91_000),
+            Band::Wide => (BAND_START_KHZ, BAND_END_KHZ),
+        }
+    }
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Band::Western),
+            1 => Some(Band::Japan),
+            2 => Some(Band::Wide),
+            _ => None,
+        }
+    }
+}
+
+/// Spacings of the channel grid, to which the tuner rounds every frequency,
+/// numbered as in the SPACE bits of the RDA5807M.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spacing {
+    Khz100 = 0,
+    Khz200 = 1,
+    Khz50 = 2,
+}
+
+impl Spacing {
+    pub const fn khz(self) -> u32 {
+        match self {
+            Spacing::Khz100 => 100,
+            Spacing::Khz200 => 200,
+            Spacing::Khz50 => 50,
+        }
+    }
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Spacing::Khz100),
+            1 => Some(Spacing::Khz200),
+            2 => Some(Spacing::Khz50),
+            _ => None,
+        }
+    }
+}
+
+// channel numbers count from the band start, so both ends of every band must lie on
+// every grid, the widest spacing is a multiple of the others
+const _: () = {
+    let bands = [Band::Western, Band::Japan, Band::Wide];
+    let mut index = 0;
+    while index < bands.len() {
+        let (start, end) = bands[index].range_khz();
+        assert!((end - start) % Spacing::Khz200.khz() == 0);
+        index += 1;
+    }
+};
+
+/// Band, to which all frequencies are limited, shared by the event loop and the tuner thread
+static BAND: AtomicU8 = AtomicU8::new(Band::Wide as u8);
+
+/// Spacing of the channel grid of the band
+static SPACING: AtomicU8 = AtomicU8::new(Spacing::Khz100 as u8);
+
+/// Changes the band and the channel grid, which frequencies are limited and rounded to.
+pub fn set_band_plan(band: Band, spacing: Spacing) {
+    BAND.store(band as u8, Ordering::Relaxed);
+    SPACING.store(spacing as u8, Ordering::Relaxed);
+}
+
+/// the band, which frequencies are limited to
+pub fn band() -> Band {
+    Band::from_u8(BAND.load(Ordering::Relaxed)).unwrap_or(Band::Wide)
+}
+
+/// spacing of the channel grid in kHz, the step of manual tuning
+pub fn channel_spacing_khz() -> u32 {
+    Spacing::from_u8(SPACING.load(Ordering::Relaxed))
+        .unwrap_or(Spacing::Khz100)
+        .khz()
+}
+
+/// Frequency of a station, kept in kHz, the unit used by the tuner.
+///
+/// Only the tuner thread and storage deal with raw numbers, everything else
+/// goes through this type, so that kHz are never confused with MHz
+/// and stepping through the band never leaves it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Frequency(u32);
+
+impl Frequency {
+    pub const fn from_khz(khz: u32) -> Self {
+        Frequency(khz)
+    }
+
+    pub const fn as_khz(self) -> u32 {
+        self.0
+    }
+
+    /// Lower end of the band
+    pub fn band_start() -> Self {
+        Frequency(band().range_khz().0)
+    }
+
+    /// Upper end of the band
+    pub fn band_end() -> Self {
+        Frequency(band().range_khz().1)
+    }
+
+    /// frequency in MHz, as shown to the user
+    pub fn as_mhz(self) -> f32 {
+        self.0 as f32 / 1000.
+    }
+
+    /// Returns the frequency of the channel with the given number, counted from 0
+    /// at the band start, channels past the band end give the band end.
+    pub fn from_channel(channel: u32) -> Self {
+        let start = Self::band_start().0;
+        Frequency(start.saturating_add(channel.saturating_mul(channel_spacing_khz()))).clamped()
+    }
+
+    /// Returns the number of the nearest channel, the same number the tuner uses for it.
+    pub fn channel(self) -> u32 {
+        let spacing = channel_spacing_khz();
+        (self.clamped().0 - Self::band_start().0 + spacing / 2) / spacing
+    }
+
+    /// Whether any band of the tuner covers the frequency, stored frequencies
+    /// are kept, when the user switches to a narrower band.
+    pub fn is_supported(self) -> bool {
+        (BAND_START_KHZ..=BAND_END_KHZ).contains(&self.0)
+    }
+
+    /// the nearest frequency within the band
+    pub fn clamped(self) -> Self {
+        self.clamp(Self::band_start(), Self::band_end())
+    }
+
+    /// Steps up by the given number of kHz, stopping at the band end.
+    pub fn stepped_up(self, step_khz: u32) -> Self {
+        Frequency(self.0.saturating_add(step_khz)).clamped()
+    }
+
+    /// Steps down by the given number of kHz, stopping at the band start.
+    pub fn stepped_down(self, step_khz: u32) -> Self {
+        Frequency(self.0.saturating_sub(step_khz)).clamped()
+    }
+
+    /// Returns the position of the frequency in the band as a fraction of `width`,
+    /// frequencies outside of the band are placed at its ends.
+    pub fn band_position(self, width: u32) -> u32 {
+        let (start, end) = band().range_khz();
+        let offset = self.clamped().0 - start;
+        (offset as u64 * width as u64 / (end - start) as u64) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channels_round_trip() {
+        let last_channel = (BAND_END_KHZ - BAND_START_KHZ) / channel_spacing_khz();
+        for channel in 0..=last_channel {
+            assert_eq!(Frequency::from_channel(channel).channel(), channel);
+        }
+        assert_eq!(Frequency::from_channel(u32::MAX), Frequency::band_end());
+    }
+
+    #[test]
+    fn channel_rounds_to_nearest() {
+        assert_eq!(Frequency::from_khz(88_049).channel(), 120);
+        assert_eq!(Frequency::from_khz(88_050).channel(), 121);
+        assert_eq!(Frequency::from_khz(0).channel(), 0);
+    }
+
+    #[test]
+    fn steps_stay_in_band() {
+        assert_eq!(Frequency::band_end().stepped_up(100), Frequency::band_end());
+        assert_eq!(
+            Frequency::band_start().stepped_down(100),
+            Frequency::band_start()
+        );
+        assert_eq!(
+            Frequency::from_khz(100_000).stepped_up(channel_spacing_khz()),
+            Frequency::from_khz(100_100)
+        );
+        assert_eq!(
+            Frequency::from_khz(u32::MAX).stepped_up(1),
+            Frequency::band_end()
+        );
+        assert!(!Frequency::from_khz(70_000).is_supported());
+        assert!(Frequency::from_khz(70_000).clamped().is_supported());
+    }
+
+    #[test]
+    fn band_position_spans_width() {
+        assert_eq!(Frequency::band_start().band_position(128), 0);
+        assert_eq!(Frequency::band_end().band_position(128), 128);
+        assert_eq!(Frequency::from_khz(92_000).band_position(128), 64);
+        assert_eq!(Frequency::from_khz(200_000).band_position(128), 128);
+    }
+}
@@ -1,30 +1,441 @@
 use embedded_graphics::{
     mono_font::MonoTextStyle,
     pixelcolor::BinaryColor,
-    prelude::{Angle, DrawTarget, Point, Primitive, Size, Transform},
+    prelude::{Angle, Dimensions, DrawTarget, Point, Primitive, Size, Transform},
     primitives::{
         Arc, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, RoundedRectangle, StrokeAlignment,
         StyledDrawable, Triangle,
     },
-    text::Text,
-    Drawable,
+    text::{Alignment, Text},
+    Drawable, Pixel,
 };
 use ssd1306::{
-    mode::BufferedGraphicsMode, prelude::WriteOnlyDataCommand, size::DisplaySize, Ssd1306,
+    mode::BufferedGraphicsMode,
+    prelude::{Brightness, WriteOnlyDataCommand},
+    size::DisplaySize,
+    Ssd1306,
 };
 
-use crate::{AppState, UIElement, NUM_PRESETS};
+use crate::{
+    about::MemoryStats,
+    country::{pi_country, pi_coverage},
+    frequency::Frequency,
+    rds_stats::{rds_stats, RDS_STATS_ROWS},
+    AppState, Direction, HomeLayout, Readout, Screen, Setting, SignalMeter, StereoMode, UIElement,
+    NUM_PRESETS,
+};
+
+/// RSSI, at which a bar of the band activity is full
+const MAX_METER_RSSI: u32 = 60;
+
+/// Number of characters of a preset name, which fit into its box
+const PRESET_LABEL_LEN: usize = 3;
+
+/// Formats the frequency in MHz, or as a channel number, to a fixed width,
+/// frequencies below 100 MHz get a leading space, so that the digits do not move
+/// when tuning across it, there is only room for one of them
+fn frequency_label(freq: Frequency, readout: Readout) -> String {
+    match readout {
+        Readout::Mhz | Readout::Both => format!("{:>5.1}", freq.as_mhz()),
+        Readout::Channel => format!("ch{:>3}", freq.channel()),
+    }
+}
+
+pub type Display<DI, SIZE> = Ssd1306<DI, SIZE, BufferedGraphicsMode<SIZE>>;
+
+/// Display, into which the event loop draws the GUI, a frame is shown once it is flushed.
+pub trait Panel: DrawTarget<Color = BinaryColor> {
+    /// Sends the drawn frame to the display.
+    fn flush(&mut self) -> Result<(), Self::Error>;
+
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<(), Self::Error>;
+
+    fn set_display_on(&mut self, on: bool) -> Result<(), Self::Error>;
+}
+
+impl<DI: WriteOnlyDataCommand, SIZE: DisplaySize> Panel for Display<DI, SIZE> {
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ssd1306::flush(self)
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<(), Self::Error> {
+        Ssd1306::set_brightness(self, brightness)
+    }
+
+    fn set_display_on(&mut self, on: bool) -> Result<(), Self::Error> {
+        Ssd1306::set_display_on(self, on)
+    }
+}
+
+/// Draw target, which draws everything twice as large onto the wrapped one
+struct DoubleSize<'a, D>(&'a mut D);
+
+impl<D: DrawTarget> Dimensions for DoubleSize<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        let bounding_box = self.0.bounding_box();
+        Rectangle::new(
+            Point::new(bounding_box.top_left.x / 2, bounding_box.top_left.y / 2),
+            Size::new(bounding_box.size.width / 2, bounding_box.size.height / 2),
+        )
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for DoubleSize<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.0
+            .draw_iter(pixels.into_iter().flat_map(|Pixel(point, color)| {
+                [(0, 0), (1, 0), (0, 1), (1, 1)]
+                    .map(|(dx, dy)| Pixel(Point::new(point.x * 2 + dx, point.y * 2 + dy), color))
+            }))
+    }
+}
+
+/// Draw a message in a box over the middle of the screen
+fn draw_notification<D: DrawTarget<Color = BinaryColor>>(
+    message: &str,
+    display: &mut D,
+) -> Result<(), D::Error> {
+    let box_style = PrimitiveStyleBuilder::new()
+        .stroke_width(1)
+        .stroke_color(BinaryColor::On)
+        .fill_color(BinaryColor::Off)
+        .build();
+    let text_style = MonoTextStyle::new(
+        &embedded_graphics::mono_font::iso_8859_2::FONT_6X9,
+        BinaryColor::On,
+    );
+
+    RoundedRectangle::with_equal_corners(
+        Rectangle::new(Point::new(4, 21), Size::new(120, 22)),
+        Size::new(3, 3),
+    )
+    .draw_styled(&box_style, display)?;
+
+    Text::with_alignment(message, Point::new(64, 35), text_style, Alignment::Center)
+        .draw(display)?;
+
+    Ok(())
+}
+
+/// Draw the help over the whole screen, a box with one gesture on each line
+fn draw_help<D: DrawTarget<Color = BinaryColor>>(
+    lines: &[&str],
+    display: &mut D,
+) -> Result<(), D::Error> {
+    let box_style = PrimitiveStyleBuilder::new()
+        .stroke_width(1)
+        .stroke_color(BinaryColor::On)
+        .fill_color(BinaryColor::Off)
+        .build();
+    let text_style = MonoTextStyle::new(
+        &embedded_graphics::mono_font::iso_8859_2::FONT_6X9,
+        BinaryColor::On,
+    );
+
+    RoundedRectangle::with_equal_corners(
+        Rectangle::new(Point::new(2, 2), Size::new(124, 60)),
+        Size::new(3, 3),
+    )
+    .draw_styled(&box_style, display)?;
+
+    // lines are centered vertically, up to four fit
+    let top = 32 - lines.len() as i32 * 11 / 2 + 8;
+    for (index, line) in lines.iter().enumerate() {
+        Text::new(line, Point::new(6, top + index as i32 * 11), text_style).draw(display)?;
+    }
+
+    Ok(())
+}
+
+/// Draw the minutes left on the sleep timer into the bottom right corner, inverted,
+/// so that it stays readable over any screen
+fn draw_sleep_countdown<D: DrawTarget<Color = BinaryColor>>(
+    countdown: &str,
+    display: &mut D,
+) -> Result<(), D::Error> {
+    let text_style = MonoTextStyle::new(
+        &embedded_graphics::mono_font::iso_8859_2::FONT_4X6,
+        BinaryColor::Off,
+    );
+    let width = countdown.len() as u32 * 4 + 3;
+
+    Rectangle::new(Point::new(128 - width as i32, 56), Size::new(width, 8))
+        .draw_styled(&PrimitiveStyle::with_fill(BinaryColor::On), display)?;
+    Text::with_alignment(countdown, Point::new(126, 62), text_style, Alignment::Right)
+        .draw(display)?;
+
+    Ok(())
+}
+
+/// Draw a titled list of rows with a label on the left and a value on the right,
+/// the list is scrolled so that the row with the cursor is always visible
+fn draw_list<D: DrawTarget<Color = BinaryColor>>(
+    title: &str,
+    rows: &[(String, String)],
+    cursor: usize,
+    display: &mut D,
+) -> Result<(), D::Error> {
+    // number of rows which fit on the screen below the title
+    const VISIBLE_ROWS: usize = 4;
+
+    let stroke_style = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+    let text_style = MonoTextStyle::new(
+        &embedded_graphics::mono_font::iso_8859_2::FONT_6X9,
+        BinaryColor::On,
+    );
+
+    Text::new(title, Point::new(2, 9), text_style).draw(display)?;
+
+    let first = cursor
+        .min(rows.len().saturating_sub(1))
+        .saturating_sub(VISIBLE_ROWS - 1);
+
+    for (row, (index, (label, value))) in rows
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(VISIBLE_ROWS)
+        .enumerate()
+    {
+        let y = 24 + row as i32 * 12;
+
+        if index == cursor {
+            RoundedRectangle::with_equal_corners(
+                Rectangle::new(Point::new(0, y - 9), Size::new(128, 12)),
+                Size::new(3, 3),
+            )
+            .draw_styled(&stroke_style, display)?;
+        }
+
+        Text::new(label, Point::new(3, y), text_style).draw(display)?;
+        Text::with_alignment(value, Point::new(125, y), text_style, Alignment::Right)
+            .draw(display)?;
+    }
+
+    Ok(())
+}
+
+/// Draw the current heap and task statistics
+fn draw_about<D: DrawTarget<Color = BinaryColor>>(
+    state: &AppState,
+    display: &mut D,
+) -> Result<(), D::Error> {
+    let stats = MemoryStats::read();
+    let audio = match (state.settings.stereo_mode, state.tuner_status.mono) {
+        (StereoMode::Auto, true) => "mono (auto)",
+        (StereoMode::Auto, false) => "stereo (auto)",
+        (_, true) => "mono",
+        (_, false) => "stereo",
+    };
+    let rows = [
+        ("Audio".to_string(), audio.to_string()),
+        ("Free heap".to_string(), format!("{} B", stats.free_heap)),
+        (
+            "Largest block".to_string(),
+            format!("{} B", stats.largest_free_block),
+        ),
+        (
+            "Min free heap".to_string(),
+            format!("{} B", stats.min_free_heap),
+        ),
+        ("Tasks".to_string(), stats.tasks.to_string()),
+    ];
+
+    draw_list("About", &rows, usize::MAX, display)
+}
+
+/// Draw the statistics of RDS reception on the tuned station
+fn draw_rds_stats<D: DrawTarget<Color = BinaryColor>>(
+    state: &AppState,
+    display: &mut D,
+) -> Result<(), D::Error> {
+    let stats = rds_stats();
+    let error_percent = match stats.blocks {
+        0 => 0,
+        blocks => stats.error_blocks * 100 / blocks,
+    };
+    let rows: [_; RDS_STATS_ROWS] = [
+        ("Blocks".to_string(), stats.blocks.to_string()),
+        (
+            "With errors".to_string(),
+            format!("{} ({error_percent}%)", stats.error_blocks),
+        ),
+        (
+            "Name".to_string(),
+            format!("{}%", stats.station_name_percent),
+        ),
+        (
+            "Last group".to_string(),
+            stats.last_group.unwrap_or_else(|| "-".to_string()),
+        ),
+        (
+            "PI".to_string(),
+            stats
+                .pi
+                .map_or_else(|| "-".to_string(), |pi| format!("{pi:04X}")),
+        ),
+        (
+            "Country".to_string(),
+            stats
+                .pi
+                .map_or_else(|| "-".to_string(), |pi| pi_country(pi, stats.ecc)),
+        ),
+        (
+            "Area".to_string(),
+            stats.pi.map_or_else(|| "-".to_string(), pi_coverage),
+        ),
+        ("Tuned".to_string(), state.tuned_label()),
+    ];
+
+    draw_list("RDS", &rows, state.rds_stats_cursor, display)
+}
+
+/// Draw an analog-style dial of the whole band between the tuner controls and presets,
+/// with a pointer at the tuned frequency, ticks at every 5 MHz, markers below the scale
+/// for presets and dots above it for stations found by the last scan
+fn draw_dial<D: DrawTarget<Color = BinaryColor>>(
+    state: &AppState,
+    display: &mut D,
+) -> Result<(), D::Error> {
+    // horizontal extent of the scale, left of the volume control
+    const LEFT: i32 = 2;
+    const WIDTH: i32 = 104;
+    // vertical position of the scale line
+    const SCALE_Y: i32 = 34;
 
-type Display<DI, SIZE> = Ssd1306<DI, SIZE, BufferedGraphicsMode<SIZE>>;
+    let fill_style = PrimitiveStyle::with_fill(BinaryColor::On);
+
+    // position of the frequency on the scale
+    let x = |freq: Frequency| LEFT + freq.band_position(WIDTH as u32) as i32;
+
+    Rectangle::new(Point::new(LEFT, SCALE_Y), Size::new(WIDTH as u32 + 1, 1))
+        .draw_styled(&fill_style, display)?;
+
+    let (start, end) = (
+        Frequency::band_start().as_khz(),
+        Frequency::band_end().as_khz(),
+    );
+    for freq in (start.next_multiple_of(5000)..=end).step_by(5000) {
+        Rectangle::new(
+            Point::new(x(Frequency::from_khz(freq)), SCALE_Y - 3),
+            Size::new(1, 3),
+        )
+        .draw_styled(&fill_style, display)?;
+    }
+
+    for &(freq, _) in &state.stations {
+        Rectangle::new(Point::new(x(freq), SCALE_Y - 6), Size::new(1, 2))
+            .draw_styled(&fill_style, display)?;
+    }
+
+    for freq in state.presets.iter().flatten() {
+        let x = x(*freq);
+        Triangle::new(
+            Point::new(x, SCALE_Y + 2),
+            Point::new(x - 2, SCALE_Y + 5),
+            Point::new(x + 2, SCALE_Y + 5),
+        )
+        .draw_styled(&fill_style, display)?;
+    }
+
+    // pointer, reaching over the whole dial
+    Rectangle::new(Point::new(x(state.frequency), 24), Size::new(1, 19))
+        .draw_styled(&fill_style, display)?;
+
+    Ok(())
+}
+
+/// Draw a full screen report with a title and rows of results
+pub fn draw_report<DI: WriteOnlyDataCommand, SIZE: DisplaySize>(
+    title: &str,
+    rows: &[(String, String)],
+    display: &mut Display<DI, SIZE>,
+) -> Result<(), <Display<DI, SIZE> as DrawTarget>::Error> {
+    display.clear(BinaryColor::Off)?;
+    draw_list(title, rows, usize::MAX, display)?;
+    display.flush()
+}
 
 impl AppState {
-    /// Redraw the whole GUI according to the current application state
-    pub fn update_ui<DI: WriteOnlyDataCommand, SIZE: DisplaySize>(
+    /// whether station info from RDS is shown, it is hidden on weak signals,
+    /// where it would likely be corrupt
+    pub fn show_station_info(&self) -> bool {
+        self.settings.rds_enabled && self.rssi >= self.settings.info_min_rssi
+    }
+
+    /// Formats the frequency for lists and notifications, in the readout picked by the user
+    pub fn frequency_text(&self, freq: Frequency) -> String {
+        match self.settings.readout {
+            Readout::Mhz => format!("{:.1}", freq.as_mhz()),
+            Readout::Channel => format!("ch{}", freq.channel()),
+            Readout::Both => format!("{:.1} ch{}", freq.as_mhz(), freq.channel()),
+        }
+    }
+
+    /// Redraw the whole GUI according to the current application state,
+    /// the caller sends the drawn frame to the display
+    pub fn update_ui<D: DrawTarget<Color = BinaryColor>>(
         &self,
-        display: &mut Display<DI, SIZE>,
-    ) -> Result<(), <Display<DI, SIZE> as DrawTarget>::Error> {
+        display: &mut D,
+    ) -> Result<(), D::Error> {
         display.clear(BinaryColor::Off)?;
 
+        match self.screen {
+            _ if self.screensaver => self.draw_screensaver(display)?,
+            Screen::Home
+                if self.settings.home_layout == HomeLayout::Minimal && self.controls_hidden =>
+            {
+                self.draw_minimal(display)?
+            }
+            Screen::Home => self.draw_home(display)?,
+            Screen::Settings => self.draw_settings(display)?,
+            Screen::Favorites => self.draw_favorites(display)?,
+            Screen::Recent => self.draw_recent(display)?,
+            Screen::About => draw_about(self, display)?,
+            Screen::RdsStats => draw_rds_stats(self, display)?,
+            Screen::TextHistory => self.draw_text_history(display)?,
+            Screen::Calibration => self.draw_calibration(display)?,
+            Screen::RemoteLearning => self.draw_remote_learning(display)?,
+            Screen::Spectrum => self.draw_spectrum(display)?,
+        }
+
+        if self.show_help && !self.screensaver {
+            draw_help(self.help_lines(), display)?;
+        }
+
+        // the last minute of the sleep timer and of the auto-off is shown
+        // as prominently as a notification
+        match (
+            &self.notification,
+            self.auto_off_countdown(),
+            self.sleep_countdown(),
+        ) {
+            _ if self.tuner_missing => draw_notification("Tuner not found", display)?,
+            (Some((message, _)), _, _) => draw_notification(message, display)?,
+            (None, Some(warning), _) => draw_notification(&warning, display)?,
+            (None, None, Some(countdown)) if self.sleep_warning() => {
+                draw_notification(&countdown, display)?
+            }
+            (None, None, Some(countdown)) if !self.screensaver => {
+                draw_sleep_countdown(&countdown, display)?
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Draw the home screen with tuner controls
+    fn draw_home<D: DrawTarget<Color = BinaryColor>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
         // style for unselected cursor
         let stroke_style = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
 
@@ -36,6 +447,7 @@ impl AppState {
             .build();
 
         let fill_style = PrimitiveStyle::with_fill(BinaryColor::On);
+        let inverted_fill_style = PrimitiveStyle::with_fill(BinaryColor::Off);
 
         // style for RDS text
         let text_style = MonoTextStyle::new(
@@ -49,6 +461,12 @@ impl AppState {
             BinaryColor::On,
         );
 
+        // style for preset names, three characters fit into a preset box
+        let small_text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::iso_8859_2::FONT_5X8,
+            BinaryColor::On,
+        );
+
         // arrows for seek buttons
         let left_arrow = Triangle::new(Point::new(0, 0), Point::new(5, -5), Point::new(5, 5))
             .into_styled(fill_style);
@@ -76,16 +494,37 @@ impl AppState {
             }
         };
 
+        // fills the seek button in the direction of manual tuning,
+        // returns the style in which the button's arrows should be drawn
+        let flash_box = |direction, x, display: &mut _| {
+            if self.tune_indicator == Some(direction) {
+                RoundedRectangle::with_equal_corners(
+                    Rectangle::new(Point::new(x, 0), Size::new(20, 20)),
+                    Size::new(3, 3),
+                )
+                .draw_styled(&fill_style, display)
+                .map(|_| inverted_fill_style)
+            } else {
+                Ok(fill_style)
+            }
+        };
+
         // -- Seek down button --
         selection_box(UIElement::SeekDown, 0, 0, 20, 20, display)?;
-        left_arrow.translate(Point::new(4, 9)).draw(display)?;
-        left_arrow.translate(Point::new(10, 9)).draw(display)?;
+        let arrow_style = flash_box(Direction::Down, 0, display)?;
+        left_arrow
+            .primitive
+            .translate(Point::new(4, 9))
+            .draw_styled(&arrow_style, display)?;
+        left_arrow
+            .primitive
+            .translate(Point::new(10, 9))
+            .draw_styled(&arrow_style, display)?;
 
         // -- Frequency setting --
         selection_box(UIElement::FreqControl, 25, 0, 60, 20, display)?;
-        let freq = self.freq_khz as f32 / 1000.;
         Text::new(
-            format!("{freq:.1}").as_str(),
+            &frequency_label(self.frequency, self.settings.readout),
             Point::new(33, 15),
             big_text_style,
         )
@@ -93,42 +532,108 @@ impl AppState {
 
         // -- Seek up button --
         selection_box(UIElement::SeekUp, 90, 0, 20, 20, display)?;
-        right_arrow.translate(Point::new(94, 9)).draw(display)?;
-        right_arrow.translate(Point::new(100, 9)).draw(display)?;
+        let arrow_style = flash_box(Direction::Up, 90, display)?;
+        right_arrow
+            .primitive
+            .translate(Point::new(94, 9))
+            .draw_styled(&arrow_style, display)?;
+        right_arrow
+            .primitive
+            .translate(Point::new(100, 9))
+            .draw_styled(&arrow_style, display)?;
 
         // -- Volume control --
         selection_box(UIElement::VolumeControl, 115, 0, 13, 40, display)?;
-        for level in 0..self.volume {
+        for level in 0..self.output_volume() {
             Rectangle::new(Point::new(117, 34 - level as i32 * 2), Size::new(9, 1))
                 .draw_styled(&fill_style, display)?;
         }
+        // dotted line above the highest allowed level
+        if self.settings.max_volume < 15 {
+            let y = 34 - self.settings.max_volume as i32 * 2;
+            for x in (117..126).step_by(2) {
+                Pixel(Point::new(x, y), BinaryColor::On).draw(display)?;
+            }
+        }
         // Level indicator
         selection_box(UIElement::VolumeControl, 109, 45, 19, 19, display)?;
         left_arrow.translate(Point::new(113, 54)).draw(display)?;
         Rectangle::new(Point::new(112, 52), Size::new(5, 5))
             .into_styled(fill_style)
             .draw(display)?;
-        for line in 1..=2 {
+        match self.settings.signal_meter {
+            SignalMeter::Off => {
+                for line in 1..=2 {
+                    Arc::with_center(
+                        Point::new(118, 53),
+                        line * 6,
+                        Angle::from_degrees(-60.),
+                        Angle::from_degrees(120.),
+                    )
+                    .draw_styled(&stroke_style, display)?;
+                }
+            }
+            SignalMeter::Bars => {
+                // three bars, each lit at a third of the calibrated range
+                for bar in 0..3 {
+                    let lit = self.meter_fraction() * 3. > bar as f32;
+                    let height = if lit { 3 + bar * 3 } else { 1 };
+                    Rectangle::new(
+                        Point::new(119 + bar * 3, 59 - height),
+                        Size::new(2, height as u32),
+                    )
+                    .draw_styled(&fill_style, display)?;
+                }
+            }
+            SignalMeter::Arc => {
+                // the scale, filled from the bottom up to the current RSSI
+                let fraction = self.meter_fraction();
+                Arc::with_center(
+                    Point::new(118, 53),
+                    14,
+                    Angle::from_degrees(-60.),
+                    Angle::from_degrees(120.),
+                )
+                .draw_styled(&stroke_style, display)?;
+                Arc::with_center(
+                    Point::new(118, 53),
+                    9,
+                    Angle::from_degrees(60.),
+                    Angle::from_degrees(-120. * fraction),
+                )
+                .draw_styled(&PrimitiveStyle::with_stroke(BinaryColor::On, 3), display)?;
+            }
+        }
+
+        // indicators between presets and the level indicator,
+        // lock or favorite on the first line, stereo on the second
+        if self.locked {
+            // padlock shackle and body
             Arc::with_center(
-                Point::new(118, 53),
-                line * 6,
-                Angle::from_degrees(-60.),
-                Angle::from_degrees(120.),
+                Point::new(102, 48),
+                5,
+                Angle::from_degrees(180.),
+                Angle::from_degrees(180.),
             )
             .draw_styled(&stroke_style, display)?;
+            Rectangle::new(Point::new(99, 48), Size::new(7, 6))
+                .draw_styled(&fill_style, display)?;
+        } else if self.is_favorite() {
+            Text::new("*", Point::new(99, 53), text_style).draw(display)?;
+        }
+        if self.tuner_status.stereo {
+            Text::new("ST", Point::new(96, 62), text_style).draw(display)?;
         }
 
-        // station info
-        // 19 chars on each line
-        let line1 = self.station_info.chars().take(19).collect::<String>();
-        let line2 = self
-            .station_info
-            .chars()
-            .skip(19)
-            .take(19)
-            .collect::<String>();
-        Text::new(line1.as_str(), Point::new(2, 30), text_style).draw(display)?;
-        Text::new(line2.as_str(), Point::new(2, 40), text_style).draw(display)?;
+        // station info, hidden when RDS is disabled, on weak signals, or replaced by the dial
+        // station name on the first line, the selected info on the second, long lines scroll
+        if self.settings.home_layout == HomeLayout::Dial {
+            draw_dial(self, display)?;
+        } else if self.show_station_info() {
+            let (line1, line2) = self.info_lines();
+            Text::new(line1.as_str(), Point::new(2, 30), text_style).draw(display)?;
+            Text::new(line2.as_str(), Point::new(2, 40), text_style).draw(display)?;
+        }
 
         // -- Preset stations --
         for preset in 0..NUM_PRESETS {
@@ -136,15 +641,411 @@ impl AppState {
             let preset = preset as i32;
             selection_box(element, preset * 25, 45, 19, 19, display)?;
 
+            // named presets show the start of the name, the others their number
+            match &self.preset_names[preset as usize] {
+                Some(name) => Text::with_alignment(
+                    &name.chars().take(PRESET_LABEL_LEN).collect::<String>(),
+                    Point::new(preset * 25 + 10, 57),
+                    small_text_style,
+                    Alignment::Center,
+                )
+                .draw(display)?,
+                None => Text::new(
+                    format!("{}", preset + 1).as_str(),
+                    Point::new(preset * 25 + 5, 60),
+                    big_text_style,
+                )
+                .draw(display)?,
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Draw the screensaver, the tuned station over bars
+    /// which move with the recent fluctuation of RSSI
+    fn draw_screensaver<D: DrawTarget<Color = BinaryColor>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        // vertical center of the bars and their largest height in each direction
+        const CENTER_Y: i32 = 44;
+        const MAX_HEIGHT: i32 = 12;
+
+        let fill_style = PrimitiveStyle::with_fill(BinaryColor::On);
+        let text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::iso_8859_2::FONT_6X9,
+            BinaryColor::On,
+        );
+
+        let freq = self.frequency_text(self.frequency);
+        let station = if !self.show_station_info() || self.station_name.trim().is_empty() {
+            match self.settings.readout {
+                Readout::Mhz => format!("{freq} MHz"),
+                Readout::Channel | Readout::Both => freq,
+            }
+        } else {
+            format!("{freq} {}", self.station_name.trim())
+        };
+        Text::with_alignment(&station, Point::new(64, 12), text_style, Alignment::Center)
+            .draw(display)?;
+
+        // bars are scaled to the range of recent samples, so that small
+        // fluctuations are visible, and a steady signal gives flat bars
+        let min = self.rssi_history.iter().min().copied().unwrap_or(0) as i32;
+        let max = self.rssi_history.iter().max().copied().unwrap_or(0) as i32;
+        let range = (max - min).max(1);
+
+        for (index, &rssi) in self.rssi_history.iter().enumerate() {
+            let height = 1 + (rssi as i32 - min) * MAX_HEIGHT / range;
+            Rectangle::new(
+                Point::new(index as i32 * 4 + 1, CENTER_Y - height),
+                Size::new(2, height as u32 * 2),
+            )
+            .draw_styled(&fill_style, display)?;
+        }
+
+        Ok(())
+    }
+
+    /// Draw the minimal home screen, only a huge frequency with the station name below it
+    fn draw_minimal<D: DrawTarget<Color = BinaryColor>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        let text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::iso_8859_2::FONT_6X9,
+            BinaryColor::On,
+        );
+        let big_text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::ascii::FONT_10X20,
+            BinaryColor::On,
+        );
+
+        // the frequency is drawn at double size, so its coordinates are halved
+        Text::with_alignment(
+            &frequency_label(self.frequency, self.settings.readout),
+            Point::new(32, 16),
+            big_text_style,
+            Alignment::Center,
+        )
+        .draw(&mut DoubleSize(display))?;
+
+        if self.show_station_info() {
+            let name = if self.station_name.trim().is_empty() {
+                self.station_info.chars().take(21).collect::<String>()
+            } else {
+                self.station_name.trim().to_string()
+            };
+            Text::with_alignment(
+                name.trim(),
+                Point::new(64, 54),
+                text_style,
+                Alignment::Center,
+            )
+            .draw(display)?;
+        }
+
+        Ok(())
+    }
+
+    /// Draw the list of settings with their current values
+    fn draw_settings<D: DrawTarget<Color = BinaryColor>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        let rows = Setting::ALL
+            .iter()
+            .map(|&setting| (setting.label().to_string(), self.setting_value(setting)))
+            .collect::<Vec<_>>();
+
+        draw_list("Settings", &rows, self.settings_cursor, display)
+    }
+
+    /// Draw the list of favorite stations
+    fn draw_favorites<D: DrawTarget<Color = BinaryColor>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        if self.favorites.is_empty() {
+            let rows = [("No favorites".to_string(), String::new())];
+            return draw_list("Favorites", &rows, usize::MAX, display);
+        }
+
+        let rows = self
+            .favorites
+            .iter()
+            .enumerate()
+            .map(|(index, &freq)| (format!("{}.", index + 1), self.frequency_text(freq)))
+            .collect::<Vec<_>>();
+
+        draw_list("Favorites", &rows, self.favorites_cursor, display)
+    }
+
+    /// Draw the list of recently strong stations with their RSSI
+    fn draw_recent<D: DrawTarget<Color = BinaryColor>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        let stations = self.recent_stations.stations();
+        if stations.is_empty() {
+            let rows = [("No stations yet".to_string(), String::new())];
+            return draw_list("Strong stations", &rows, usize::MAX, display);
+        }
+
+        let rows = stations
+            .iter()
+            .map(|&(freq, rssi)| (self.frequency_text(freq), format!("RSSI {rssi}")))
+            .collect::<Vec<_>>();
+
+        draw_list("Strong stations", &rows, self.recent_cursor, display)
+    }
+
+    /// Draw the instructions for the current step of the meter calibration,
+    /// with the tuned frequency and its RSSI below them
+    fn draw_calibration<D: DrawTarget<Color = BinaryColor>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        let text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::iso_8859_2::FONT_6X9,
+            BinaryColor::On,
+        );
+
+        let (line1, line2) = self.calibration_lines();
+        Text::new("Meter calibration", Point::new(2, 9), text_style).draw(display)?;
+        Text::new(line1, Point::new(2, 26), text_style).draw(display)?;
+        Text::new(line2, Point::new(2, 37), text_style).draw(display)?;
+        Text::new(
+            &format!(
+                "{}  RSSI {}",
+                self.frequency_text(self.frequency),
+                self.rssi
+            ),
+            Point::new(2, 56),
+            text_style,
+        )
+        .draw(display)?;
+
+        Ok(())
+    }
+
+    /// Draw the key of the IR remote, which is being learned
+    fn draw_remote_learning<D: DrawTarget<Color = BinaryColor>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        let text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::iso_8859_2::FONT_6X9,
+            BinaryColor::On,
+        );
+
+        Text::new("Learn remote", Point::new(2, 9), text_style).draw(display)?;
+        Text::new("Press the key for", Point::new(2, 26), text_style).draw(display)?;
+        Text::new(&self.remote_learning_line(), Point::new(2, 37), text_style).draw(display)?;
+        Text::new("Knob: keep old key", Point::new(2, 56), text_style).draw(display)?;
+
+        Ok(())
+    }
+
+    /// Draw one of the recent Radio Text messages, wrapped over the lines below the title,
+    /// with its position among the messages, the newest is the first
+    fn draw_text_history<D: DrawTarget<Color = BinaryColor>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        // characters on a line, and the lines which fit below the title
+        const LINE_CHARS: usize = 21;
+        const LINES: usize = 4;
+
+        let text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::iso_8859_2::FONT_6X9,
+            BinaryColor::On,
+        );
+
+        let texts = self.radio_texts();
+        if texts.is_empty() {
+            Text::new("Radio text", Point::new(2, 9), text_style).draw(display)?;
+            return Text::new("No text yet", Point::new(2, 36), text_style)
+                .draw(display)
+                .map(|_| ());
+        }
+
+        let index = self.text_history_cursor.min(texts.len() - 1);
+        Text::new("Radio text", Point::new(2, 9), text_style).draw(display)?;
+        Text::with_alignment(
+            &format!("{}/{}", index + 1, texts.len()),
+            Point::new(125, 9),
+            text_style,
+            Alignment::Right,
+        )
+        .draw(display)?;
+
+        let chars = texts[index].chars().collect::<Vec<_>>();
+        for (line, chunk) in chars.chunks(LINE_CHARS).take(LINES).enumerate() {
+            let line_text = chunk.iter().collect::<String>();
             Text::new(
-                format!("{}", preset + 1).as_str(),
-                Point::new(preset * 25 + 5, 60),
-                big_text_style,
+                line_text.trim_start(),
+                Point::new(2, 24 + line as i32 * 11),
+                text_style,
             )
             .draw(display)?;
         }
 
-        // draw to the display
-        display.flush()
+        Ok(())
+    }
+
+    /// Draw a histogram of the scanned stations across the band, the bar height follows
+    /// the RSSI of the station, the selected one is marked below the baseline
+    fn draw_spectrum<D: DrawTarget<Color = BinaryColor>>(
+        &self,
+        display: &mut D,
+    ) -> Result<(), D::Error> {
+        // horizontal extent of the band
+        const LEFT: i32 = 2;
+        const WIDTH: i32 = 123;
+        // vertical position of the baseline, and the height of the strongest bar
+        const BASE_Y: i32 = 58;
+        const MAX_HEIGHT: u32 = 40;
+
+        let fill_style = PrimitiveStyle::with_fill(BinaryColor::On);
+        let text_style = MonoTextStyle::new(
+            &embedded_graphics::mono_font::iso_8859_2::FONT_6X9,
+            BinaryColor::On,
+        );
+
+        Text::new("Band activity", Point::new(2, 9), text_style).draw(display)?;
+
+        let Some(&(selected, rssi)) = self.stations.get(self.spectrum_cursor) else {
+            return Text::new("No scan yet", Point::new(2, 36), text_style)
+                .draw(display)
+                .map(|_| ());
+        };
+
+        Text::with_alignment(
+            &format!("{} {rssi}", self.frequency_text(selected)),
+            Point::new(125, 9),
+            text_style,
+            Alignment::Right,
+        )
+        .draw(display)?;
+
+        // position of the frequency in the band
+        let x = |freq: Frequency| LEFT + freq.band_position(WIDTH as u32) as i32;
+
+        Rectangle::new(Point::new(LEFT, BASE_Y + 1), Size::new(WIDTH as u32 + 1, 1))
+            .draw_styled(&fill_style, display)?;
+
+        for &(freq, rssi) in &self.stations {
+            let height = 1 + (rssi as u32).min(MAX_METER_RSSI) * (MAX_HEIGHT - 1) / MAX_METER_RSSI;
+            Rectangle::new(
+                Point::new(x(freq), BASE_Y + 1 - height as i32),
+                Size::new(1, height),
+            )
+            .draw_styled(&fill_style, display)?;
+        }
+
+        let x = x(selected);
+        Triangle::new(
+            Point::new(x, BASE_Y + 2),
+            Point::new(x - 2, BASE_Y + 5),
+            Point::new(x + 2, BASE_Y + 5),
+        )
+        .draw_styled(&fill_style, display)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use embedded_graphics::prelude::OriginDimensions;
+
+    use super::*;
+    use crate::state::UI_ELEMENT_RING;
+
+    /// Panel of the size of the display, which fails with the first pixel outside of it
+    #[derive(Default)]
+    pub struct MockDisplay {
+        /// Number of pixels drawn
+        pub drawn: usize,
+        /// Number of frames flushed
+        pub flushed: usize,
+        pub brightness: Option<Brightness>,
+        pub on: bool,
+    }
+
+    impl OriginDimensions for MockDisplay {
+        fn size(&self) -> Size {
+            Size::new(128, 64)
+        }
+    }
+
+    impl DrawTarget for MockDisplay {
+        type Color = BinaryColor;
+        type Error = Point;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            let bounding_box = self.bounding_box();
+            for Pixel(point, _) in pixels {
+                if !bounding_box.contains(point) {
+                    return Err(point);
+                }
+                self.drawn += 1;
+            }
+            Ok(())
+        }
+    }
+
+    impl Panel for MockDisplay {
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            self.flushed += 1;
+            Ok(())
+        }
+
+        fn set_brightness(&mut self, brightness: Brightness) -> Result<(), Self::Error> {
+            self.brightness = Some(brightness);
+            Ok(())
+        }
+
+        fn set_display_on(&mut self, on: bool) -> Result<(), Self::Error> {
+            self.on = on;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn home_screen_is_drawn_in_every_state() {
+        let mut state = AppState::new();
+        let long_info = "W".repeat(64);
+
+        for layout in [HomeLayout::Buttons, HomeLayout::Dial, HomeLayout::Minimal] {
+            for element in UI_ELEMENT_RING {
+                for active in [false, true] {
+                    for volume in [0, 15] {
+                        for info in ["", long_info.as_str()] {
+                            state.settings.home_layout = layout;
+                            state.cursor_at = element;
+                            state.element_is_active = active;
+                            state.volume = volume;
+                            state.station_info = info.to_string();
+
+                            let mut display = MockDisplay::default();
+                            let result = state.update_ui(&mut display);
+                            assert!(
+                                result.is_ok(),
+                                "{layout:?}, {element:?}, active {active}, volume {volume}, \
+                                 info {}: {result:?}",
+                                info.len()
+                            );
+                            assert!(display.drawn > 0);
+                        }
+                    }
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,71 @@
+use crate::{AppState, InputEvent, Screen, UIElement};
+
+impl AppState {
+    /// Shows or hides the help overlay over the current screen.
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
+
+    /// Hides the help overlay on any user input, returns whether it was shown,
+    /// in which case the input should not be processed any further.
+    pub fn dismiss_help(&mut self, event: &InputEvent) -> bool {
+        event.is_user_input() && std::mem::replace(&mut self.show_help, false)
+    }
+
+    /// Returns what the gestures do on the current screen, one gesture per line,
+    /// on the home screen for the element under the cursor.
+    pub fn help_lines(&self) -> &'static [&'static str] {
+        match self.screen {
+            Screen::Home => match (self.cursor_at, self.element_is_active) {
+                (UIElement::SeekDown | UIElement::SeekUp, _) => &[
+                    "Turn: move cursor",
+                    "Press: seek",
+                    "Hold: keep seeking",
+                    "Double: last station",
+                ],
+                (UIElement::FreqControl, false) => &[
+                    "Turn: move cursor",
+                    "Press: tune manually",
+                    "Long: scan band",
+                    "Double: favorite",
+                ],
+                (UIElement::Preset(_), false) if self.settings.confirm_recall => &[
+                    "Turn: move cursor",
+                    "Press: pick preset",
+                    "Long hold: store",
+                    "Double: strongest",
+                ],
+                (UIElement::Preset(_), false) => &[
+                    "Turn: move cursor",
+                    "Press: tune preset",
+                    "Long hold: store",
+                    "Double: strongest",
+                ],
+                (UIElement::Preset(_), true) => &["Turn: cancel", "Press: tune preset"],
+                (UIElement::VolumeControl, false) => &[
+                    "Turn: move cursor",
+                    "Press: set volume",
+                    "Long: settings",
+                    "Double: volume",
+                ],
+                (UIElement::FreqControl, true) => {
+                    &["Turn: tune", "Press: done", "Double: favorite"]
+                }
+                (UIElement::VolumeControl, true) => &["Turn: volume", "Press: done"],
+            },
+            Screen::Settings => &["Turn: choose", "Press: change", "Long: home"],
+            Screen::Favorites => &[
+                "Turn: choose",
+                "Press: tune",
+                "Double: remove",
+                "Long: home",
+            ],
+            Screen::Recent | Screen::Spectrum => &["Turn: choose", "Press: tune", "Long: home"],
+            Screen::TextHistory => &["Turn: older/newer", "Long: home"],
+            Screen::Calibration => &["Turn: tune", "Press: measure", "Long: cancel"],
+            Screen::RemoteLearning => &["Remote: learn key", "Press: keep old", "Long: cancel"],
+            Screen::RdsStats => &["Turn: scroll", "Long: home"],
+            Screen::About => &["Long: home"],
+        }
+    }
+}
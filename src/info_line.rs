@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    rds::{program_type_name, RdsReception},
+    AppState, HomeLayout, InfoLine, Screen,
+};
+
+/// Number of characters, which fit on a line of station info
+const LINE_CHARS: usize = 19;
+
+/// Time between moving a scrolling line by one character
+const SCROLL_STEP: Duration = Duration::from_millis(400);
+
+/// Number of steps, for which a scrolling line rests at either end
+const SCROLL_PAUSE_STEPS: usize = 5;
+
+/// Returns the part of the text, which fits on a line, `elapsed` since the text appeared.
+///
+/// Longer texts scroll to their end and back, resting for a while at both ends.
+fn scrolled(text: &str, elapsed: Duration) -> String {
+    let len = text.chars().count();
+    if len <= LINE_CHARS {
+        return text.to_string();
+    }
+
+    let overflow = len - LINE_CHARS;
+    let half = overflow + SCROLL_PAUSE_STEPS;
+    let step = (elapsed.as_millis() / SCROLL_STEP.as_millis()) as usize % (2 * half);
+    let offset = if step < half {
+        step.saturating_sub(SCROLL_PAUSE_STEPS)
+    } else {
+        overflow - (step - half).saturating_sub(SCROLL_PAUSE_STEPS)
+    };
+
+    text.chars().skip(offset).take(LINE_CHARS).collect()
+}
+
+impl AppState {
+    /// Returns the time from the last clock time received from RDS, as `HH:MM`.
+    fn clock_label(&self) -> String {
+        match self.clock {
+            Some((minutes, received)) => {
+                let minutes = (minutes as u64 + received.elapsed().as_secs() / 60) % (24 * 60);
+                format!("{:02}:{:02}", minutes / 60, minutes % 60)
+            }
+            None => "--:--".to_string(),
+        }
+    }
+
+    /// Returns how long the station has been tuned, as `MM:SS`, or `H:MM:SS` after an hour.
+    pub fn tuned_label(&self) -> String {
+        let seconds = self
+            .tuned_since
+            .map_or(0, |(_, since)| since.elapsed().as_secs());
+        match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+            (0, minutes, seconds) => format!("{minutes:02}:{seconds:02}"),
+            (hours, minutes, seconds) => format!("{hours}:{minutes:02}:{seconds:02}"),
+        }
+    }
+
+    /// Restarts the time tuned, when the frequency differs from the one it runs for.
+    ///
+    /// The frequencies are compared only once a seek finishes, so that a seek landing
+    /// back on the same station keeps the time, as does retuning the same frequency.
+    pub fn update_tuned_since(&mut self) {
+        if self.seek_from.is_some() {
+            return;
+        }
+        if self
+            .tuned_since
+            .map_or(true, |(frequency, _)| frequency != self.frequency)
+        {
+            self.tuned_since = Some((self.frequency, Instant::now()));
+        }
+    }
+
+    /// Returns the visible parts of both lines of station info,
+    /// the station name on the first, the selected info on the second.
+    pub fn info_lines(&self) -> (String, String) {
+        let line2 = match self.settings.info_line {
+            InfoLine::RadioText => self.station_info.trim().to_string(),
+            InfoLine::ProgramType => program_type_name(self.program_type).to_string(),
+            InfoLine::Clock => self.clock_label(),
+            InfoLine::TunedTime => format!("Tuned {}", self.tuned_label()),
+        };
+
+        // until a name arrives, the first line tells whether any RDS is coming
+        let line1 = match (self.station_name.trim(), self.rds_reception) {
+            ("", RdsReception::Waiting) => "...",
+            ("", RdsReception::Missing) => "no RDS",
+            (name, _) => name,
+        };
+
+        let elapsed = self.info_scroll_from.elapsed();
+        (scrolled(line1, elapsed), scrolled(&line2, elapsed))
+    }
+
+    /// Restarts scrolling of station info from the start of both lines,
+    /// called when the tuned station changes.
+    pub fn restart_info_scroll(&mut self) {
+        self.info_scroll_from = Instant::now();
+    }
+
+    /// Advances scrolling of station info, and the clock shown by it,
+    /// returns whether the shown lines changed and should be redrawn.
+    pub fn update_info_scroll(&mut self) -> bool {
+        let shown = self.screen == Screen::Home
+            && self.settings.home_layout == HomeLayout::Buttons
+            && !self.screensaver
+            && self.show_station_info();
+        if !shown {
+            return false;
+        }
+
+        let lines = self.info_lines();
+        if lines == self.info_shown {
+            return false;
+        }
+        self.info_shown = lines;
+        true
+    }
+}
@@ -1,60 +1,286 @@
-use esp_idf_hal::gpio::PinDriver;
-use esp_idf_svc::hal::{self as esp_idf_hal, gpio::InputPin};
-use std::{sync::mpsc::Sender, thread};
+use esp_idf_hal::gpio::{Input, PinDriver};
+use esp_idf_svc::{
+    hal::{self as esp_idf_hal, gpio::InputPin},
+    sys::EspError,
+};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
-use crate::InputEvent;
+use crate::{error::Error, InputEvent};
+
+/// Pause after waiting for an edge failed, before the pin is read as after an edge
+const EDGE_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// How long after a short press the second press of a double press may come
+const DOUBLE_PRESS_WINDOW: Duration = Duration::from_millis(250);
+
+/// How long the button must be held, before the press becomes long
+const LONG_PRESS_DURATION: Duration = Duration::from_millis(600);
+
+/// Interval between hold events, while the button is held after a long press
+const HOLD_REPEAT_INTERVAL: Duration = Duration::from_millis(700);
+
+/// State shared by the listeners of the rotary encoder and its integrated button,
+/// so that a press and a turn close to each other are not mistaken for one another.
+#[derive(Debug, Default)]
+
+pub struct EncoderLink {
+    /// Whether the button of the encoder is held
+    pressed: AtomicBool,
+    /// Number of presses started so far, a change tells the encoder a press came mid-turn
+    presses: AtomicU32,
+    /// Number of detents reported so far, a change tells the button the encoder turned
+    detents: AtomicU32,
+}
+
+impl EncoderLink {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    fn press_started(&self) {
+        self.pressed.store(true, Ordering::Relaxed);
+        self.presses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn press_ended(&self) {
+        self.pressed.store(false, Ordering::Relaxed);
+    }
+
+    fn detents(&self) -> u32 {
+        self.detents.load(Ordering::Relaxed)
+    }
+}
+
+/// Button, which the presses come from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ButtonSource {
+    /// Button of the rotary encoder, all gestures are reported
+    Encoder,
+    /// Dedicated back button, a long press is reported as `Help`, a double press
+    /// as `QuickSave`, every other press as `Back`
+    Back,
+}
+
+impl ButtonSource {
+    /// Returns the event, which the gesture on this button should be reported as.
+    fn event(self, gesture: InputEvent) -> Option<InputEvent> {
+        match (self, gesture) {
+            (ButtonSource::Encoder, gesture) => Some(gesture),
+            (ButtonSource::Back, InputEvent::Hold) => None,
+            (ButtonSource::Back, InputEvent::LongPress) => Some(InputEvent::Help),
+            (ButtonSource::Back, InputEvent::DoublePress) => Some(InputEvent::QuickSave),
+            (ButtonSource::Back, _) => Some(InputEvent::Back),
+        }
+    }
+}
+
+/// Waits for an edge of the pin. A failure is logged and, after a pause, returned
+/// as an edge, which the listeners drop as a bounce, unless the level changed.
+fn wait_for_edge(edge: impl Future<Output = Result<(), EspError>>) {
+    if let Err(err) = esp_idf_hal::task::block_on(edge) {
+        log::warn!("Waiting for a pin edge failed: {err}");
+        thread::sleep(EDGE_RETRY_DELAY);
+    }
+}
 
 /// Spawns a new thread which waits on a button press using interrupt, then measures
 /// the press length, removes bounces and sends an input event to the event loop.
-pub fn spawn_button_listener(button_pin: impl InputPin, event_sender: Sender<InputEvent>) {
+///
+/// A short press is reported only after it is clear, that it is not the first half
+/// of a double press. While the button is held longer than a short press, hold events
+/// are repeatedly sent, the long press itself is reported on release.
+///
+/// Presses held for at least `very_long_press` are reported as very long presses,
+/// which are reserved for actions that overwrite something.
+///
+/// The source decides, which events the gestures are reported as.
+///
+/// With a link to the encoder, a turn between two presses ends the first as a short press,
+/// and the second press is measured on its own instead of completing a double press.
+pub fn spawn_button_listener(
+    mut encoder_button: PinDriver<'static, impl InputPin, Input>,
+    source: ButtonSource,
+    very_long_press: Duration,
+    link: Option<Arc<EncoderLink>>,
+    event_sender: Sender<InputEvent>,
+) {
     thread::spawn(move || {
-        let mut encoder_button = PinDriver::input(button_pin).unwrap();
+        let send = |gesture| {
+            if let Some(event) = source.event(gesture) {
+                event_sender.send(event).unwrap();
+            }
+        };
+
+        // a press, which came during the double press window, but was not part of it
+        let mut pressed_already = false;
 
         loop {
-            esp_idf_hal::task::block_on(encoder_button.wait_for_falling_edge()).unwrap();
+            if !std::mem::take(&mut pressed_already) {
+                wait_for_edge(encoder_button.wait_for_falling_edge());
+            }
             let start = std::time::Instant::now();
+            if let Some(link) = &link {
+                link.press_started();
+            }
 
-            esp_idf_hal::task::block_on(encoder_button.wait_for_rising_edge()).unwrap();
+            // wait for the release, repeating hold events meanwhile
+            let mut next_hold = start + LONG_PRESS_DURATION;
+            while encoder_button.is_low() {
+                if Instant::now() >= next_hold {
+                    send(InputEvent::Hold);
+                    next_hold += HOLD_REPEAT_INTERVAL;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
             let duration = std::time::Instant::now() - start;
+            if let Some(link) = &link {
+                link.press_ended();
+            }
 
             match duration.as_millis() {
                 // debouncing
                 0..50 => (),
-                50..600 => event_sender.send(InputEvent::ShortPress).unwrap(),
-                600.. => event_sender.send(InputEvent::LongPress).unwrap(),
+                50..600 => {
+                    // let the release of the first press settle before looking for the second
+                    thread::sleep(Duration::from_millis(20));
+
+                    let detents = link.as_ref().map(|link| link.detents());
+                    let turned = || link.as_ref().map(|link| link.detents()) != detents;
+
+                    let second_press = Instant::now();
+                    while encoder_button.is_high()
+                        && second_press.elapsed() < DOUBLE_PRESS_WINDOW
+                        && !turned()
+                    {
+                        thread::sleep(Duration::from_millis(10));
+                    }
+
+                    if turned() {
+                        // the encoder turned in between, so presses around it stand alone
+                        send(InputEvent::ShortPress);
+                        pressed_already = encoder_button.is_low();
+                    } else if encoder_button.is_low() {
+                        wait_for_edge(encoder_button.wait_for_rising_edge());
+                        send(InputEvent::DoublePress);
+                    } else {
+                        send(InputEvent::ShortPress);
+                    }
+                }
+                ms if ms >= very_long_press.as_millis() => send(InputEvent::VeryLongPress),
+                600.. => send(InputEvent::LongPress),
             }
         }
     });
 }
 
+/// Spawns a new thread which waits on presses of the mute button using interrupt,
+/// and sends a `MuteDown` event once a press outlasts bounces, and `MuteUp`
+/// on its release, whether they toggle or hold the mute is decided by the event loop.
+pub fn spawn_mute_listener(
+    mut mute_button: PinDriver<'static, impl InputPin, Input>,
+    event_sender: Sender<InputEvent>,
+) {
+    thread::spawn(move || loop {
+        wait_for_edge(mute_button.wait_for_falling_edge());
+
+        // debouncing
+        thread::sleep(Duration::from_millis(50));
+        if mute_button.is_high() {
+            continue;
+        }
+
+        event_sender.send(InputEvent::MuteDown).unwrap();
+        while mute_button.is_low() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        event_sender.send(InputEvent::MuteUp).unwrap();
+    });
+}
+
+/// Number of full quadrature cycles, which the rotary encoder generates per detent.
+///
+/// This differs between encoder models, setting it correctly makes
+/// one physical click produce exactly one scroll event.
+#[allow(dead_code)] // selected according to the used encoder
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PulsesPerDetent {
+    One = 1,
+    Two = 2,
+    Four = 4,
+}
+
 /// Spawns a new thread which waits on a turn of the rotary encoder using interrupt,
-/// then sends an input event to the event loop.
+/// decodes the quadrature signal, then sends an input event to the event loop
+/// for each detent.
+///
+/// Pressing the integrated button wobbles the shaft, so a part of a detent turned
+/// before a press is not completed by edges after it, and edges while the button
+/// is held are not counted.
 pub fn spawn_encoder_listener(
     s1: impl InputPin,
     s2: impl InputPin,
+    pulses_per_detent: PulsesPerDetent,
+    link: Arc<EncoderLink>,
     event_sender: Sender<InputEvent>,
-) {
-    thread::spawn(move || {
-        let mut s1 = PinDriver::input(s1).unwrap();
-        let s2 = PinDriver::input(s2).unwrap();
+) -> Result<(), Error> {
+    let mut s1 = PinDriver::input(s1).map_err(Error::Gpio)?;
+    let s2 = PinDriver::input(s2).map_err(Error::Gpio)?;
 
-        let mut second = false;
+    thread::spawn(move || {
+        // every edge on s1 is one half of a quadrature cycle
+        let steps_per_detent = 2 * pulses_per_detent as i32;
+        let mut steps = 0;
+        let mut prev_level = s1.get_level();
+        let mut presses = link.presses.load(Ordering::Relaxed);
 
         loop {
-            esp_idf_hal::task::block_on(async {
-                s1.wait_for_rising_edge().await.unwrap();
-            });
-
-            // the rotary encoder generates two rising edges each turn, filter out every other
-            // (this is consistent, given by the construction of the module, not by any bouncing)
-            if !second {
-                if s2.get_level() == s1.get_level() {
-                    event_sender.send(InputEvent::ScrollUp).unwrap();
-                } else {
-                    event_sender.send(InputEvent::ScrollDown).unwrap();
-                }
+            wait_for_edge(s1.wait_for_any_edge());
+            let level = s1.get_level();
+
+            // start counting anew from the level after a press, so that the edge
+            // ending a wobble is not filtered out as a bounce of the turn before
+            let pressed = link.pressed.load(Ordering::Relaxed);
+            let presses_now = link.presses.load(Ordering::Relaxed);
+            if pressed || presses_now != presses {
+                presses = presses_now;
+                prev_level = level;
+                steps = 0;
+                continue;
+            }
+
+            // a bounce, which ended at the previous level
+            if level == prev_level {
+                continue;
+            }
+            prev_level = level;
+
+            // when turning clockwise, s1 follows s2, so bounces
+            // on s1 cancel out instead of producing false steps
+            if s2.get_level() == level {
+                steps += 1;
+            } else {
+                steps -= 1;
+            }
+
+            if steps >= steps_per_detent {
+                link.detents.fetch_add(1, Ordering::Relaxed);
+                event_sender.send(InputEvent::ScrollUp).unwrap();
+                steps = 0;
+            } else if steps <= -steps_per_detent {
+                link.detents.fetch_add(1, Ordering::Relaxed);
+                event_sender.send(InputEvent::ScrollDown).unwrap();
+                steps = 0;
             }
-            second = !second;
         }
     });
+    Ok(())
 }
@@ -0,0 +1,121 @@
+use esp_idf_svc::hal::gpio::PinDriver;
+use std::{
+    sync::atomic::{AtomicU8, Ordering},
+    thread,
+    time::Duration,
+};
+
+use crate::{confirm::take_led_confirm, error::Error, pins::output_pin, AppState};
+
+/// Lowest RSSI, at which the station is considered strong
+const LED_STRONG_RSSI: u8 = 30;
+
+/// How long the LED is off around the flash confirming a landed seek,
+/// so that the flash stands out of a solid pattern
+const LED_CONFIRM_GAP: Duration = Duration::from_millis(100);
+
+/// Status of the radio shown by the LED.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedStatus {
+    /// Volume is zero
+    Muted,
+    /// A seek or tune is running
+    Seeking,
+    /// Tuned station is lost
+    NoSignal,
+    /// Tuned to a weak station
+    Weak,
+    /// Tuned to a strong station
+    Strong,
+}
+
+/// Blink pattern, a zero duration keeps the LED off or on all the time.
+struct LedPattern {
+    on: Duration,
+    off: Duration,
+}
+
+/// Patterns for each status, in the order of `LedStatus`
+const LED_PATTERNS: [LedPattern; 5] = [
+    // muted, off
+    LedPattern {
+        on: Duration::ZERO,
+        off: Duration::from_millis(100),
+    },
+    // seeking, fast blink
+    LedPattern {
+        on: Duration::from_millis(100),
+        off: Duration::from_millis(100),
+    },
+    // no signal, short flash
+    LedPattern {
+        on: Duration::from_millis(100),
+        off: Duration::from_millis(1900),
+    },
+    // weak, slow blink
+    LedPattern {
+        on: Duration::from_millis(500),
+        off: Duration::from_millis(500),
+    },
+    // strong, solid
+    LedPattern {
+        on: Duration::from_millis(100),
+        off: Duration::ZERO,
+    },
+];
+
+/// Status currently shown by the LED
+static LED_STATUS: AtomicU8 = AtomicU8::new(LedStatus::Muted as u8);
+
+/// Changes the status shown by the LED, the new pattern starts after the current one.
+pub fn set_led_status(status: LedStatus) {
+    LED_STATUS.store(status as u8, Ordering::Relaxed);
+}
+
+/// Spawns a new thread which blinks the LED on the pin with the given number,
+/// according to the pattern of the status set by `set_led_status`.
+///
+/// A landed seek is confirmed between two patterns by a solid flash.
+pub fn spawn_led_thread(pin: i32) -> Result<(), Error> {
+    let mut led = PinDriver::output(output_pin(pin)).map_err(Error::Gpio)?;
+
+    thread::spawn(move || loop {
+        if let Some(duration) = take_led_confirm() {
+            led.set_low().unwrap();
+            thread::sleep(LED_CONFIRM_GAP);
+            led.set_high().unwrap();
+            thread::sleep(duration);
+            led.set_low().unwrap();
+            thread::sleep(LED_CONFIRM_GAP);
+        }
+
+        let pattern = &LED_PATTERNS[LED_STATUS.load(Ordering::Relaxed) as usize];
+
+        if !pattern.on.is_zero() {
+            led.set_high().unwrap();
+            thread::sleep(pattern.on);
+        }
+        if !pattern.off.is_zero() {
+            led.set_low().unwrap();
+            thread::sleep(pattern.off);
+        }
+    });
+    Ok(())
+}
+
+impl AppState {
+    /// Returns the status of the radio, which should be shown by the LED.
+    pub fn led_status(&self) -> LedStatus {
+        if self.output_volume() == 0 {
+            LedStatus::Muted
+        } else if !self.tuner_status.seek_complete {
+            LedStatus::Seeking
+        } else if self.signal_lost {
+            LedStatus::NoSignal
+        } else if self.rssi >= LED_STRONG_RSSI {
+            LedStatus::Strong
+        } else {
+            LedStatus::Weak
+        }
+    }
+}
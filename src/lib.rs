@@ -0,0 +1,1262 @@
+// on the development machine, where the logic is unit tested,
+// a stand-in provides the parts of the ESP-IDF services, which the firmware uses
+#[cfg(not(target_os = "espidf"))]
+extern crate esp_idf_host as esp_idf_svc;
+
+mod about;
+mod ambient;
+mod auto_off;
+mod boot;
+mod calibration;
+mod chip;
+mod confirm;
+mod console;
+mod country;
+mod display;
+mod double_buffer;
+mod error;
+#[cfg(test)]
+mod fake_chip;
+mod favorites;
+mod frequency;
+mod gui;
+mod help;
+mod info_line;
+mod input;
+mod led;
+mod lock;
+mod mute;
+mod nvs_writer;
+mod panic;
+mod pins;
+mod quick_save;
+mod rds;
+mod rds_stats;
+mod recent;
+mod remote;
+mod replay;
+mod sampling;
+mod screensaver;
+mod selftest;
+mod settings;
+mod signal;
+mod sleep;
+mod snapshot;
+mod spectrum;
+mod state;
+mod stereo;
+mod storage;
+mod text_history;
+mod trace;
+mod tuner;
+mod verbosity;
+mod watchdog;
+
+use esp_idf_svc::{
+    hal::{
+        gpio::{Level, PinDriver},
+        prelude::Peripherals,
+        units::KiloHertz,
+    },
+    nvs::{EspDefaultNvsPartition, EspNvs, NvsDefault},
+    sys::esp_restart,
+};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    time::{Duration, Instant},
+};
+
+use ambient::spawn_ambient_thread;
+use auto_off::power_off;
+use boot::boot_step;
+use calibration::Calibration;
+use confirm::spawn_confirm_thread;
+use console::spawn_console;
+use display::{setup_display, BRIGHTNESS_LEVELS};
+use error::Error;
+use frequency::{Band, Frequency, Spacing};
+use gui::Panel;
+use input::{
+    spawn_button_listener, spawn_encoder_listener, spawn_mute_listener, ButtonSource, EncoderLink,
+    PulsesPerDetent,
+};
+use led::{set_led_status, spawn_led_thread};
+use nvs_writer::DebouncedWriter;
+use panic::{install_panic_hook, panicked, turn_off_amp};
+use pins::{input_pin, io_pin, output_pin, PIN_CONFIG};
+use rda5807m::Address;
+use rds::RdsReception;
+use recent::RecentStations;
+use remote::{remote_receiver, spawn_remote_listener, RemoteKey, REMOTE_KEYS};
+use replay::replay_events;
+use selftest::run_self_test;
+use storage::{
+    load_frequencies, load_last_station, load_meter_range, load_preset_names, load_preset_uses,
+    load_preset_volumes, load_presets, load_remote_codes, load_stations, FAVORITES_KEY,
+};
+use trace::record_event;
+use tuner::spawn_tuner_thread;
+use watchdog::{spawn_watchdog, Heartbeat};
+
+/// All possible inputs to be processed in the event loop.
+///
+/// The events are generated by user actions and by the tuner module itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum InputEvent {
+    /// Short (< 600ms) press of the rotary encoder button
+    ShortPress,
+    /// Long (> 600ms, shorter than a very long press) press of the rotary encoder button
+    LongPress,
+    /// Very long (> `VERY_LONG_PRESS_DURATION`) press of the rotary encoder button
+    VeryLongPress,
+    /// Repeated while the rotary encoder button is held after a long press, before its release
+    Hold,
+    /// Two short presses of the rotary encoder button in quick succession
+    DoublePress,
+    /// Counterclockwise turn of the rotary encoder
+    ScrollDown,
+    /// Clockwise turn of the rotary encoder
+    ScrollUp,
+    /// Press of the dedicated back button
+    Back,
+    /// Long press of the dedicated back button, or its entry in settings, toggles the help
+    Help,
+    /// Double press of the dedicated back button, stores the station into the first empty preset
+    QuickSave,
+    /// Mute button was pressed
+    MuteDown,
+    /// Mute button was released
+    MuteUp,
+    /// Key of the IR remote was pressed, with its NEC code
+    RemoteCode(u32),
+    /// Key of the IR remote, which was pressed last, is still held
+    RemoteRepeat,
+    /// Frequency of the tuner was changed during seeking
+    ChangeFrequency(Frequency),
+    /// Station info changed
+    ChangeStationInfo(String),
+    /// Finished Radio Text messages of the tuned station changed, newest first
+    ChangeTextHistory(Vec<String>),
+    /// Station name (Program Service name from RDS) changed,
+    /// with whether all of its segments were received
+    ChangeStationName(String, bool),
+    /// Program Type code from RDS changed, zero when unknown
+    ChangeProgramType(u8),
+    /// Whether RDS is received from the tuned station changed
+    ChangeRdsReception(RdsReception),
+    /// Clock time from RDS was received, as minutes since the local midnight
+    ChangeClockTime(u16),
+    /// RSSI value changed (changes are debounced)
+    ChangeRSSI(u8),
+    /// Some of the tuner status flags changed
+    ChangeStatus(TunerStatus),
+    /// Band scan finished with the frequencies of all found stations and their RSSI
+    ScanFinished(Vec<(Frequency, u8)>),
+    /// Seek went through the whole band without finding a station
+    SeekFailed,
+    /// Running seek was aborted, the tuner returned to the given frequency
+    SeekCancelled(Frequency),
+    /// RSSI of the tuned station stayed below the threshold for a while
+    SignalLost,
+    /// RSSI of the lost station stayed above the threshold for a while
+    SignalAcquired,
+    /// Number of frequencies already sampled by `TuneStrongest`, and of all of them
+    SamplingProgress(usize, usize),
+    /// Sampling finished on the strongest frequency, with its RSSI
+    SamplingFinished(Frequency, u8),
+    /// Tuner did not answer on the I2C bus at startup, the radio cannot play
+    TunerNotFound,
+    /// Nearest station was found in the given direction and tuned
+    NearestFound(Frequency, Direction),
+    /// Search for the nearest station found none close enough on either side
+    NoStationNearby,
+    /// Ambient light changed, with the fitting display brightness, an index into `BRIGHTNESS_LEVELS`
+    AmbientLight(u8),
+    /// Average RSSI measured on the tuned frequency by `MeasureRssi`
+    RssiMeasured(u8),
+    /// Events pasted into the serial console, processed as if they just happened
+    Replay(Vec<InputEvent>),
+}
+
+/// All possible actions sent to the tuner.
+enum OutputCommand {
+    SetFrequency(Frequency),
+    SetVolume(u8),
+    /// Seek with increasing frequency, wrapping around at the band end if set
+    SeekUp(bool),
+    /// Seek with decreasing frequency, wrapping around at the band end if set
+    SeekDown(bool),
+    /// Abort the running seek and tune the given frequency, nothing happens without a seek
+    CancelSeek(Frequency),
+    /// Seek through the whole band and collect all stations
+    ScanBand(ScanCriteria),
+    /// Measure the RSSI on each of the frequencies and stay on the strongest
+    TuneStrongest(Vec<Frequency>),
+    /// Seek a short way up and down and tune the station closer to the current frequency
+    FindNearest,
+    /// Average the RSSI of the tuned frequency over a while and report it
+    MeasureRssi,
+    /// Enable or disable RDS decoding
+    SetRds(bool),
+    /// Enable or disable logging of every received RDS group, for debugging
+    SetRdsDump(bool),
+    /// Set the lowest RSSI, at which seek stops on a station
+    SetSeekThreshold(u8),
+    /// Set the offset, which is added to the volume set on the tuner
+    SetVolumeTrim(i8),
+    /// Set, whether the tuner outputs mono or stereo, or decides by the RSSI
+    SetStereoMode(StereoMode),
+    /// Set the band of the tuner, it applies from the next tuned frequency
+    SetBand(Band),
+    /// Set the channel spacing of the tuner, it applies from the next tuned frequency
+    SetSpacing(Spacing),
+    /// Set the de-emphasis of the tuner audio
+    SetDeemphasis(Deemphasis),
+    /// Send the frequency reported by the tuner back through the channel
+    GetFrequency(Sender<Frequency>),
+    /// Write the volume to the tuner again, and report its frequency, RSSI and status,
+    /// even if they did not change, so that the UI matches the chip
+    Resync,
+    /// Execute the command and send its result back through the channel
+    WithReply(Box<OutputCommand>, Sender<CommandResult>),
+}
+
+/// Result of a command executed by the tuner, the error describes the failure.
+type CommandResult = Result<(), Error>;
+
+/// Snapshot of the tuner status flags.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct TunerStatus {
+    /// Stereo pilot is received, debounced over several reads
+    stereo: bool,
+    /// Decoder is synchronized to the RDS stream
+    rds_synchronized: bool,
+    /// Last seek or tune is complete
+    seek_complete: bool,
+    /// Last seek went through the whole band without finding a station
+    seek_failed: bool,
+    /// Tuner outputs mono, forced by the stereo mode
+    mono: bool,
+}
+
+/// Criteria for accepting a station found during a band scan.
+///
+/// Stricter criteria reduce false positives, but make the scan slower.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScanCriteria {
+    /// Accept every station found by seek (RSSI above the seek threshold)
+    Rssi,
+    /// Additionally require a stereo pilot
+    RssiStereo,
+    /// Additionally require RDS data
+    RssiRds,
+}
+
+/// This enum represents all UI elements to be drawn to the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UIElement {
+    SeekDown,
+    FreqControl,
+    SeekUp,
+    /// Preset button with its index
+    Preset(u8),
+    VolumeControl,
+}
+
+/// Direction of tuning or seeking.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    Up,
+    Down,
+}
+
+/// Screens of the user interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Screen {
+    /// Main screen with tuner controls
+    Home,
+    /// List of user settings
+    Settings,
+    /// List of favorite stations
+    Favorites,
+    /// List of recently strong stations
+    Recent,
+    /// Heap and task statistics
+    About,
+    /// Statistics of RDS reception
+    RdsStats,
+    /// Recent Radio Text messages of the tuned station
+    TextHistory,
+    /// Guided calibration of the signal meter
+    Calibration,
+    /// Learning of the codes of an IR remote, one key after another
+    RemoteLearning,
+    /// Histogram of the RSSI of scanned stations across the band
+    Spectrum,
+}
+
+/// Layouts of the home screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HomeLayout {
+    /// Station info below the tuner controls
+    Buttons,
+    /// Analog-style dial with the band, presets and scanned stations, instead of station info
+    Dial,
+    /// Huge frequency and station name, controls are shown only after an input
+    Minimal,
+}
+
+/// Styles of the signal meter, drawn next to the speaker icon.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SignalMeter {
+    /// Only the speaker icon with its static arcs
+    Off,
+    /// Bars of increasing height, lit according to RSSI
+    Bars,
+    /// Arc around the speaker, which fills proportionally to RSSI
+    Arc,
+}
+
+/// Searches for a station started at boot.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BootSearch {
+    /// Stay on the last station
+    Off,
+    /// Seek up to the next station
+    Seek,
+    /// Scan the whole band and tune the strongest station
+    Scan,
+}
+
+/// What the encoder does on the home screen, while no element is selected.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EncoderAction {
+    /// Move the cursor between UI elements
+    Navigate,
+    /// Change the volume, a press starts moving the cursor
+    Volume,
+}
+
+/// What the seek buttons on the home screen do.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SeekButtons {
+    /// Seek to the next station found by the tuner
+    Seek,
+    /// Step by one channel, for when seek stops on noise
+    Step,
+    /// Find the station closest to the tuned frequency, on either side
+    Nearest,
+}
+
+/// What the quick save does, when all presets are stored.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FullPresets {
+    /// Store nothing, the presets stay as they are
+    Refuse,
+    /// Replace the preset, which was recalled or stored the longest time ago
+    Oldest,
+    /// Replace the preset with the weakest signal seen, the oldest of equally weak ones
+    Weakest,
+}
+
+/// Whether the tuner outputs mono or stereo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StereoMode {
+    /// Mono on weak stations, stereo on strong ones
+    Auto,
+    ForceMono,
+    ForceStereo,
+}
+
+/// De-emphasis of the tuner audio, which has to match the pre-emphasis of the region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Deemphasis {
+    /// 50 us, used in Europe and most of the world
+    Us50,
+    /// 75 us, used in the Americas and South Korea
+    Us75,
+}
+
+/// What the second line of station info shows, below the station name.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum InfoLine {
+    /// Radio Text from RDS
+    RadioText,
+    /// Program Type from RDS, e.g. News or Pop music
+    ProgramType,
+    /// Clock time from RDS
+    Clock,
+    /// Time, for which the station has been tuned
+    TunedTime,
+}
+
+/// How frequencies are shown to the user.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Readout {
+    /// Frequency in MHz
+    Mhz,
+    /// Number of the channel in the grid of the band
+    Channel,
+    /// Frequency in MHz on the home screen, both where there is room for them
+    Both,
+}
+
+/// All user settings, in the order in which they are listed on the settings screen.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    ScanCriteria,
+    InvertEncoder,
+    Rds,
+    InfoMinRssi,
+    TunePreview,
+    HomeLayout,
+    PresetVolume,
+    SeekThreshold,
+    SeekReverse,
+    SignalMeter,
+    VolumeTrim,
+    BootSearch,
+    HomeTimeout,
+    MaxVolume,
+    EncoderAction,
+    Brightness,
+    StartupRamp,
+    SeekButtons,
+    StereoMode,
+    SeekConfirm,
+    AutoBrightness,
+    InfoLine,
+    VolumeStep,
+    Readout,
+    MuteButton,
+    AutoOff,
+    ConfirmRecall,
+    FullPresets,
+    Band,
+    Spacing,
+    Deemphasis,
+    /// Not a value, opens the favorites screen
+    Favorites,
+    /// Not a value, opens the list of recently strong stations
+    Recent,
+    /// Not a value, shows the help on the home screen
+    Help,
+    /// Not a value, opens the RDS reception statistics
+    RdsStats,
+    /// Not a value, opens the recent Radio Text messages
+    TextHistory,
+    /// Not a value, starts the calibration of the signal meter
+    CalibrateMeter,
+    /// Not a value, starts learning the keys of an IR remote
+    LearnRemote,
+    /// Not a value, opens the histogram of scanned stations
+    Spectrum,
+    /// Not a value, opens the heap and task statistics
+    About,
+    /// Not a value, arms or extends the sleep timer
+    SleepTimer,
+    /// Not a value, locks the controls
+    Lock,
+    /// Not a value, stores the tuned station into a free preset
+    QuickSave,
+}
+
+/// Number of preset stations available to the user.
+const NUM_PRESETS: u8 = 4;
+
+/// How long the button must be held to store a preset, longer than a long press
+/// so that presets are not overwritten by accident
+const VERY_LONG_PRESS_DURATION: Duration = Duration::from_millis(1500);
+
+/// Pulses per detent of the used rotary encoder
+const ENCODER_PULSES_PER_DETENT: PulsesPerDetent = PulsesPerDetent::Two;
+
+/// Speed of the I2C bus to the tuner
+const TUNER_I2C_BAUDRATE: KiloHertz = KiloHertz(100);
+
+/// Timeout of a single I2C transaction with the tuner
+const TUNER_I2C_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Level of the amplifier enable pin, at which the amplifier is on
+const AMP_ACTIVE_LEVEL: Level = Level::High;
+
+/// How long transient indicators stay on screen after the last input
+const TRANSIENT_DURATION: Duration = Duration::from_millis(150);
+
+/// Interval of the updates of animations, timers and transient indicators,
+/// which run on their own, without any event
+const TICK_INTERVAL: Duration = Duration::from_millis(150);
+
+/// Shortest time between two frames drawn to the display, which limits it
+/// to 20 frames per second, so that bursts of events do not keep the SPI bus busy
+const FRAME_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long scrolling must pause, before a previewed frequency is sent to the tuner
+const TUNE_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// How long without user input before the screensaver starts
+const SCREENSAVER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Interval between frames of the screensaver animation
+const SCREENSAVER_FRAME: Duration = Duration::from_millis(500);
+
+/// How long the controls of the minimal home layout stay shown after the last input
+const CONTROLS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long notifications stay on screen
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(2);
+
+/// Volume, to which the radio fades in at boot, when enabled in settings
+const STARTUP_VOLUME: u8 = 5;
+
+/// How long the fade in at boot takes to reach `STARTUP_VOLUME`
+const STARTUP_RAMP_DURATION: Duration = Duration::from_millis(1500);
+
+/// All user settings, which are stored together in NVS.
+#[derive(Clone, Copy)]
+struct Settings {
+    /// Criteria used when scanning the band
+    scan_criteria: ScanCriteria,
+
+    /// Whether the direction of the rotary encoder is swapped
+    invert_encoder: bool,
+
+    /// Whether RDS is decoded and station info is shown
+    rds_enabled: bool,
+
+    /// Lowest RSSI, at which station info is shown
+    info_min_rssi: u8,
+
+    /// Whether manual tuning only previews the frequency,
+    /// until scrolling pauses for `TUNE_DEBOUNCE`
+    tune_preview: bool,
+
+    /// Layout of the home screen
+    home_layout: HomeLayout,
+
+    /// Whether presets store and recall the volume along with the frequency
+    preset_volume: bool,
+
+    /// Lowest RSSI, at which seek stops on a station
+    seek_threshold: u8,
+
+    /// Whether a seek, which reaches the band end, reverses instead of wrapping around
+    seek_reverse: bool,
+
+    /// Style of the signal meter on the home screen
+    signal_meter: SignalMeter,
+
+    /// Offset added to the volume set on the tuner, the shown volume is not affected
+    volume_trim: i8,
+
+    /// Search for a station started at boot
+    boot_search: BootSearch,
+
+    /// Seconds without input, after which the UI returns to the home screen, zero disables it
+    home_timeout: u8,
+
+    /// Highest volume, which can be set
+    max_volume: u8,
+
+    /// What the encoder does on the home screen by default
+    encoder_action: EncoderAction,
+
+    /// Brightness of the display, an index into `BRIGHTNESS_LEVELS`
+    brightness: u8,
+
+    /// Whether the volume fades in from mute at boot, instead of starting at full level
+    startup_ramp: bool,
+
+    /// Whether the seek buttons seek, step by one channel or find the nearest station
+    seek_buttons: SeekButtons,
+
+    /// Whether the tuner outputs mono or stereo
+    stereo_mode: StereoMode,
+
+    /// Length of the confirmation of a landed seek, an index into `SEEK_CONFIRM_DURATIONS`
+    seek_confirm: u8,
+
+    /// Whether the display brightness follows the ambient light, when a light sensor is wired
+    auto_brightness: bool,
+
+    /// What the second line of station info shows
+    info_line: InfoLine,
+
+    /// Number of volume levels changed by one encoder click, 1 to `MAX_VOLUME_STEP`
+    volume_step: u8,
+
+    /// Whether frequencies are shown in MHz, as channel numbers, or both
+    readout: Readout,
+
+    /// Whether the mute button mutes only while held, instead of toggling the mute
+    mute_hold: bool,
+
+    /// Minutes without user input, after which the radio powers off, zero disables it
+    auto_off: u8,
+
+    /// Whether a preset is only picked by the first press, and tuned by the second
+    confirm_recall: bool,
+
+    /// Which preset the quick save replaces, when all are stored
+    full_presets: FullPresets,
+
+    /// Band, which the tuner receives, and which tuning is limited to
+    band: Band,
+
+    /// Spacing of the channel grid, the step of manual tuning
+    spacing: Spacing,
+
+    /// De-emphasis of the tuner audio
+    deemphasis: Deemphasis,
+}
+
+/// This struct holds the current state of the whole application.
+struct AppState {
+    /// Currently tuned frequency, updated through
+    /// GUI by the user and by the tuner during seek.
+    frequency: Frequency,
+
+    volume: u8,
+
+    /// Whether the mute button silenced the radio, the volume is kept for unmuting
+    muted: bool,
+
+    /// Current station info (Radio Text from RDS),
+    /// updated by the tuner.
+    station_info: String,
+
+    /// Current station name (Program Service name from RDS),
+    /// updated by the tuner.
+    station_name: String,
+
+    /// Whether all segments of the station name were received
+    station_name_complete: bool,
+
+    rssi: u8,
+
+    /// Last status reported by the tuner
+    tuner_status: TunerStatus,
+
+    /// Whether the tuner reported the station as lost, updated by the tuner.
+    signal_lost: bool,
+
+    /// Station tuned before presets started being sampled, while they are
+    sampling_from: Option<Frequency>,
+
+    /// When the sleep timer mutes the radio, if it is armed
+    sleep_at: Option<Instant>,
+
+    /// Countdown of the sleep timer, which was last drawn
+    sleep_countdown_shown: Option<String>,
+
+    /// Warning of the auto-off, which was last drawn
+    auto_off_countdown_shown: Option<String>,
+
+    /// Frequency, from which the running seek started, while there is one
+    seek_from: Option<Frequency>,
+
+    /// Station tuned before the last station picked by the user, a double press
+    /// on a seek button flips back to it
+    prev_frequency: Option<Frequency>,
+
+    /// When the volume started fading in at boot, until it reaches the startup volume
+    volume_ramp: Option<Instant>,
+
+    /// Display brightness fitting the ambient light, reported by the light sensor
+    ambient_brightness: Option<u8>,
+
+    /// Current Program Type code from RDS, updated by the tuner
+    program_type: u8,
+
+    /// Whether RDS is received from the tuned station, updated by the tuner
+    rds_reception: RdsReception,
+
+    /// RSSI of the noise floor and of a strong station, between which the signal meter fills
+    meter_range: (u8, u8),
+
+    /// Running calibration of the signal meter
+    calibration: Option<Calibration>,
+
+    /// Finished Radio Text messages of the tuned station, newest first, updated by the tuner
+    text_history: Vec<String>,
+
+    /// Last clock time from RDS, in minutes since the local midnight, and when it was received
+    clock: Option<(u16, Instant)>,
+
+    /// When the station info lines started scrolling from their start
+    info_scroll_from: Instant,
+
+    /// Station tuned since the last change of the frequency, and since when,
+    /// seeks are left to finish before their frequency counts
+    tuned_since: Option<(Frequency, Instant)>,
+
+    /// Whether the tuner was not found at startup, the error is then shown over every screen
+    tuner_missing: bool,
+
+    /// Whether the help overlay with the gestures of the current screen is shown
+    show_help: bool,
+
+    /// Parts of the station info lines, which were last drawn, they change as the lines scroll
+    info_shown: (String, String),
+
+    /// Frequencies of preset stations, cached from NVS
+    presets: [Option<Frequency>; NUM_PRESETS as usize],
+
+    /// Volumes stored with preset stations, cached from NVS
+    preset_volumes: [Option<u8>; NUM_PRESETS as usize],
+
+    /// RDS names of preset stations, cached from NVS
+    preset_names: [Option<String>; NUM_PRESETS as usize],
+
+    /// When a preset name was last updated from RDS, the updates are throttled
+    preset_name_saved: Option<Instant>,
+
+    /// NEC codes of the keys of the IR remote, in the order of `RemoteKey::ALL`,
+    /// zero for keys which are not mapped, cached from NVS
+    remote_codes: [u32; REMOTE_KEYS],
+
+    /// Key of the IR remote, which was pressed last, repeats of it are for this key
+    remote_held: Option<RemoteKey>,
+
+    /// Key of the IR remote being learned, with the codes learned so far
+    remote_learning: Option<(usize, [u32; REMOTE_KEYS])>,
+
+    /// When presets were last recalled or stored, as a count of all uses of presets
+    /// up to then, the highest is the most recent, cached from NVS
+    preset_uses: [u32; NUM_PRESETS as usize],
+
+    /// Stations found by the last band scan, with the RSSI measured on them
+    stations: Vec<(Frequency, u8)>,
+
+    /// Strongest stations seen recently while tuning
+    recent_stations: RecentStations,
+
+    /// Stations marked as favorite by the user, in increasing order
+    favorites: Vec<Frequency>,
+
+    /// User settings, stored in NVS
+    settings: Settings,
+
+    /// Values waiting to be written to NVS, once they stop changing
+    pending_writes: DebouncedWriter,
+
+    /// Frequency and volume last staged for storing, restored at boot
+    stored_station: (Frequency, u8),
+
+    /// Time after which the previewed frequency is sent to the tuner
+    pending_tune: Option<Instant>,
+
+    /// Whether the controls are hidden in the minimal home layout
+    controls_hidden: bool,
+
+    /// Currently displayed screen
+    screen: Screen,
+
+    /// UI element, on which the cursor is currently located
+    cursor_at: UIElement,
+
+    /// Whether the current UI element is selected for manipulation
+    /// (valid only for frequency and volume control, otherwise always false)
+    element_is_active: bool,
+
+    /// UI element, from which the cursor jumped to the volume control,
+    /// the cursor returns there once the volume control is deselected
+    volume_jump_from: Option<UIElement>,
+
+    /// Direction, in which the volume was last scrolled, holding the button keeps changing it
+    volume_direction: Option<Direction>,
+
+    /// Whether the running scan was started at boot, it then tunes the strongest station
+    boot_scan: bool,
+
+    /// Seek button, from which the last seek was started, and the direction of that seek
+    last_seek: Option<(UIElement, Direction)>,
+
+    /// Whether the last seek was started automatically, after the previous one reached the band end
+    seek_reversed: bool,
+
+    /// Whether the controls are locked against accidental changes
+    locked: bool,
+
+    /// Number of correctly entered inputs of the unlock sequence
+    unlock_progress: usize,
+
+    /// Direction of the last manual tuning step, shown briefly
+    /// by flashing the seek button in that direction
+    tune_indicator: Option<Direction>,
+
+    /// Short message shown over the GUI until the given time
+    notification: Option<(String, Instant)>,
+
+    /// Whether the screensaver is shown instead of the current screen
+    screensaver: bool,
+
+    /// Time of the last user input, starts the screensaver after a while
+    last_input: Instant,
+
+    /// Time of the last frame of the screensaver animation
+    last_frame: Instant,
+
+    /// Time, when the statistics on the about screen were last redrawn
+    about_refreshed: Instant,
+
+    /// Recent RSSI samples, animated by the screensaver
+    rssi_history: VecDeque<u8>,
+
+    /// Index of the setting, on which the cursor is located on the settings screen
+    settings_cursor: usize,
+
+    /// Index of the favorite, on which the cursor is located on the favorites screen
+    favorites_cursor: usize,
+
+    /// Index of the station, on which the cursor is located on the recently strong screen
+    recent_cursor: usize,
+
+    /// Index of the row, on which the cursor is located on the RDS statistics screen
+    rds_stats_cursor: usize,
+
+    /// Index of the message shown on the Radio Text screen, 0 is the one being received
+    text_history_cursor: usize,
+
+    /// Index of the scanned station, on which the cursor is located on the spectrum screen
+    spectrum_cursor: usize,
+}
+
+/// Entry point of the firmware, called by the binary.
+pub fn main() {
+    // It is necessary to call this function once. Otherwise some patches to the runtime
+    // implemented by esp-idf-sys might not link properly. See https://github.com/esp-rs/esp-idf-template/issues/71
+    esp_idf_svc::sys::link_patches();
+
+    // Bind the log crate to the ESP Logging facilities
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    // mute the radio and show the error, if any thread panics
+    install_panic_hook();
+
+    if let Err(err) = start() {
+        log::error!("Startup failed, restarting: {err}");
+        turn_off_amp();
+        unsafe { esp_restart() };
+    }
+}
+
+/// Initializes all peripherals, spawns the worker threads and runs the event loop.
+///
+/// Returns an error, if a peripheral needed by the radio fails to initialize.
+fn start() -> Result<(), Error> {
+    // initialize nonvolatile storage
+    let nvs_partition = boot_step(
+        "NVS partition taken",
+        EspDefaultNvsPartition::take().map_err(Error::Nvs),
+    )?;
+    let mut nvs = boot_step(
+        "NVS opened",
+        EspNvs::new(nvs_partition, "namespace", true).map_err(Error::Nvs),
+    )?;
+
+    // initialize peripherals, pins are assigned in `PIN_CONFIG`
+    let peripherals = boot_step("peripherals taken", Peripherals::take())?;
+
+    // create channels for sending inputs and outputs
+    let (event_sender, event_receiver) = channel::<InputEvent>();
+    let (command_sender, command_receiver) = channel::<OutputCommand>();
+
+    // setup listener for button presses
+    let button = boot_step(
+        "button pin set",
+        PinDriver::input(input_pin(PIN_CONFIG.button)).map_err(Error::Gpio),
+    )?;
+
+    // holding the button during startup starts the self-test
+    let self_test = button.is_low();
+
+    // the button is integrated in the encoder, their listeners coordinate through the link
+    let encoder_link = EncoderLink::new();
+    spawn_button_listener(
+        button,
+        ButtonSource::Encoder,
+        VERY_LONG_PRESS_DURATION,
+        Some(encoder_link.clone()),
+        event_sender.clone(),
+    );
+
+    // the back button is optional, only some builds have it
+    if let Some(pin) = PIN_CONFIG.back_button {
+        let back_button = boot_step(
+            "back button pin set",
+            PinDriver::input(input_pin(pin)).map_err(Error::Gpio),
+        )?;
+        spawn_button_listener(
+            back_button,
+            ButtonSource::Back,
+            VERY_LONG_PRESS_DURATION,
+            None,
+            event_sender.clone(),
+        );
+    }
+
+    // the mute button is optional as well
+    if let Some(pin) = PIN_CONFIG.mute_button {
+        let mute_button = boot_step(
+            "mute button pin set",
+            PinDriver::input(input_pin(pin)).map_err(Error::Gpio),
+        )?;
+        spawn_mute_listener(mute_button, event_sender.clone());
+    }
+
+    // and so is the IR receiver
+    if let Some(pin) = PIN_CONFIG.ir_receiver {
+        let receiver = boot_step(
+            "IR receiver set",
+            remote_receiver(peripherals.rmt.channel0, input_pin(pin)).map_err(Error::Esp),
+        )?;
+        spawn_remote_listener(receiver, event_sender.clone());
+    }
+
+    // setup listener for rotary encoder inputs
+    boot_step(
+        "encoder pins set",
+        spawn_encoder_listener(
+            input_pin(PIN_CONFIG.encoder_s1),
+            input_pin(PIN_CONFIG.encoder_s2),
+            ENCODER_PULSES_PER_DETENT,
+            encoder_link,
+            event_sender.clone(),
+        ),
+    )?;
+
+    // worker threads are watched, so that a hung one restarts the device
+    let tuner_heartbeat = Heartbeat::new();
+    let event_loop_heartbeat = Heartbeat::new();
+    spawn_watchdog(vec![
+        ("tuner", tuner_heartbeat.clone()),
+        ("event loop", event_loop_heartbeat.clone()),
+    ]);
+
+    // setup commands over the serial console
+    spawn_console(command_sender.clone(), event_sender.clone());
+
+    // setup the light sensor, without it the display keeps the brightness from settings
+    if let Some(pin) = PIN_CONFIG.ambient_light {
+        if let Err(err) = spawn_ambient_thread(pin, event_sender.clone()) {
+            log::warn!("Light sensor could not be set up: {err}");
+        }
+    }
+
+    // setup RDA5807M tuner
+    boot_step(
+        "tuner thread spawned",
+        spawn_tuner_thread(
+            peripherals.i2c0,
+            io_pin(PIN_CONFIG.tuner_sda),
+            io_pin(PIN_CONFIG.tuner_scl),
+            TUNER_I2C_BAUDRATE,
+            TUNER_I2C_TIMEOUT,
+            // change for boards, whose tuner needs another address
+            Address::default(),
+            PIN_CONFIG.amp_enable.map(output_pin),
+            AMP_ACTIVE_LEVEL,
+            tuner_heartbeat,
+            event_sender,
+            command_receiver,
+        ),
+    )?;
+
+    // setup the output confirming landed seeks, the radio works without it
+    if let Some(pin) = PIN_CONFIG.seek_confirm {
+        if let Err(err) = spawn_confirm_thread(pin) {
+            log::warn!("Seek confirmation pin could not be set up: {err}");
+        }
+    }
+
+    // setup status LED
+    // the radio works without it, so a failure is only logged
+    if let Some(pin) = PIN_CONFIG.status_led {
+        if let Err(err) = spawn_led_thread(pin) {
+            log::warn!("Status LED could not be set up: {err}");
+        }
+    }
+
+    // initialize application state, settings are needed already by the display
+    let mut state = AppState::new();
+    state.load_settings(&mut nvs);
+    state.restore_station(load_last_station(&nvs));
+
+    // setup SSD1306 display
+    let mut display = setup_display(
+        peripherals.spi3,
+        io_pin(PIN_CONFIG.display_sclk),
+        io_pin(PIN_CONFIG.display_sdo),
+        output_pin(PIN_CONFIG.display_dc),
+        output_pin(PIN_CONFIG.display_reset),
+        BRIGHTNESS_LEVELS[state.settings.brightness as usize],
+    )
+    .unwrap_or_else(|err| {
+        log::error!("Display could not be set up, running without display: {err}");
+        None
+    });
+
+    state.presets = load_presets(&nvs);
+    state.preset_volumes = load_preset_volumes(&nvs);
+    state.preset_names = load_preset_names(&nvs);
+    state.preset_uses = load_preset_uses(&nvs);
+    state.remote_codes = load_remote_codes(&nvs);
+    state.stations = load_stations(&nvs);
+    state.favorites = load_frequencies(&nvs, FAVORITES_KEY);
+    if let Some(range) = load_meter_range(&nvs) {
+        state.meter_range = range;
+    }
+
+    log::info!(
+        "boot: restored {} presets, {} stations and {} favorites, frequency {} kHz, volume {}",
+        state.presets.iter().flatten().count(),
+        state.stations.len(),
+        state.favorites.len(),
+        state.frequency.as_khz(),
+        state.volume,
+    );
+
+    // apply stored settings and the restored station to the tuner
+    state.apply_settings(&command_sender);
+    command_sender.send(OutputCommand::SetFrequency(state.frequency))?;
+    command_sender.send(OutputCommand::SetVolume(state.volume))?;
+
+    if self_test {
+        run_self_test(
+            &mut state,
+            display.as_deref_mut(),
+            &command_sender,
+            &event_receiver,
+            &mut nvs,
+            &event_loop_heartbeat,
+        );
+    }
+
+    state.reset_encoder_action();
+    state.start_volume_ramp(&command_sender);
+    state.start_boot_search(&command_sender);
+
+    run(
+        state,
+        event_receiver,
+        command_sender,
+        display.as_deref_mut(),
+        nvs,
+        event_loop_heartbeat,
+    );
+    Ok(())
+}
+
+/// Runs the event loop, until all event senders are dropped. Waits for next input event,
+/// processes it, and updates GUI, at most once per `FRAME_INTERVAL`, when there is
+/// no input for a while, transient indicators are removed. Timers and animations tick
+/// every `TICK_INTERVAL`. Without a display the radio runs headless.
+///
+/// When the auto-off runs out, the display is turned off and the radio powers off.
+fn run<D: Panel>(
+    mut state: AppState,
+    event_receiver: Receiver<InputEvent>,
+    command_sender: Sender<OutputCommand>,
+    mut display: Option<&mut D>,
+    mut nvs: EspNvs<NvsDefault>,
+    heartbeat: Heartbeat,
+) where
+    D::Error: Debug,
+{
+    // brightness set on the display, a new one is applied before redrawing
+    let mut brightness = state.settings.brightness;
+
+    let mut redraw = |state: &AppState, display: Option<&mut D>| {
+        if let Some(display) = display {
+            // a failed frame is only logged, the next one is drawn anew
+            if state.display_brightness() != brightness {
+                brightness = state.display_brightness();
+                if let Err(err) = display.set_brightness(BRIGHTNESS_LEVELS[brightness as usize]) {
+                    log::warn!("Display brightness not set: {}", Error::display(err));
+                }
+            }
+            if let Err(err) = state.update_ui(display).and_then(|()| display.flush()) {
+                log::warn!("Display not updated: {}", Error::display(err));
+            }
+        }
+    };
+
+    // draw GUI
+    state.publish_snapshot();
+    redraw(&state, display.as_deref_mut());
+
+    // frames are coalesced, a change is drawn once `FRAME_INTERVAL` passed since the last frame
+    let mut last_frame = Instant::now();
+    let mut changed = false;
+    // periodic updates tick steadily, however many events arrive in between
+    let mut next_tick = Instant::now() + TICK_INTERVAL;
+    let mut last_event = Instant::now();
+
+    let powering_off = loop {
+        heartbeat.feed();
+        let write_failed = state.write_pending(&mut nvs);
+
+        // the radio was left on without input for too long
+        if state.auto_off_due() {
+            break true;
+        }
+
+        // another thread panicked, keep the error shown until the hook aborts
+        if panicked() {
+            state.flush_pending(&mut nvs);
+            state.screensaver = false;
+            state.notification = Some(("Error, restarting".to_string(), Instant::now()));
+            redraw(&state, display.as_deref_mut());
+            loop {
+                std::thread::park();
+            }
+        }
+
+        // wait for the next event, at most until the next tick, or the postponed frame
+        let mut deadline = next_tick;
+        if changed {
+            deadline = deadline.min(last_frame + FRAME_INTERVAL);
+        }
+        match event_receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            // every replayed event gets its own frame, so that a glitch can be watched
+            Ok(InputEvent::Replay(events)) => {
+                let display = display.as_deref_mut();
+                if let Err(err) =
+                    replay_events(&mut state, events, &command_sender, &mut nvs, display)
+                {
+                    log::warn!("Display not updated: {}", Error::display(err));
+                }
+                last_event = Instant::now();
+                changed = true;
+            }
+            Ok(event) => {
+                record_event(&event);
+                state.process_event(event, &command_sender, &mut nvs);
+                last_event = Instant::now();
+                changed = true;
+            }
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => break false,
+        }
+
+        if Instant::now() >= next_tick {
+            next_tick = Instant::now() + TICK_INTERVAL;
+            state.send_pending_tune(&command_sender);
+            // transient indicators stay, while events keep coming
+            let cleared = last_event.elapsed() >= TRANSIENT_DURATION && state.clear_transients();
+            state.update_tuned_since();
+            let about = state.update_about();
+            let home = state.return_home_when_idle();
+            let sleep = state.update_sleep_timer(&command_sender);
+            let ramp = state.update_volume_ramp(&command_sender);
+            let info = state.update_info_scroll();
+            let auto_off = state.update_auto_off();
+            changed |= state.update_screensaver()
+                || cleared
+                || about
+                || home
+                || sleep
+                || ramp
+                || info
+                || auto_off;
+        }
+        changed |= write_failed;
+
+        if !changed || last_frame.elapsed() < FRAME_INTERVAL {
+            continue;
+        }
+        changed = false;
+        last_frame = Instant::now();
+        set_led_status(state.led_status());
+        state.publish_snapshot();
+        redraw(&state, display.as_deref_mut());
+    };
+
+    state.flush_pending(&mut nvs);
+
+    if powering_off {
+        if let Some(display) = display {
+            if let Err(err) = display.set_display_on(false) {
+                log::warn!("Display not turned off: {}", Error::display(err));
+            }
+        }
+        power_off(&command_sender);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gui::tests::MockDisplay;
+
+    #[test]
+    fn event_loop_processes_events_until_senders_are_dropped() {
+        let mut state = AppState::new();
+        state.volume = 8;
+        let (event_sender, event_receiver) = channel();
+        let (command_sender, command_receiver) = channel();
+        let nvs = EspNvs::new(EspDefaultNvsPartition::take().unwrap(), "test", true).unwrap();
+        let mut display = MockDisplay::default();
+
+        // the mute button toggles the mute
+        for event in [
+            InputEvent::MuteDown,
+            InputEvent::MuteUp,
+            InputEvent::MuteDown,
+        ] {
+            event_sender.send(event).unwrap();
+        }
+        drop(event_sender);
+
+        run(
+            state,
+            event_receiver,
+            command_sender,
+            Some(&mut display),
+            nvs,
+            Heartbeat::new(),
+        );
+
+        let volumes: Vec<_> = command_receiver
+            .try_iter()
+            .filter_map(|command| match command {
+                OutputCommand::SetVolume(volume) => Some(volume),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(volumes, [0, 8]);
+        assert!(display.flushed > 0);
+        assert!(display.drawn > 0);
+    }
+
+    #[test]
+    fn replayed_events_are_drawn_one_by_one() {
+        let (event_sender, event_receiver) = channel();
+        let (command_sender, _command_receiver) = channel();
+        let nvs = EspNvs::new(EspDefaultNvsPartition::take().unwrap(), "test", true).unwrap();
+        let mut display = MockDisplay::default();
+
+        let events = vec![
+            InputEvent::ScrollUp,
+            InputEvent::ScrollUp,
+            InputEvent::ScrollDown,
+        ];
+        event_sender.send(InputEvent::Replay(events)).unwrap();
+        drop(event_sender);
+
+        run(
+            AppState::new(),
+            event_receiver,
+            command_sender,
+            Some(&mut display),
+            nvs,
+            Heartbeat::new(),
+        );
+
+        // the first frame is drawn before any event
+        assert!(display.flushed > 3);
+    }
+}
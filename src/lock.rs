@@ -0,0 +1,52 @@
+use crate::{AppState, InputEvent, Screen, UIElement};
+
+/// Inputs which unlock the controls, when entered in this order
+const UNLOCK_SEQUENCE: [InputEvent; 4] = [
+    InputEvent::LongPress,
+    InputEvent::ScrollUp,
+    InputEvent::ScrollDown,
+    InputEvent::ScrollUp,
+];
+
+impl AppState {
+    /// Locks all controls, until the unlock sequence is entered.
+    pub fn lock(&mut self) {
+        self.locked = true;
+        self.unlock_progress = 0;
+
+        // leave the controls in a neutral state
+        self.screen = Screen::Home;
+        self.cursor_at = UIElement::SeekDown;
+        self.element_is_active = false;
+        self.volume_jump_from = None;
+
+        self.notify("Locked");
+    }
+
+    /// Processes user input while the controls are locked,
+    /// all inputs are ignored, except for the unlock sequence.
+    pub fn process_locked_input(&mut self, event: InputEvent) {
+        // holding the button is part of the long press of the sequence
+        if event == InputEvent::Hold {
+            return;
+        }
+
+        let event = match event {
+            InputEvent::VeryLongPress => InputEvent::LongPress,
+            event => event,
+        };
+
+        if event == UNLOCK_SEQUENCE[self.unlock_progress] {
+            self.unlock_progress += 1;
+        } else {
+            // a wrong input may still be the start of a new attempt
+            self.unlock_progress = (event == UNLOCK_SEQUENCE[0]) as usize;
+        }
+
+        if self.unlock_progress == UNLOCK_SEQUENCE.len() {
+            self.locked = false;
+            self.unlock_progress = 0;
+            self.notify("Unlocked");
+        }
+    }
+}
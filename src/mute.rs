@@ -0,0 +1,38 @@
+use std::sync::mpsc::Sender;
+
+use crate::{tuner::send_command, AppState, OutputCommand};
+
+impl AppState {
+    /// Returns the volume, which the tuner should play at, zero while muted.
+    pub fn output_volume(&self) -> u8 {
+        if self.muted {
+            0
+        } else {
+            self.volume
+        }
+    }
+
+    /// Mutes or unmutes the radio, keeping the volume to return to.
+    pub fn set_muted(&mut self, muted: bool, command: &Sender<OutputCommand>) {
+        if muted == self.muted {
+            return;
+        }
+        self.muted = muted;
+        send_command(command, OutputCommand::SetVolume(self.output_volume()));
+        self.notify(if muted { "Muted" } else { "Unmuted" });
+    }
+
+    /// Processes a press of the mute button, which toggles the mute,
+    /// or mutes until the release, when the button is set to hold.
+    pub fn mute_down(&mut self, command: &Sender<OutputCommand>) {
+        let muted = self.settings.mute_hold || !self.muted;
+        self.set_muted(muted, command);
+    }
+
+    /// Processes a release of the mute button, which unmutes, when the button is set to hold.
+    pub fn mute_up(&mut self, command: &Sender<OutputCommand>) {
+        if self.settings.mute_hold {
+            self.set_muted(false, command);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use esp_idf_svc::{
+    nvs::{EspNvs, NvsDefault},
+    sys::EspError,
+};
+use std::time::{Duration, Instant};
+
+use crate::{
+    double_buffer::write_double_buffered,
+    frequency::Frequency,
+    storage::{double_buffered_keys, LAST_FREQUENCY_KEY, LAST_VOLUME_KEY},
+    AppState,
+};
+
+/// How long no value has to change, before the pending ones are written
+const QUIET_PERIOD: Duration = Duration::from_secs(5);
+
+/// Batches writes of values, which change often, into one write after they settle,
+/// so that e.g. turning the volume through many levels writes the flash only once.
+///
+/// Values are stored as blobs under their keys, a newer value of a pending key
+/// replaces the older one. Values, which must survive a power loss during the write,
+/// are kept in two copies instead, see `double_buffered_keys`.
+pub struct DebouncedWriter {
+    /// Values waiting to be written, with their keys
+    pending: Vec<(&'static str, Vec<u8>)>,
+    /// When the last value was staged
+    changed_at: Instant,
+    /// Whether the pending values should be written without waiting for the quiet period
+    flush_requested: bool,
+}
+
+impl DebouncedWriter {
+    pub fn new() -> Self {
+        DebouncedWriter {
+            pending: Vec::new(),
+            changed_at: Instant::now(),
+            flush_requested: false,
+        }
+    }
+
+    /// Stages the value to be written under the key, restarts the quiet period.
+    pub fn stage(&mut self, key: &'static str, value: Vec<u8>) {
+        match self.pending.iter_mut().find(|(pending, _)| *pending == key) {
+            Some((_, pending)) => *pending = value,
+            None => self.pending.push((key, value)),
+        }
+        self.changed_at = Instant::now();
+    }
+
+    /// Makes the next `write_due` write the pending values at once,
+    /// called before the radio goes quiet, so that nothing pending is lost.
+    pub fn request_flush(&mut self) {
+        self.flush_requested = true;
+    }
+
+    /// Writes the pending values, once they settled for `QUIET_PERIOD` or a flush
+    /// was requested. Values that fail are dropped, the first error is returned.
+    pub fn write_due(&mut self, nvs: &mut EspNvs<NvsDefault>) -> Result<(), EspError> {
+        if self.pending.is_empty()
+            || !(self.flush_requested || self.changed_at.elapsed() >= QUIET_PERIOD)
+        {
+            return Ok(());
+        }
+        self.flush(nvs)
+    }
+
+    /// Writes all pending values right away.
+    pub fn flush(&mut self, nvs: &mut EspNvs<NvsDefault>) -> Result<(), EspError> {
+        self.flush_requested = false;
+        let mut result = Ok(());
+        for (key, value) in self.pending.drain(..) {
+            let written = match double_buffered_keys(key) {
+                Some(keys) => write_double_buffered(nvs, keys, &value),
+                None => nvs.set_raw(key, &value).map(|_| ()),
+            };
+            if let Err(err) = written {
+                log::warn!("Writing {key} to NVS failed: {err:?}");
+                result = result.and(Err(err));
+            }
+        }
+        result
+    }
+}
+
+impl AppState {
+    /// Stages the tuned station and volume, when they changed since they were last staged,
+    /// then writes the pending values, which are due, returns whether a write failed,
+    /// in which case the GUI should be redrawn to show it.
+    ///
+    /// The volume is not kept while muted or fading in, so that a radio muted
+    /// by the sleep timer does not wake up muted.
+    pub fn write_pending(&mut self, nvs: &mut EspNvs<NvsDefault>) -> bool {
+        if self.frequency != self.stored_station.0 {
+            self.stored_station.0 = self.frequency;
+            let khz = self.frequency.as_khz().to_le_bytes().to_vec();
+            self.pending_writes.stage(LAST_FREQUENCY_KEY, khz);
+        }
+        if self.volume != self.stored_station.1 && self.volume > 0 && self.volume_ramp.is_none() {
+            self.stored_station.1 = self.volume;
+            self.pending_writes
+                .stage(LAST_VOLUME_KEY, vec![self.volume]);
+        }
+
+        match self.pending_writes.write_due(nvs) {
+            Ok(()) => false,
+            Err(err) => {
+                self.save_failed(err);
+                true
+            }
+        }
+    }
+
+    /// Writes everything pending at once, called before the event loop stops.
+    pub fn flush_pending(&mut self, nvs: &mut EspNvs<NvsDefault>) {
+        self.pending_writes.request_flush();
+        self.write_pending(nvs);
+    }
+
+    /// Restores the station and volume tuned before the last power off, if any were stored.
+    pub fn restore_station(&mut self, station: (Option<Frequency>, Option<u8>)) {
+        if let Some(frequency) = station.0 {
+            self.frequency = frequency.clamped();
+        }
+        if let Some(volume) = station.1 {
+            self.volume = volume.min(self.settings.max_volume);
+        }
+        self.stored_station = (self.frequency, self.volume);
+    }
+}
@@ -0,0 +1,56 @@
+use esp_idf_svc::{hal::gpio::Level, sys::gpio_set_level};
+use std::{
+    panic,
+    sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering},
+    thread,
+    time::Duration,
+};
+
+/// How long a panicking thread waits before aborting,
+/// so that the other threads can mute the tuner and show the error
+const PANIC_GRACE_PERIOD: Duration = Duration::from_millis(300);
+
+/// Set once any thread panics
+static PANICKED: AtomicBool = AtomicBool::new(false);
+
+/// GPIO number of the amplifier enable pin, negative without an amplifier
+static AMP_PIN: AtomicI32 = AtomicI32::new(-1);
+
+/// Level of the amplifier enable pin, at which the amplifier is off
+static AMP_OFF_LEVEL: AtomicU32 = AtomicU32::new(0);
+
+/// Returns whether some thread panicked, threads which own peripherals
+/// should then leave them in a safe state and stop.
+pub fn panicked() -> bool {
+    PANICKED.load(Ordering::Relaxed)
+}
+
+/// Registers the amplifier enable pin, so that the amplifier can be turned off
+/// on panic even when the thread which owns the pin is the one panicking.
+pub fn register_amp_pin(pin: i32, off_level: Level) {
+    AMP_OFF_LEVEL.store((off_level == Level::High) as u32, Ordering::Relaxed);
+    AMP_PIN.store(pin, Ordering::Relaxed);
+}
+
+/// Turns off the amplifier from any thread, even when the pin driver is owned by another.
+pub fn turn_off_amp() {
+    let pin = AMP_PIN.load(Ordering::Relaxed);
+    if pin >= 0 {
+        // setting the level of a pin configured as output cannot fail
+        unsafe { gpio_set_level(pin, AMP_OFF_LEVEL.load(Ordering::Relaxed)) };
+    }
+}
+
+/// Installs a panic hook, which turns off the amplifier and gives the other threads
+/// time to mute the tuner and show the error, before the default hook aborts.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        PANICKED.store(true, Ordering::Relaxed);
+        turn_off_amp();
+
+        thread::sleep(PANIC_GRACE_PERIOD);
+        default_hook(info);
+    }));
+}
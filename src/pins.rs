@@ -0,0 +1,143 @@
+use esp_idf_svc::hal::gpio::{AnyIOPin, AnyInputPin, AnyOutputPin};
+
+/// GPIO numbers of all pins used by the radio, change these to match the board.
+pub const PIN_CONFIG: PinConfig = PinConfig {
+    button: 17,
+    back_button: None,
+    mute_button: None,
+    encoder_s1: 25,
+    encoder_s2: 26,
+    tuner_sda: 21,
+    tuner_scl: 22,
+    amp_enable: Some(27),
+    display_sclk: 18,
+    display_sdo: 23,
+    display_dc: 13,
+    display_reset: 12,
+    status_led: Some(2),
+    seek_confirm: None,
+    ambient_light: None,
+    ir_receiver: None,
+}
+.validated();
+
+/// Assignment of GPIO pins to the connected hardware.
+pub struct PinConfig {
+    /// Button of the rotary encoder, active low
+    pub button: i32,
+    /// Dedicated back button, active low, `None` on builds without one
+    pub back_button: Option<i32>,
+    /// Mute button, active low, `None` on builds without one
+    pub mute_button: Option<i32>,
+    /// First output of the rotary encoder
+    pub encoder_s1: i32,
+    /// Second output of the rotary encoder
+    pub encoder_s2: i32,
+    /// I2C data line of the tuner
+    pub tuner_sda: i32,
+    /// I2C clock line of the tuner
+    pub tuner_scl: i32,
+    /// Enable pin of the amplifier, `None` on builds without one
+    pub amp_enable: Option<i32>,
+    /// SPI clock of the display
+    pub display_sclk: i32,
+    /// SPI data of the display
+    pub display_sdo: i32,
+    /// Data/command select of the display
+    pub display_dc: i32,
+    /// Reset of the display
+    pub display_reset: i32,
+    /// Status LED, active high, `None` on builds without one
+    pub status_led: Option<i32>,
+    /// Buzzer or LED pulsed when a seek lands, active high, `None` on builds without one
+    pub seek_confirm: Option<i32>,
+    /// ADC input of the light sensor, `None` on builds without one
+    pub ambient_light: Option<i32>,
+    /// Output of the IR receiver for a remote, active low, `None` on builds without one
+    pub ir_receiver: Option<i32>,
+}
+
+impl PinConfig {
+    /// Checks that no pin is assigned twice, `PIN_CONFIG` then fails to compile.
+    const fn validated(self) -> Self {
+        let amp_enable = match self.amp_enable {
+            Some(pin) => pin,
+            None => -1,
+        };
+        let back_button = match self.back_button {
+            Some(pin) => pin,
+            None => -1,
+        };
+        let mute_button = match self.mute_button {
+            Some(pin) => pin,
+            None => -1,
+        };
+        let status_led = match self.status_led {
+            Some(pin) => pin,
+            None => -1,
+        };
+        let seek_confirm = match self.seek_confirm {
+            Some(pin) => pin,
+            None => -1,
+        };
+        let ambient_light = match self.ambient_light {
+            Some(pin) => pin,
+            None => -1,
+        };
+        let ir_receiver = match self.ir_receiver {
+            Some(pin) => pin,
+            None => -1,
+        };
+        let pins = [
+            self.button,
+            back_button,
+            mute_button,
+            self.encoder_s1,
+            self.encoder_s2,
+            self.tuner_sda,
+            self.tuner_scl,
+            amp_enable,
+            self.display_sclk,
+            self.display_sdo,
+            self.display_dc,
+            self.display_reset,
+            status_led,
+            seek_confirm,
+            ambient_light,
+            ir_receiver,
+        ];
+
+        let mut i = 0;
+        while i < pins.len() {
+            let mut j = i + 1;
+            while j < pins.len() {
+                if pins[i] >= 0 && pins[i] == pins[j] {
+                    panic!("a GPIO pin is assigned twice in the pin configuration");
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+
+        self
+    }
+}
+
+// Pins are created from their numbers, which is sound as long as every pin
+// is created only once and the pins of `Peripherals` are not used, `PIN_CONFIG`
+// guarantees the former and this module is the only place creating pins.
+
+/// creates the pin with the given number for use as an input
+pub fn input_pin(pin: i32) -> AnyInputPin {
+    unsafe { AnyInputPin::new(pin) }
+}
+
+/// creates the pin with the given number for use as an output
+pub fn output_pin(pin: i32) -> AnyOutputPin {
+    unsafe { AnyOutputPin::new(pin) }
+}
+
+/// creates the pin with the given number for use as both input and output
+pub fn io_pin(pin: i32) -> AnyIOPin {
+    unsafe { AnyIOPin::new(pin) }
+}
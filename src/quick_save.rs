@@ -0,0 +1,80 @@
+use esp_idf_svc::nvs::{EspNvs, NvsDefault};
+
+use crate::{frequency::Frequency, storage::PRESET_USES_KEY, AppState, FullPresets, NUM_PRESETS};
+
+impl AppState {
+    /// Notes that the preset was recalled or stored, for finding the oldest one.
+    pub fn mark_preset_used(&mut self, preset: usize) {
+        let latest = self.preset_uses.iter().max().copied().unwrap_or(0);
+        self.preset_uses[preset] = latest.saturating_add(1);
+
+        let blob = self
+            .preset_uses
+            .iter()
+            .flat_map(|uses| uses.to_le_bytes())
+            .collect();
+        self.pending_writes.stage(PRESET_USES_KEY, blob);
+    }
+
+    /// Returns the strongest RSSI seen on the frequency, by the last scan
+    /// or among the recently strong stations, zero when it was not seen.
+    fn seen_rssi(&self, freq: Frequency) -> u8 {
+        self.stations
+            .iter()
+            .chain(self.recent_stations.stations())
+            .filter(|(station, _)| *station == freq)
+            .map(|&(_, rssi)| rssi)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the preset, which the quick save replaces when all presets are stored,
+    /// as picked in settings.
+    fn preset_to_replace(&self) -> Option<usize> {
+        let presets = 0..NUM_PRESETS as usize;
+        match self.settings.full_presets {
+            FullPresets::Refuse => None,
+            FullPresets::Oldest => presets.min_by_key(|&preset| self.preset_uses[preset]),
+            FullPresets::Weakest => presets.min_by_key(|&preset| {
+                let rssi = self.presets[preset].map_or(0, |freq| self.seen_rssi(freq));
+                (rssi, self.preset_uses[preset])
+            }),
+        }
+    }
+
+    /// Stores the tuned station into the first empty preset, or replaces one,
+    /// when all are stored, and shows which one it was.
+    pub fn quick_save(&mut self, nvs: &mut EspNvs<NvsDefault>) {
+        if let Some(preset) = self
+            .presets
+            .iter()
+            .position(|&freq| freq == Some(self.frequency))
+        {
+            self.notify(format!("Already preset {}", preset + 1));
+            return;
+        }
+
+        if let Some(preset) = self.presets.iter().position(Option::is_none) {
+            if self.store_preset(preset, nvs) {
+                self.notify(format!("Saved to preset {}", preset + 1));
+            }
+            return;
+        }
+
+        let Some(preset) = self.preset_to_replace() else {
+            self.notify("Presets full");
+            return;
+        };
+
+        // the replaced station is named, so that it can be stored again elsewhere
+        let replaced = match (&self.preset_names[preset], self.presets[preset]) {
+            (Some(name), _) => name.clone(),
+            (None, Some(freq)) => self.frequency_text(freq),
+            (None, None) => String::new(),
+        };
+        if self.store_preset(preset, nvs) {
+            log::info!("quick save replaced preset {} ({replaced})", preset + 1);
+            self.notify(format!("Replaced P{} {replaced}", preset + 1));
+        }
+    }
+}
@@ -0,0 +1,456 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// How long a station may stay without any RDS group, before it is considered to have no RDS
+const RDS_GRACE: Duration = Duration::from_secs(5);
+
+/// Number of finished Radio Text messages kept, besides the one being received
+const TEXT_HISTORY_LEN: usize = 4;
+
+/// Characters 0x80-0xFF of the RDS character set (EN 50067, annex E),
+/// the unused position 0xFF is mapped to a space.
+const EXTENDED_CHARS: [char; 128] = [
+    'á', 'à', 'é', 'è', 'í', 'ì', 'ó', 'ò', 'ú', 'ù', 'Ñ', 'Ç', 'Ş', 'ß', '¡', 'Ĳ', //
+    'â', 'ä', 'ê', 'ë', 'î', 'ï', 'ô', 'ö', 'û', 'ü', 'ñ', 'ç', 'ş', 'ğ', 'ı', 'ĳ', //
+    'ª', 'α', '©', '‰', 'Ğ', 'ě', 'ň', 'ő', 'π', '€', '£', '$', '←', '↑', '→', '↓', //
+    'º', '¹', '²', '³', '±', 'İ', 'ń', 'ű', 'µ', '¿', '÷', '°', '¼', '½', '¾', '§', //
+    'Á', 'À', 'É', 'È', 'Í', 'Ì', 'Ó', 'Ò', 'Ú', 'Ù', 'Ř', 'Č', 'Š', 'Ž', 'Ð', 'Ŀ', //
+    'Â', 'Ä', 'Ê', 'Ë', 'Î', 'Ï', 'Ô', 'Ö', 'Û', 'Ü', 'ř', 'č', 'š', 'ž', 'đ', 'ŀ', //
+    'Ã', 'Å', 'Æ', 'Œ', 'ŷ', 'Ý', 'Õ', 'Ø', 'Þ', 'Ŋ', 'Ŕ', 'Ć', 'Ś', 'Ź', 'Ŧ', 'ð', //
+    'ã', 'å', 'æ', 'œ', 'ŵ', 'ý', 'õ', 'ø', 'þ', 'ŋ', 'ŕ', 'ć', 'ś', 'ź', 'ŧ', ' ', //
+];
+
+/// Marks the end of a Radio Text shorter than the maximum length
+const END_OF_TEXT: u8 = 0x0D;
+
+/// Converts a character from the RDS character set to Unicode.
+///
+/// The basic set mostly matches ASCII, control characters are replaced by spaces.
+pub fn decode_char(byte: u8) -> char {
+    match byte {
+        0x00..=0x1F | 0x7F => ' ',
+        0x24 => '¤',
+        0x5E => '―',
+        0x60 => '‖',
+        0x7E => '¯',
+        0x20..=0x7E => byte as char,
+        0x80..=0xFF => EXTENDED_CHARS[byte as usize - 0x80],
+    }
+}
+
+/// Returns the name of the type of the group with the given block B, e.g. `0A` or `2B`.
+pub fn group_name(block_b: u16) -> String {
+    let group_type = (block_b >> 12) & 0xF;
+    let version = if block_b & 0x800 != 0 { 'B' } else { 'A' };
+    format!("{group_type}{version}")
+}
+
+/// Names of the Program Types (EN 50067, annex F), indexed by their code
+const PROGRAM_TYPES: [&str; 32] = [
+    "",
+    "News",
+    "Current affairs",
+    "Information",
+    "Sport",
+    "Education",
+    "Drama",
+    "Culture",
+    "Science",
+    "Varied",
+    "Pop music",
+    "Rock music",
+    "Easy listening",
+    "Light classical",
+    "Serious classical",
+    "Other music",
+    "Weather",
+    "Finance",
+    "Children's progs",
+    "Social affairs",
+    "Religion",
+    "Phone-in",
+    "Travel",
+    "Leisure",
+    "Jazz music",
+    "Country music",
+    "National music",
+    "Oldies music",
+    "Folk music",
+    "Documentary",
+    "Alarm test",
+    "Alarm",
+];
+
+/// Returns the name of the Program Type with the given code, empty for no type.
+pub fn program_type_name(code: u8) -> &'static str {
+    PROGRAM_TYPES.get(code as usize).copied().unwrap_or("")
+}
+
+/// Whether RDS data is received from the tuned station.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RdsReception {
+    /// No group arrived yet, but the station was tuned only recently
+    Waiting,
+    /// At least one group arrived since the station was tuned
+    Receiving,
+    /// No group arrived within `RDS_GRACE`, the station likely has no RDS
+    Missing,
+}
+
+/// Information decoded from a single RDS group.
+pub enum RdsUpdate {
+    /// Radio Text (group 2) changed
+    RadioText(String),
+    /// Program Service name (group 0) changed
+    StationName(String),
+    /// Clock time (group 4A) was received, as minutes since the local midnight
+    ClockTime(u16),
+}
+
+/// Assembles the Program Service name and Radio Text from RDS groups,
+/// which each carry only a few characters.
+pub struct RdsDecoder {
+    /// raw Radio Text characters, up to 64 in version A groups, 32 in version B
+    radio_text: [u8; 64],
+
+    /// Radio Text A/B flag, its change means that the text should be cleared
+    text_ab_flag: Option<bool>,
+
+    /// bit mask of received Radio Text segments of the current message
+    text_segments: u16,
+
+    /// finished Radio Text messages, newest first, without consecutive repeats
+    text_history: VecDeque<String>,
+
+    /// Whether a message was added to the history since it was last taken
+    text_history_changed: bool,
+
+    /// raw Program Service name characters
+    station_name: [u8; 8],
+
+    /// bit mask of received Program Service name segments, two characters each
+    station_name_segments: u8,
+
+    /// Program Type code from the last group, zero when unknown
+    program_type: u8,
+
+    /// Program Identification code from block A of the last group
+    pi: Option<u16>,
+
+    /// Extended Country Code from the last group 1A carrying it
+    ecc: Option<u8>,
+
+    /// Whether any group arrived since the last reset
+    received: bool,
+
+    /// When the decoder started waiting for the first group, set by the first check
+    waiting_since: Option<Instant>,
+}
+
+impl RdsDecoder {
+    pub fn new() -> Self {
+        RdsDecoder {
+            radio_text: [b' '; 64],
+            text_ab_flag: None,
+            text_segments: 0,
+            text_history: VecDeque::with_capacity(TEXT_HISTORY_LEN + 1),
+            text_history_changed: false,
+            station_name: [b' '; 8],
+            station_name_segments: 0,
+            program_type: 0,
+            pi: None,
+            ecc: None,
+            received: false,
+            waiting_since: None,
+        }
+    }
+
+    /// Forgets all received data, should be called when the tuned station changes.
+    pub fn reset(&mut self) {
+        *self = RdsDecoder::new();
+    }
+
+    /// Returns the Radio Text received so far, converted to Unicode.
+    pub fn radio_text(&self) -> String {
+        self.radio_text
+            .iter()
+            .take_while(|&&byte| byte != END_OF_TEXT)
+            .map(|&byte| decode_char(byte))
+            .collect()
+    }
+
+    /// Returns the finished Radio Text messages, newest first, if a message
+    /// was added since the last call.
+    pub fn take_text_history(&mut self) -> Option<Vec<String>> {
+        std::mem::take(&mut self.text_history_changed)
+            .then(|| self.text_history.iter().cloned().collect())
+    }
+
+    /// Moves the current Radio Text into the history and clears it for the next message,
+    /// a message repeating the last one is not added again.
+    fn finish_text(&mut self) {
+        let text = self.radio_text().trim().to_string();
+        if !text.is_empty() && self.text_history.front() != Some(&text) {
+            self.text_history.push_front(text);
+            self.text_history.truncate(TEXT_HISTORY_LEN);
+            self.text_history_changed = true;
+        }
+        self.radio_text = [b' '; 64];
+        self.text_segments = 0;
+    }
+
+    /// Returns the Program Service name received so far, converted to Unicode.
+    ///
+    /// While the name is incomplete, characters which were not yet received are
+    /// shown as underscores, before the first segment arrives, the name is blank.
+    pub fn station_name(&self) -> String {
+        if self.station_name_segments == 0 {
+            return " ".repeat(self.station_name.len());
+        }
+
+        self.station_name
+            .iter()
+            .enumerate()
+            .map(|(index, &byte)| {
+                if self.station_name_segments & (1 << (index / 2)) != 0 {
+                    decode_char(byte)
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    /// Returns whether all segments of the Program Service name were received.
+    pub fn station_name_complete(&self) -> bool {
+        self.station_name_segments == 0xF
+    }
+
+    /// Returns how much of the Program Service name was received, in percent.
+    pub fn station_name_percent(&self) -> u8 {
+        (self.station_name_segments.count_ones() * 25) as u8
+    }
+
+    /// Returns whether RDS data is received, the grace period starts with the first check
+    /// after a reset, so that it is not used up by a seek or a scan.
+    pub fn reception(&mut self) -> RdsReception {
+        if self.received {
+            return RdsReception::Receiving;
+        }
+
+        let since = *self.waiting_since.get_or_insert_with(Instant::now);
+        if since.elapsed() < RDS_GRACE {
+            RdsReception::Waiting
+        } else {
+            RdsReception::Missing
+        }
+    }
+
+    /// Returns the Program Type code, carried by every group, zero when unknown.
+    pub fn program_type(&self) -> u8 {
+        self.program_type
+    }
+
+    /// Returns the Program Identification code, once a group arrived.
+    pub fn pi(&self) -> Option<u16> {
+        self.pi
+    }
+
+    /// Returns the Extended Country Code, which tells apart the countries
+    /// sharing the country code of the PI code, not all stations send it.
+    pub fn ecc(&self) -> Option<u8> {
+        self.ecc
+    }
+
+    /// Processes one error-free RDS group, given as its blocks A-D.
+    ///
+    /// Returns the new text, if the group changed some of it.
+    pub fn process_group(
+        &mut self,
+        [block_a, block_b, block_c, block_d]: [u16; 4],
+    ) -> Option<RdsUpdate> {
+        let group_type = (block_b >> 12) & 0xF;
+        let version_b = block_b & 0x800 != 0;
+        self.program_type = ((block_b >> 5) & 0x1F) as u8;
+        self.pi = Some(block_a);
+        self.received = true;
+
+        let [c_high, c_low] = block_c.to_be_bytes();
+        let [d_high, d_low] = block_d.to_be_bytes();
+
+        match group_type {
+            // Program Service name, two characters in block D
+            0 => {
+                let previous = (self.station_name, self.station_name_segments);
+
+                let segment = (block_b & 0x3) as usize;
+                self.station_name[segment * 2..segment * 2 + 2].copy_from_slice(&[d_high, d_low]);
+                self.station_name_segments |= 1 << segment;
+
+                let current = (self.station_name, self.station_name_segments);
+                (current != previous).then(|| RdsUpdate::StationName(self.station_name()))
+            }
+
+            // Radio Text, four characters in blocks C and D (version A),
+            // or two characters in block D (version B)
+            2 => {
+                let previous = self.radio_text;
+
+                let segment = (block_b & 0xF) as usize;
+                let (start, chars) = if version_b {
+                    (segment * 2, &[d_high, d_low][..])
+                } else {
+                    (segment * 4, &[c_high, c_low, d_high, d_low][..])
+                };
+
+                // a new message starts, when the A/B flag flips, or when stations
+                // without the flag overwrite a received segment with other text
+                let text_ab_flag = block_b & 0x10 != 0;
+                let overwritten = self.text_segments & (1 << segment) != 0
+                    && self.radio_text[start..start + chars.len()] != *chars;
+                if self.text_ab_flag.is_some_and(|flag| flag != text_ab_flag) || overwritten {
+                    self.finish_text();
+                }
+                self.text_ab_flag = Some(text_ab_flag);
+
+                self.radio_text[start..start + chars.len()].copy_from_slice(chars);
+                self.text_segments |= 1 << segment;
+
+                (self.radio_text != previous).then(|| RdsUpdate::RadioText(self.radio_text()))
+            }
+
+            // Slow labelling codes, variant 0 carries the Extended Country Code in block C
+            1 if !version_b && (block_c >> 12) & 0x7 == 0 => {
+                self.ecc = Some(c_low);
+                None
+            }
+
+            // Clock time, UTC hour and minute split across blocks C and D,
+            // with the local offset in half hours, zero date means the time is not set
+            4 if !version_b => {
+                let date = ((block_b as u32 & 0x3) << 15) | (block_c as u32 >> 1);
+                let hour = ((block_c & 0x1) << 4) | (block_d >> 12);
+                let minute = (block_d >> 6) & 0x3F;
+                if date == 0 || hour > 23 || minute > 59 {
+                    return None;
+                }
+
+                let offset = (block_d & 0x1F) as i32 * 30;
+                let offset = if block_d & 0x20 != 0 { -offset } else { offset };
+                let local = (hour as i32 * 60 + minute as i32 + offset).rem_euclid(24 * 60);
+                Some(RdsUpdate::ClockTime(local as u16))
+            }
+
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PI: u16 = 0x2204;
+
+    /// Returns the group 0A carrying two characters of the Program Service name.
+    fn name_group(segment: u16, chars: &[u8; 2]) -> [u16; 4] {
+        [PI, segment, 0, u16::from_be_bytes(*chars)]
+    }
+
+    /// Returns the group 2A carrying four characters of the Radio Text.
+    fn text_group(segment: u16, ab_flag: bool, chars: &[u8; 4]) -> [u16; 4] {
+        let block_b = 0x2000 | (ab_flag as u16) << 4 | segment;
+        let [a, b, c, d] = *chars;
+        [
+            PI,
+            block_b,
+            u16::from_be_bytes([a, b]),
+            u16::from_be_bytes([c, d]),
+        ]
+    }
+
+    #[test]
+    fn station_name_is_assembled_from_segments() {
+        let mut decoder = RdsDecoder::new();
+        assert_eq!(decoder.station_name(), "        ");
+
+        decoder.process_group(name_group(1, b"DI"));
+        assert_eq!(decoder.station_name(), "__DI____");
+        assert!(!decoder.station_name_complete());
+        assert_eq!(decoder.station_name_percent(), 25);
+
+        for (segment, chars) in [(0, b"RA"), (2, b"O "), (3, b"  ")] {
+            decoder.process_group(name_group(segment, chars));
+        }
+        assert_eq!(decoder.station_name(), "RADIO   ");
+        assert!(decoder.station_name_complete());
+        assert_eq!(decoder.pi(), Some(PI));
+    }
+
+    #[test]
+    fn repeated_group_reports_no_update() {
+        let mut decoder = RdsDecoder::new();
+        assert!(matches!(
+            decoder.process_group(name_group(0, b"RA")),
+            Some(RdsUpdate::StationName(_))
+        ));
+        assert!(decoder.process_group(name_group(0, b"RA")).is_none());
+    }
+
+    #[test]
+    fn radio_text_ends_at_end_of_text() {
+        let mut decoder = RdsDecoder::new();
+        decoder.process_group(text_group(0, false, b"Hi t"));
+        decoder.process_group(text_group(1, false, b"here"));
+        decoder.process_group(text_group(2, false, b"\r   "));
+        assert_eq!(decoder.radio_text(), "Hi there");
+    }
+
+    #[test]
+    fn rds_characters_are_decoded() {
+        assert_eq!(decode_char(b'A'), 'A');
+        assert_eq!(decode_char(0x24), '¤');
+        assert_eq!(decode_char(0x0A), ' ');
+        assert_eq!(decode_char(0x91), 'ä');
+        assert_eq!(decode_char(0xDB), 'č');
+        assert_eq!(decode_char(0xFF), ' ');
+
+        let mut decoder = RdsDecoder::new();
+        decoder.process_group(name_group(0, &[0xDB, b'T']));
+        assert_eq!(decoder.station_name(), "čT______");
+    }
+
+    #[test]
+    fn ab_flag_moves_text_to_history() {
+        let mut decoder = RdsDecoder::new();
+        decoder.process_group(text_group(0, false, b"One\r"));
+        assert_eq!(decoder.take_text_history(), None);
+
+        decoder.process_group(text_group(0, true, b"Two\r"));
+        assert_eq!(decoder.radio_text(), "Two");
+        assert_eq!(decoder.take_text_history(), Some(vec!["One".to_string()]));
+        assert_eq!(decoder.take_text_history(), None);
+    }
+
+    #[test]
+    fn clock_time_is_local() {
+        let mut decoder = RdsDecoder::new();
+        // 23:30 UTC, two half hours ahead, on some date
+        let block_c = (1 << 1) | 1;
+        let block_d = (7 << 12) | (30 << 6) | 2;
+        let update = decoder.process_group([PI, 0x4000, block_c, block_d]);
+        assert!(matches!(update, Some(RdsUpdate::ClockTime(30))));
+
+        // the time is not set without a date
+        assert!(decoder.process_group([PI, 0x4000, 1, block_d]).is_none());
+    }
+
+    #[test]
+    fn extended_country_code_is_read() {
+        let mut decoder = RdsDecoder::new();
+        decoder.process_group([PI, 0x1000, 0x00E2, 0]);
+        assert_eq!(decoder.ecc(), Some(0xE2));
+    }
+}
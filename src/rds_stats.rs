@@ -0,0 +1,91 @@
+use std::sync::Mutex;
+
+use crate::{AppState, InputEvent, Screen};
+
+/// Number of rows on the RDS statistics screen, which can be scrolled through
+pub const RDS_STATS_ROWS: usize = 8;
+
+/// Counters of RDS reception on the tuned station, last updated by the tuner thread
+static RDS_STATS: Mutex<RdsStats> = Mutex::new(RdsStats::new());
+
+/// Statistics of RDS reception since the tuned station changed, for judging the antenna.
+#[derive(Clone, Debug)]
+pub struct RdsStats {
+    /// Blocks read from the tuner, four in each group
+    pub blocks: u32,
+    /// Blocks, in which the tuner detected errors, it only reports them for blocks A and B
+    pub error_blocks: u32,
+    /// Share of the Program Service name received so far, in percent
+    pub station_name_percent: u8,
+    /// Type of the last error-free group, e.g. `0A`
+    pub last_group: Option<String>,
+    /// Program Identification code of the station
+    pub pi: Option<u16>,
+    /// Extended Country Code of the station, if it sends one
+    pub ecc: Option<u8>,
+}
+
+impl RdsStats {
+    const fn new() -> Self {
+        RdsStats {
+            blocks: 0,
+            error_blocks: 0,
+            station_name_percent: 0,
+            last_group: None,
+            pi: None,
+            ecc: None,
+        }
+    }
+}
+
+/// Clears the statistics, called by the tuner thread when the tuned station changes.
+pub fn reset_rds_stats() {
+    *RDS_STATS.lock().unwrap() = RdsStats::new();
+}
+
+/// Counts a group read from the tuner, with the error levels of its blocks A and B.
+pub fn record_rds_group(block_errors: (u8, u8)) {
+    let mut stats = RDS_STATS.lock().unwrap();
+    stats.blocks += 4;
+    stats.error_blocks += (block_errors.0 > 0) as u32 + (block_errors.1 > 0) as u32;
+}
+
+/// Notes an error-free group of the given type, and how much of the name it completed.
+pub fn record_rds_decoded(group: String, station_name_percent: u8) {
+    let mut stats = RDS_STATS.lock().unwrap();
+    stats.last_group = Some(group);
+    stats.station_name_percent = station_name_percent;
+}
+
+/// Notes the identification of the station, from an error-free group.
+pub fn record_rds_station(pi: Option<u16>, ecc: Option<u8>) {
+    let mut stats = RDS_STATS.lock().unwrap();
+    stats.pi = pi;
+    stats.ecc = ecc;
+}
+
+/// Returns the current statistics, can be called from any thread.
+pub fn rds_stats() -> RdsStats {
+    RDS_STATS.lock().unwrap().clone()
+}
+
+impl AppState {
+    /// Processes user input on the RDS statistics screen.
+    pub fn process_rds_stats_input(&mut self, event: InputEvent) {
+        use InputEvent as I;
+        match event {
+            // scrolling through the rows, which do not fit on the screen
+            I::ScrollUp if self.rds_stats_cursor + 1 < RDS_STATS_ROWS => self.rds_stats_cursor += 1,
+            I::ScrollDown if self.rds_stats_cursor > 0 => self.rds_stats_cursor -= 1,
+
+            // return back to the home screen
+            I::LongPress | I::VeryLongPress => self.screen = Screen::Home,
+
+            // ignore all other user inputs
+            I::ScrollDown | I::ScrollUp | I::ShortPress | I::DoublePress | I::Hold => (),
+
+            // events from radio are processed before reaching the screen
+            _ => unreachable!(),
+        }
+    }
+}
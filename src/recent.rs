@@ -0,0 +1,80 @@
+use std::sync::mpsc::Sender;
+
+use crate::{frequency::Frequency, AppState, InputEvent, OutputCommand, Screen};
+
+/// Number of frequencies kept in the list of recently strong stations
+const RECENT_STATIONS_LEN: usize = 8;
+
+/// Lowest RSSI, at which a tuned frequency is kept as a recently strong station
+pub const RECENT_MIN_RSSI: u8 = 16;
+
+/// Bounded list of the strongest frequencies seen recently, sorted by decreasing RSSI.
+///
+/// It is filled as the user tunes around, so that it works as a band map
+/// even without a band scan.
+pub struct RecentStations {
+    /// frequency and the last RSSI measured on it
+    stations: Vec<(Frequency, u8)>,
+}
+
+impl RecentStations {
+    pub fn new() -> Self {
+        RecentStations {
+            stations: Vec::with_capacity(RECENT_STATIONS_LEN + 1),
+        }
+    }
+
+    /// Records the RSSI measured on the frequency, when the list is full,
+    /// the weakest station is dropped.
+    pub fn record(&mut self, freq: Frequency, rssi: u8) {
+        self.stations.retain(|&(other, _)| other != freq);
+
+        let index = self
+            .stations
+            .iter()
+            .position(|&(_, other)| other < rssi)
+            .unwrap_or(self.stations.len());
+        self.stations.insert(index, (freq, rssi));
+
+        self.stations.truncate(RECENT_STATIONS_LEN);
+    }
+
+    /// Returns the stations with their RSSI, strongest first.
+    pub fn stations(&self) -> &[(Frequency, u8)] {
+        &self.stations
+    }
+}
+
+impl AppState {
+    /// Processes user input on the screen with recently strong stations.
+    pub fn process_recent_input(&mut self, event: InputEvent, command: &Sender<OutputCommand>) {
+        let count = self.recent_stations.stations().len();
+
+        use InputEvent as I;
+        match event {
+            // scrolling through stations in a loop
+            I::ScrollDown if count > 0 => {
+                self.recent_cursor = (self.recent_cursor + count - 1) % count
+            }
+            I::ScrollUp if count > 0 => self.recent_cursor = (self.recent_cursor + 1) % count,
+
+            // tune the selected station and return to the home screen
+            I::ShortPress if count > 0 => {
+                self.tune_station(
+                    self.recent_stations.stations()[self.recent_cursor].0,
+                    command,
+                );
+                self.screen = Screen::Home;
+            }
+
+            // return back to the home screen
+            I::LongPress | I::VeryLongPress => self.screen = Screen::Home,
+
+            // ignore all other user inputs
+            I::ScrollDown | I::ScrollUp | I::ShortPress | I::DoublePress | I::Hold => (),
+
+            // events from radio are processed before reaching the screen
+            _ => unreachable!(),
+        }
+    }
+}
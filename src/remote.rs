@@ -0,0 +1,422 @@
+use esp_idf_svc::{
+    hal::{
+        delay::BLOCK,
+        gpio::AnyInputPin,
+        rmt::{config::ReceiveConfig, PinState, Pulse, Receive, RxRmtDriver, CHANNEL0},
+    },
+    sys::EspError,
+};
+use std::{
+    sync::mpsc::Sender,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{storage::REMOTE_CODES_KEY, AppState, Direction, InputEvent, OutputCommand, Screen};
+
+/// Burst, which starts every NEC frame, the receiver pulls its output low during bursts
+const LEADER_BURST: Duration = Duration::from_micros(9000);
+/// Space after the leader of a frame with a code
+const CODE_SPACE: Duration = Duration::from_micros(4500);
+/// Space after the leader of a frame, which repeats the last code while the key is held
+const REPEAT_SPACE: Duration = Duration::from_micros(2250);
+/// Burst before every bit, and the space of a zero bit
+const BIT_UNIT: Duration = Duration::from_micros(562);
+/// Space of a one bit
+const ONE_SPACE: Duration = Duration::from_micros(1687);
+/// Number of bits in a code, address, inverted address, command and inverted command
+const CODE_BITS: u8 = 32;
+
+/// Longest time between frames of a held key, a repeat frame after a longer
+/// pause does not belong to the last code
+const REPEAT_WINDOW: Duration = Duration::from_millis(200);
+
+/// Number of keys of the remote, which can be mapped
+pub const REMOTE_KEYS: usize = 9;
+
+/// Keys of the remote, which can be mapped to its codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemoteKey {
+    VolumeUp,
+    VolumeDown,
+    SeekUp,
+    SeekDown,
+    /// Recalls the preset with the given index
+    Preset(u8),
+    Mute,
+}
+
+impl RemoteKey {
+    /// All keys, in the order in which they are learned
+    pub const ALL: [RemoteKey; REMOTE_KEYS] = [
+        RemoteKey::VolumeUp,
+        RemoteKey::VolumeDown,
+        RemoteKey::SeekUp,
+        RemoteKey::SeekDown,
+        RemoteKey::Preset(0),
+        RemoteKey::Preset(1),
+        RemoteKey::Preset(2),
+        RemoteKey::Preset(3),
+        RemoteKey::Mute,
+    ];
+
+    /// name of the key shown while it is learned
+    fn label(self) -> String {
+        match self {
+            RemoteKey::VolumeUp => "Volume up".to_string(),
+            RemoteKey::VolumeDown => "Volume down".to_string(),
+            RemoteKey::SeekUp => "Seek up".to_string(),
+            RemoteKey::SeekDown => "Seek down".to_string(),
+            RemoteKey::Preset(preset) => format!("Preset {}", preset + 1),
+            RemoteKey::Mute => "Mute".to_string(),
+        }
+    }
+}
+
+/// Frame decoded from the IR receiver.
+enum NecFrame {
+    /// A key was pressed, with its whole code
+    Code(u32),
+    /// The last key is still held
+    Repeat,
+}
+
+/// Where in a frame the decoder is.
+enum NecState {
+    /// Waiting for the leader burst
+    Idle,
+    /// The leader burst was received, its space tells a code from a repeat
+    Leader,
+    /// Bits of a code, received so far, least significant first
+    Bits { code: u32, count: u8 },
+}
+
+/// Decodes NEC frames from the lengths of the bursts and spaces between edges.
+struct NecDecoder {
+    state: NecState,
+}
+
+impl NecDecoder {
+    /// Returns whether the measured length is within a quarter of the expected one.
+    fn near(measured: Duration, expected: Duration) -> bool {
+        let tolerance = expected / 4;
+        measured >= expected - tolerance && measured <= expected + tolerance
+    }
+
+    /// Processes the length of a burst, when `burst` is set, otherwise of a space,
+    /// returns the frame ended by it. Anything unexpected restarts the decoding.
+    fn push(&mut self, burst: bool, length: Duration) -> Option<NecFrame> {
+        let near = |expected| Self::near(length, expected);
+
+        let (state, frame) = match std::mem::replace(&mut self.state, NecState::Idle) {
+            NecState::Idle | NecState::Leader if burst && near(LEADER_BURST) => {
+                (NecState::Leader, None)
+            }
+            NecState::Leader if !burst && near(CODE_SPACE) => {
+                (NecState::Bits { code: 0, count: 0 }, None)
+            }
+            NecState::Leader if !burst && near(REPEAT_SPACE) => {
+                (NecState::Idle, Some(NecFrame::Repeat))
+            }
+            NecState::Bits { code, count } if burst && near(BIT_UNIT) => {
+                (NecState::Bits { code, count }, None)
+            }
+            NecState::Bits { code, count } if !burst && (near(BIT_UNIT) || near(ONE_SPACE)) => {
+                let code = code | ((near(ONE_SPACE) as u32) << count);
+                if count + 1 < CODE_BITS {
+                    (
+                        NecState::Bits {
+                            code,
+                            count: count + 1,
+                        },
+                        None,
+                    )
+                } else {
+                    // the command is sent a second time inverted, noise rarely matches it
+                    let [_, _, command, inverted] = code.to_le_bytes();
+                    let valid = command == !inverted;
+                    (NecState::Idle, valid.then_some(NecFrame::Code(code)))
+                }
+            }
+            // the burst, which broke off a frame, may start the next one
+            _ if burst && near(LEADER_BURST) => (NecState::Leader, None),
+            _ => (NecState::Idle, None),
+        };
+
+        self.state = state;
+        frame
+    }
+}
+
+/// Frames of the IR receiver are measured by the RMT in microseconds
+const RMT_CLOCK_DIVIDER: u8 = 80;
+/// Time without an edge, in microseconds, after which the RMT ends a frame,
+/// longer than the leader burst
+const RMT_IDLE_THRESHOLD: u16 = 12_000;
+/// Size of the ring buffer of the RMT, room for several frames
+const RMT_RING_BUFFER_SIZE: usize = 1000;
+/// Number of pulse pairs read at once, a frame has 34 bursts with their spaces
+const RMT_PULSES: usize = 64;
+
+/// Sets up the RMT channel, which measures the pulses from the IR receiver.
+pub fn remote_receiver(
+    channel: CHANNEL0,
+    pin: AnyInputPin,
+) -> Result<RxRmtDriver<'static>, EspError> {
+    let config = ReceiveConfig::new()
+        .clock_divider(RMT_CLOCK_DIVIDER)
+        .idle_threshold(RMT_IDLE_THRESHOLD);
+    let receiver = RxRmtDriver::new(channel, pin, &config, RMT_RING_BUFFER_SIZE)?;
+    receiver.start()?;
+    Ok(receiver)
+}
+
+/// Spawns a new thread which receives the pulses from an IR receiver measured by the RMT,
+/// decodes NEC frames from them and sends the codes of pressed keys to the event loop,
+/// which maps them to actions. While a key is held, repeats are sent about every 110 ms.
+///
+/// The receiver output is active low, as with the common TSOP modules.
+pub fn spawn_remote_listener(mut receiver: RxRmtDriver<'static>, event_sender: Sender<InputEvent>) {
+    thread::spawn(move || {
+        let mut pulses = [(Pulse::zero(), Pulse::zero()); RMT_PULSES];
+        let mut last_frame: Option<Instant> = None;
+
+        loop {
+            let count = match receiver.receive(&mut pulses, BLOCK) {
+                Ok(Receive::Read(count)) => count,
+                Ok(Receive::BufferTooSmall(_) | Receive::Timeout) => continue,
+                Err(err) => {
+                    log::warn!("remote: receiving failed: {err:?}");
+                    thread::sleep(REPEAT_WINDOW);
+                    continue;
+                }
+            };
+            let now = Instant::now();
+
+            // every frame is decoded on its own, the RMT delivers them whole
+            let mut decoder = NecDecoder {
+                state: NecState::Idle,
+            };
+            let frame = pulses[..count]
+                .iter()
+                .flat_map(|&(first, second)| [first, second])
+                // a zero length marks the end of the frame
+                .take_while(|pulse| pulse.ticks.ticks() != 0)
+                .find_map(|pulse| {
+                    let burst = pulse.pin_state == PinState::Low;
+                    let length = Duration::from_micros(pulse.ticks.ticks().into());
+                    decoder.push(burst, length)
+                });
+
+            match frame {
+                Some(NecFrame::Code(code)) => {
+                    last_frame = Some(now);
+                    event_sender.send(InputEvent::RemoteCode(code)).unwrap();
+                }
+                Some(NecFrame::Repeat)
+                    if last_frame.is_some_and(|frame| now - frame < REPEAT_WINDOW) =>
+                {
+                    last_frame = Some(now);
+                    event_sender.send(InputEvent::RemoteRepeat).unwrap();
+                }
+                Some(NecFrame::Repeat) | None => (),
+            }
+        }
+    });
+}
+
+impl AppState {
+    /// Does what the key with the code is mapped to, codes of unmapped keys are ignored.
+    pub fn process_remote_code(&mut self, code: u32, command: &Sender<OutputCommand>) {
+        let Some(index) = self.remote_codes.iter().position(|&mapped| mapped == code) else {
+            log::info!("remote: unmapped code {code:08X}");
+            self.remote_held = None;
+            return;
+        };
+
+        let key = RemoteKey::ALL[index];
+        self.remote_held = Some(key);
+        match key {
+            RemoteKey::VolumeUp => self.change_volume(Direction::Up, command),
+            RemoteKey::VolumeDown => self.change_volume(Direction::Down, command),
+            RemoteKey::SeekUp => self.start_seek(Direction::Up, command),
+            RemoteKey::SeekDown => self.start_seek(Direction::Down, command),
+            RemoteKey::Preset(preset) => self.recall_preset(preset as usize, command),
+            RemoteKey::Mute => self.set_muted(!self.muted, command),
+        }
+    }
+
+    /// Keeps changing the volume, while a volume key is held, other keys act only once.
+    pub fn process_remote_repeat(&mut self, command: &Sender<OutputCommand>) {
+        match self.remote_held {
+            Some(RemoteKey::VolumeUp) => self.change_volume(Direction::Up, command),
+            Some(RemoteKey::VolumeDown) => self.change_volume(Direction::Down, command),
+            _ => (),
+        }
+    }
+
+    /// Opens the screen, which asks for each key of the remote in turn.
+    pub fn start_remote_learning(&mut self) {
+        self.remote_learning = Some((0, self.remote_codes));
+        self.screen = Screen::RemoteLearning;
+    }
+
+    /// Returns the number of keys, which are mapped to codes.
+    pub fn remote_keys_learned(&self) -> usize {
+        self.remote_codes.iter().filter(|&&code| code != 0).count()
+    }
+
+    /// Returns the key, which is being learned, with its position among all keys.
+    pub fn remote_learning_line(&self) -> String {
+        let index = self.remote_learning.map_or(0, |(index, _)| index);
+        format!(
+            "{}/{} {}",
+            index + 1,
+            REMOTE_KEYS,
+            RemoteKey::ALL[index].label()
+        )
+    }
+
+    /// Maps the received code to the key being learned, once all keys are through,
+    /// the codes are stored and used from then on.
+    pub fn learn_remote_code(&mut self, code: u32) {
+        let Some((index, mut codes)) = self.remote_learning else {
+            return;
+        };
+
+        // a code used by an earlier key would do two things
+        if codes[..index].contains(&code) {
+            self.notify("Key already used");
+            return;
+        }
+        codes[index] = code;
+        self.advance_remote_learning(index, codes);
+    }
+
+    /// Moves on to the next key, or stores the codes after the last one.
+    fn advance_remote_learning(&mut self, index: usize, mut codes: [u32; REMOTE_KEYS]) {
+        // the code may have been mapped to a later key before, that mapping goes
+        let code = codes[index];
+        for later in &mut codes[index + 1..] {
+            if *later == code {
+                *later = 0;
+            }
+        }
+
+        if index + 1 < REMOTE_KEYS {
+            self.remote_learning = Some((index + 1, codes));
+            return;
+        }
+
+        self.remote_learning = None;
+        self.remote_codes = codes;
+        let blob = codes.iter().flat_map(|code| code.to_le_bytes()).collect();
+        self.pending_writes.stage(REMOTE_CODES_KEY, blob);
+        self.screen = Screen::Settings;
+        self.notify(format!("{} keys learned", self.remote_keys_learned()));
+    }
+
+    /// Processes user input on the remote learning screen.
+    pub fn process_remote_learning_input(&mut self, event: InputEvent) {
+        let Some((index, codes)) = self.remote_learning else {
+            return;
+        };
+
+        use InputEvent as I;
+        match event {
+            // keep the code, which the key had before, if any
+            I::ShortPress => self.advance_remote_learning(index, codes),
+
+            // return back to the home screen, leaving the codes as they were
+            I::LongPress | I::VeryLongPress => {
+                self.remote_learning = None;
+                self.screen = Screen::Home;
+            }
+
+            // ignore all other user inputs
+            I::ScrollDown | I::ScrollUp | I::DoublePress | I::Hold => (),
+
+            // events from radio are processed before reaching the screen
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the bursts and spaces of the frame with the code, as the receiver outputs them.
+    fn code_frame(code: u32) -> Vec<(bool, Duration)> {
+        let mut frame = vec![(true, LEADER_BURST), (false, CODE_SPACE)];
+        for bit in 0..CODE_BITS {
+            let space = match code & (1 << bit) {
+                0 => BIT_UNIT,
+                _ => ONE_SPACE,
+            };
+            frame.extend([(true, BIT_UNIT), (false, space)]);
+        }
+        frame
+    }
+
+    fn decode(decoder: &mut NecDecoder, frame: &[(bool, Duration)]) -> Vec<NecFrame> {
+        frame
+            .iter()
+            .filter_map(|&(burst, length)| decoder.push(burst, length))
+            .collect()
+    }
+
+    /// Code of address 0x04 and command 0x08, each followed by its inverse
+    const CODE: u32 = 0xF708FB04;
+
+    #[test]
+    fn code_is_decoded() {
+        let mut decoder = NecDecoder {
+            state: NecState::Idle,
+        };
+        let frames = decode(&mut decoder, &code_frame(CODE));
+        assert!(matches!(frames[..], [NecFrame::Code(CODE)]));
+    }
+
+    #[test]
+    fn repeat_is_decoded() {
+        let mut decoder = NecDecoder {
+            state: NecState::Idle,
+        };
+        let frames = decode(&mut decoder, &[(true, LEADER_BURST), (false, REPEAT_SPACE)]);
+        assert!(matches!(frames[..], [NecFrame::Repeat]));
+    }
+
+    #[test]
+    fn lengths_within_tolerance_are_accepted() {
+        let mut decoder = NecDecoder {
+            state: NecState::Idle,
+        };
+        let frame: Vec<_> = code_frame(CODE)
+            .into_iter()
+            .map(|(burst, length)| (burst, length * 6 / 5))
+            .collect();
+        let frames = decode(&mut decoder, &frame);
+        assert!(matches!(frames[..], [NecFrame::Code(CODE)]));
+    }
+
+    #[test]
+    fn corrupted_command_is_rejected() {
+        let mut decoder = NecDecoder {
+            state: NecState::Idle,
+        };
+        let frames = decode(&mut decoder, &code_frame(CODE ^ 0x0001_0000));
+        assert!(frames.is_empty());
+    }
+
+    #[test]
+    fn broken_frame_does_not_hide_next_one() {
+        let mut decoder = NecDecoder {
+            state: NecState::Idle,
+        };
+        let mut frame = code_frame(CODE)[..10].to_vec();
+        frame.extend(code_frame(CODE));
+        let frames = decode(&mut decoder, &frame);
+        assert!(matches!(frames[..], [NecFrame::Code(CODE)]));
+    }
+}
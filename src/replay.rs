@@ -0,0 +1,269 @@
+use esp_idf_svc::nvs::{EspNvs, NvsDefault};
+use std::sync::mpsc::Sender;
+
+use crate::{
+    frequency::Frequency, gui::Panel, rds::RdsReception, trace::record_event, AppState, Direction,
+    InputEvent, OutputCommand, TunerStatus,
+};
+
+/// Applies a recorded sequence of input events to the application state,
+/// exactly as the event loop would, so that reported UI glitches can be reproduced.
+///
+/// If a display is given, the GUI is redrawn after every event.
+pub fn replay_events<D: Panel>(
+    state: &mut AppState,
+    events: impl IntoIterator<Item = InputEvent>,
+    command: &Sender<OutputCommand>,
+    nvs: &mut EspNvs<NvsDefault>,
+    mut display: Option<&mut D>,
+) -> Result<(), D::Error> {
+    for event in events {
+        record_event(&event);
+        state.process_event(event, command, nvs);
+
+        if let Some(display) = display.as_deref_mut() {
+            state.update_ui(display)?;
+            display.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a single recorded event, in the same format as its `Debug` output,
+/// e.g. `ShortPress` or `ChangeFrequency(Frequency(100500))`.
+pub fn parse_event(line: &str) -> Option<InputEvent> {
+    let line = line.trim();
+
+    let (name, arg) = match line.split_once('(') {
+        Some((name, rest)) => (name, Some(rest.strip_suffix(')')?)),
+        None => (line, None),
+    };
+
+    use InputEvent as I;
+    let event = match (name, arg) {
+        ("ShortPress", None) => I::ShortPress,
+        ("LongPress", None) => I::LongPress,
+        ("VeryLongPress", None) => I::VeryLongPress,
+        ("DoublePress", None) => I::DoublePress,
+        ("Hold", None) => I::Hold,
+        ("ScrollDown", None) => I::ScrollDown,
+        ("ScrollUp", None) => I::ScrollUp,
+        ("Back", None) => I::Back,
+        ("QuickSave", None) => I::QuickSave,
+        ("Help", None) => I::Help,
+        ("MuteDown", None) => I::MuteDown,
+        ("MuteUp", None) => I::MuteUp,
+        ("RemoteRepeat", None) => I::RemoteRepeat,
+        ("SeekFailed", None) => I::SeekFailed,
+        ("TunerNotFound", None) => I::TunerNotFound,
+        ("NoStationNearby", None) => I::NoStationNearby,
+        ("SignalLost", None) => I::SignalLost,
+        ("SignalAcquired", None) => I::SignalAcquired,
+        ("ChangeFrequency", Some(freq)) => I::ChangeFrequency(parse_frequency(freq)?),
+        ("SeekCancelled", Some(freq)) => I::SeekCancelled(parse_frequency(freq)?),
+        ("ChangeRSSI", Some(rssi)) => I::ChangeRSSI(rssi.parse().ok()?),
+        ("ChangeProgramType", Some(code)) => I::ChangeProgramType(code.parse().ok()?),
+        ("ChangeRdsReception", Some(reception)) => I::ChangeRdsReception(match reception {
+            "Waiting" => RdsReception::Waiting,
+            "Receiving" => RdsReception::Receiving,
+            "Missing" => RdsReception::Missing,
+            _ => return None,
+        }),
+        ("ChangeClockTime", Some(minutes)) => I::ChangeClockTime(minutes.parse().ok()?),
+        ("AmbientLight", Some(level)) => I::AmbientLight(level.parse().ok()?),
+        ("RssiMeasured", Some(rssi)) => I::RssiMeasured(rssi.parse().ok()?),
+        ("RemoteCode", Some(code)) => I::RemoteCode(code.parse().ok()?),
+        ("ChangeStatus", Some(status)) => I::ChangeStatus(parse_status(status)?),
+        ("SamplingProgress", Some(progress)) => {
+            let (sampled, total) = progress.split_once(", ")?;
+            I::SamplingProgress(sampled.parse().ok()?, total.parse().ok()?)
+        }
+        ("SamplingFinished", Some(result)) => {
+            let (freq, rssi) = result.split_once(", ")?;
+            I::SamplingFinished(parse_frequency(freq)?, rssi.parse().ok()?)
+        }
+        ("NearestFound", Some(result)) => {
+            let (freq, direction) = result.split_once(", ")?;
+            let direction = match direction {
+                "Up" => Direction::Up,
+                "Down" => Direction::Down,
+                _ => return None,
+            };
+            I::NearestFound(parse_frequency(freq)?, direction)
+        }
+        ("ScanFinished", Some(stations)) => {
+            let stations = stations.strip_prefix('[')?.strip_suffix(']')?;
+            I::ScanFinished(
+                stations
+                    .split("), (")
+                    .filter(|station| !station.is_empty())
+                    .map(|station| {
+                        let station = station.trim_start_matches('(').trim_end_matches(')');
+                        let (freq, rssi) = station.split_once(", ")?;
+                        Some((parse_frequency(freq)?, rssi.parse().ok()?))
+                    })
+                    .collect::<Option<_>>()?,
+            )
+        }
+        ("ChangeStationInfo", Some(info)) => I::ChangeStationInfo(parse_string(info)?),
+        ("ChangeStationName", Some(name)) => {
+            let (name, complete) = name.rsplit_once(", ")?;
+            I::ChangeStationName(parse_string(name)?, complete.parse().ok()?)
+        }
+        ("ChangeTextHistory", Some(history)) => {
+            let history = history.strip_prefix('[')?.strip_suffix(']')?;
+            I::ChangeTextHistory(
+                history
+                    .split(r#"", ""#)
+                    .filter(|text| !text.is_empty())
+                    .map(|text| {
+                        let text = text.strip_prefix('"').unwrap_or(text);
+                        let text = text.strip_suffix('"').unwrap_or(text);
+                        parse_string(&format!("\"{text}\""))
+                    })
+                    .collect::<Option<_>>()?,
+            )
+        }
+        _ => return None,
+    };
+
+    Some(event)
+}
+
+/// Parses a frequency argument of a recorded event, in the format of its `Debug` output,
+/// logs recorded before frequencies had their own type contain only the number of kHz.
+fn parse_frequency(arg: &str) -> Option<Frequency> {
+    let khz = match arg.strip_prefix("Frequency(") {
+        Some(khz) => khz.strip_suffix(')')?,
+        None => arg,
+    };
+    Some(Frequency::from_khz(khz.parse().ok()?))
+}
+
+/// Parses a string argument of a recorded event, in the format of its `Debug` output.
+fn parse_string(arg: &str) -> Option<String> {
+    let arg = arg.strip_prefix('"')?.strip_suffix('"')?;
+    Some(arg.replace("\\\"", "\"").replace("\\\\", "\\"))
+}
+
+/// Parses a tuner status argument of a recorded event, in the format of its `Debug` output.
+fn parse_status(arg: &str) -> Option<TunerStatus> {
+    let fields = arg.strip_prefix("TunerStatus {")?.strip_suffix('}')?;
+
+    let mut status = TunerStatus::default();
+    for field in fields.split(',').filter(|field| !field.trim().is_empty()) {
+        let (name, value) = field.split_once(':')?;
+        let value = value.trim().parse().ok()?;
+        match name.trim() {
+            "stereo" => status.stereo = value,
+            "rds_synchronized" => status.rds_synchronized = value,
+            "seek_complete" => status.seek_complete = value,
+            "seek_failed" => status.seek_failed = value,
+            "mono" => status.mono = value,
+            _ => return None,
+        }
+    }
+
+    Some(status)
+}
+
+/// Parses a recorded event log with one event per line, skipping empty lines.
+///
+/// Returns the number of the first line which could not be parsed.
+pub fn parse_event_log(log: &str) -> Result<Vec<InputEvent>, usize> {
+    log.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| parse_event(line).ok_or(number + 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use esp_idf_svc::nvs::EspDefaultNvsPartition;
+    use std::sync::mpsc::channel;
+
+    use super::*;
+    use crate::gui::tests::MockDisplay;
+
+    #[test]
+    fn debug_output_is_parsed_back() {
+        let freq = Frequency::from_khz(100_500);
+        let status = TunerStatus {
+            stereo: true,
+            seek_complete: true,
+            ..TunerStatus::default()
+        };
+
+        use InputEvent as I;
+        let events = [
+            I::ShortPress,
+            I::Hold,
+            I::RemoteCode(0x00FF_A25D),
+            I::ChangeFrequency(freq),
+            I::SeekCancelled(freq),
+            I::ChangeRSSI(42),
+            I::ChangeRdsReception(RdsReception::Missing),
+            I::ChangeStatus(status),
+            I::ChangeStatus(TunerStatus::default()),
+            I::SamplingProgress(3, 7),
+            I::SamplingFinished(freq, 30),
+            I::NearestFound(freq, Direction::Down),
+            I::ScanFinished(vec![]),
+            I::ScanFinished(vec![(freq, 30)]),
+            I::ScanFinished(vec![(freq, 30), (Frequency::from_khz(104_000), 25)]),
+            I::ChangeStationInfo(r#"say "hi" \ bye"#.to_string()),
+            I::ChangeStationName("RADIO 1 ".to_string(), true),
+            I::ChangeTextHistory(vec![]),
+            I::ChangeTextHistory(vec!["first".to_string(), "second".to_string()]),
+        ];
+
+        for event in events {
+            assert_eq!(parse_event(&format!("{event:?}")), Some(event));
+        }
+    }
+
+    #[test]
+    fn frequencies_of_old_logs_are_parsed() {
+        assert_eq!(
+            parse_event("ChangeFrequency(100500)"),
+            Some(InputEvent::ChangeFrequency(Frequency::from_khz(100_500)))
+        );
+    }
+
+    #[test]
+    fn unknown_line_is_reported() {
+        assert_eq!(
+            parse_event_log("ShortPress\n\n  ScrollUp  \n"),
+            Ok(vec![InputEvent::ShortPress, InputEvent::ScrollUp])
+        );
+        assert_eq!(parse_event_log("ShortPress\n\nNope"), Err(3));
+        assert_eq!(parse_event_log("ChangeRSSI(loud)"), Err(1));
+    }
+
+    #[test]
+    fn replayed_log_is_applied_and_drawn() {
+        let log = "ChangeFrequency(Frequency(104000))\n\
+                   ChangeStationInfo(\"News\")\n\
+                   ChangeRSSI(35)\n\
+                   ScrollUp\n\
+                   ScrollDown\n\
+                   Back\n";
+
+        let mut state = AppState::new();
+        let (command, _commands) = channel();
+        let mut nvs = EspNvs::new(EspDefaultNvsPartition::take().unwrap(), "test", true).unwrap();
+
+        let mut display = MockDisplay::default();
+        let events = parse_event_log(log).unwrap();
+        let count = events.len();
+        let result = replay_events(&mut state, events, &command, &mut nvs, Some(&mut display));
+        assert_eq!(result, Ok(()));
+        assert_eq!(display.flushed, count);
+
+        assert_eq!(state.frequency, Frequency::from_khz(104_000));
+        assert_eq!(state.station_info, "News");
+        assert_eq!(state.rssi, 35);
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::Sender,
+};
+
+use crate::{frequency::Frequency, tuner::send_command, AppState, OutputCommand};
+
+/// Set by the event loop to stop the sampling running in the tuner thread
+static SAMPLING_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Clears a cancellation left over from a previous sampling, called as sampling starts.
+pub fn start_sampling() {
+    SAMPLING_CANCELLED.store(false, Ordering::Relaxed);
+}
+
+/// Returns whether the running sampling should stop.
+pub fn sampling_cancelled() -> bool {
+    SAMPLING_CANCELLED.load(Ordering::Relaxed)
+}
+
+impl AppState {
+    /// Starts sampling the RSSI of all stored presets in the tuner thread,
+    /// which then stays on the strongest of them.
+    pub fn tune_strongest_preset(&mut self, command: &Sender<OutputCommand>) {
+        let presets = self.presets.iter().flatten().copied().collect::<Vec<_>>();
+        if presets.is_empty() {
+            self.notify("No presets stored");
+            return;
+        }
+
+        self.sampling_from = Some(self.frequency);
+        self.notify("Checking presets");
+        send_command(command, OutputCommand::TuneStrongest(presets));
+    }
+
+    /// Stops the running sampling and returns to the station tuned before it.
+    ///
+    /// The sampling may already be finished, in which case its result arrives later
+    /// and is ignored, either way the tuner ends up on the previous station.
+    pub fn cancel_sampling(&mut self, command: &Sender<OutputCommand>) {
+        let Some(previous) = self.sampling_from.take() else {
+            return;
+        };
+
+        SAMPLING_CANCELLED.store(true, Ordering::Relaxed);
+        self.frequency = previous;
+        send_command(command, OutputCommand::SetFrequency(self.frequency));
+        self.notify("Cancelled");
+    }
+
+    /// Shows how many of the presets were already sampled.
+    pub fn sampling_progress(&mut self, sampled: usize, total: usize) {
+        if self.sampling_from.is_some() {
+            self.notify(format!("Checking presets {sampled}/{total}"));
+        }
+    }
+
+    /// Takes over the strongest preset, on which the tuner stayed,
+    /// unless the sampling was cancelled meanwhile.
+    pub fn sampling_finished(&mut self, freq: Frequency, rssi: u8) {
+        if self.sampling_from.take().is_none() {
+            return;
+        }
+
+        self.frequency = freq;
+        self.rssi = rssi;
+        self.recent_stations.record(self.frequency, self.rssi);
+        self.notify(format!("Strongest {}", self.frequency_text(freq)));
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    tuner::send_command, AppState, OutputCommand, Screen, SCREENSAVER_FRAME, SCREENSAVER_TIMEOUT,
+};
+
+/// Number of RSSI samples shown by the screensaver animation
+pub const RSSI_HISTORY_LEN: usize = 32;
+
+impl AppState {
+    /// Notes a user input, returns whether the input only woke up the screensaver,
+    /// in which case it should not be processed any further.
+    pub fn wake_up(&mut self) -> bool {
+        self.last_input = Instant::now();
+        std::mem::replace(&mut self.screensaver, false)
+    }
+
+    /// Asks the tuner to report its actual state, so that the UI shown after a long idle
+    /// time matches the chip, even if some updates were missed.
+    pub fn resync(&self, command: &Sender<OutputCommand>) {
+        send_command(command, OutputCommand::Resync);
+    }
+
+    /// Starts the screensaver after a while without user input, and advances
+    /// its animation by sampling the current RSSI, returns whether it should be redrawn.
+    ///
+    /// Frames are spaced by `SCREENSAVER_FRAME`, so that the display bus is not kept busy.
+    pub fn update_screensaver(&mut self) -> bool {
+        let now = Instant::now();
+
+        if !self.screensaver {
+            if now - self.last_input < SCREENSAVER_TIMEOUT {
+                return false;
+            }
+            self.screensaver = true;
+            self.rssi_history.clear();
+        } else if now - self.last_frame < SCREENSAVER_FRAME {
+            return false;
+        }
+
+        if self.rssi_history.len() == RSSI_HISTORY_LEN {
+            self.rssi_history.pop_front();
+        }
+        self.rssi_history.push_back(self.rssi);
+        self.last_frame = now;
+
+        true
+    }
+
+    /// Returns to the home screen after `home_timeout` without user input, returns whether
+    /// it should be redrawn. A running screensaver is not interrupted, the home screen
+    /// is then shown once it wakes up.
+    pub fn return_home_when_idle(&mut self) -> bool {
+        // a calibration waits for the user, it may be taking a while to find a station,
+        // and learning a remote may take a while to find its keys
+        let timeout = Duration::from_secs(self.settings.home_timeout as u64);
+        if timeout.is_zero()
+            || matches!(
+                self.screen,
+                Screen::Home | Screen::Calibration | Screen::RemoteLearning
+            )
+            || self.last_input.elapsed() < timeout
+        {
+            return false;
+        }
+
+        self.screen = Screen::Home;
+        !self.screensaver
+    }
+}
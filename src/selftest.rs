@@ -0,0 +1,108 @@
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::DrawTarget};
+use esp_idf_svc::nvs::{EspNvs, NvsDefault};
+use ssd1306::{prelude::WriteOnlyDataCommand, size::DisplaySize};
+use std::{
+    sync::mpsc::{channel, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    frequency::Frequency,
+    gui::{draw_report, Display},
+    state::UI_ELEMENT_RING,
+    tuner::request,
+    watchdog::Heartbeat,
+    AppState, InputEvent, OutputCommand,
+};
+
+/// Grid-aligned frequency, which is tuned and read back from the tuner
+const TEST_FREQ: Frequency = Frequency::from_khz(98_000);
+
+/// Volume, at which the audio path is tested
+const TEST_VOLUME: u8 = 5;
+
+/// How long each step of the display and audio tests lasts
+const STEP_DURATION: Duration = Duration::from_millis(1000);
+
+/// Walks through all subsystems, so that a freshly assembled board can be checked:
+/// fills the display, highlights each UI element, reads back a frequency from the tuner,
+/// and mutes and unmutes the audio. Results are shown on the display and logged,
+/// then the self-test waits for any user input and returns to normal operation.
+///
+/// The display is write-only, and audio cannot be measured, so these
+/// have to be confirmed by the user, only their bus errors are detected.
+pub fn run_self_test<DI: WriteOnlyDataCommand, SIZE: DisplaySize>(
+    state: &mut AppState,
+    mut display: Option<&mut Display<DI, SIZE>>,
+    command: &Sender<OutputCommand>,
+    event_receiver: &Receiver<InputEvent>,
+    nvs: &mut EspNvs<NvsDefault>,
+    heartbeat: &Heartbeat,
+) {
+    let mut results = Vec::new();
+    let report = |name: &str, passed: bool, results: &mut Vec<(String, String)>| {
+        let result = if passed { "pass" } else { "fail" };
+        log::info!("self-test: {name} {result}");
+        results.push((name.to_string(), result.to_string()));
+    };
+
+    // display, all pixels lit
+    let passed = display
+        .as_deref_mut()
+        .is_some_and(|display| display.clear(BinaryColor::On).is_ok() && display.flush().is_ok());
+    thread::sleep(STEP_DURATION);
+    report("display", passed, &mut results);
+
+    // highlight of each UI element
+    let passed = match display.as_deref_mut() {
+        Some(display) => UI_ELEMENT_RING.iter().all(|&element| {
+            heartbeat.feed();
+            state.cursor_at = element;
+            let drawn = state.update_ui(display).is_ok() && display.flush().is_ok();
+            thread::sleep(STEP_DURATION / 4);
+            drawn
+        }),
+        None => false,
+    };
+    state.cursor_at = UI_ELEMENT_RING[0];
+    report("ui", passed, &mut results);
+    heartbeat.feed();
+
+    // tuner register read back
+    let (reply, frequency) = channel();
+    let passed = request(command, OutputCommand::SetFrequency(TEST_FREQ)).is_ok()
+        && command.send(OutputCommand::GetFrequency(reply)).is_ok()
+        && frequency.recv_timeout(STEP_DURATION) == Ok(TEST_FREQ);
+    report("tuner", passed, &mut results);
+    request(command, OutputCommand::SetFrequency(state.frequency)).unwrap_or(());
+
+    // audio path, unmuted, muted and unmuted again for the user to listen to
+    if let Some(display) = display.as_deref_mut() {
+        let mut rows = results.clone();
+        rows.push(("audio".to_string(), "listen".to_string()));
+        draw_report("Self-test", &rows, display).unwrap_or(());
+    }
+    let passed = [TEST_VOLUME, 0, TEST_VOLUME].iter().all(|&volume| {
+        heartbeat.feed();
+        let set = request(command, OutputCommand::SetVolume(volume)).is_ok();
+        thread::sleep(STEP_DURATION);
+        set
+    });
+    request(command, OutputCommand::SetVolume(state.volume)).unwrap_or(());
+    report("audio", passed, &mut results);
+
+    // keep the results shown until the user continues
+    if let Some(display) = display {
+        draw_report("Self-test done, press", &results, display).unwrap_or(());
+    }
+
+    loop {
+        heartbeat.feed();
+        match event_receiver.recv_timeout(STEP_DURATION) {
+            Ok(event) if event.is_user_input() => break,
+            Ok(event) => state.process_event(event, command, nvs),
+            Err(_) => (),
+        }
+    }
+}
@@ -0,0 +1,996 @@
+use esp_idf_svc::nvs::{EspNvs, NvsDefault};
+use std::sync::mpsc::Sender;
+
+use crate::{
+    auto_off::AUTO_OFF_LIMITS,
+    confirm::{set_seek_confirm, SEEK_CONFIRM_DURATIONS},
+    display::BRIGHTNESS_LEVELS,
+    frequency::{set_band_plan, Band, Frequency, Spacing},
+    remote::REMOTE_KEYS,
+    storage::SETTINGS_KEY,
+    tuner::send_command,
+    AppState, BootSearch, Deemphasis, EncoderAction, FullPresets, HomeLayout, InfoLine, InputEvent,
+    OutputCommand, Readout, ScanCriteria, Screen, SeekButtons, Setting, Settings, SignalMeter,
+    StereoMode,
+};
+
+/// Step, in which the minimum RSSI for station info is changed
+const INFO_MIN_RSSI_STEP: u8 = 10;
+/// Highest selectable minimum RSSI for station info
+const MAX_INFO_MIN_RSSI: u8 = 60;
+
+/// Step, in which the seek threshold is changed
+const SEEK_THRESHOLD_STEP: u8 = 5;
+/// Lowest and highest selectable seek threshold
+const SEEK_THRESHOLD_RANGE: (u8, u8) = (20, 50);
+
+/// Selectable timeouts for returning to the home screen, in seconds, zero disables it
+const HOME_TIMEOUTS: [u8; 4] = [0, 15, 30, 60];
+
+/// Largest selectable volume step
+const MAX_VOLUME_STEP: u8 = 3;
+
+/// Selectable highest volumes, the tuner supports up to 15
+const MAX_VOLUMES: [u8; 6] = [15, 12, 10, 8, 6, 4];
+
+/// Largest volume trim in either direction
+const MAX_VOLUME_TRIM: i8 = 3;
+
+/// Version of the stored settings blob, it has to be increased when
+/// the meaning of a stored value changes, new settings only extend the blob
+const SETTINGS_VERSION: u8 = 1;
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            scan_criteria: ScanCriteria::RssiStereo,
+            invert_encoder: false,
+            rds_enabled: true,
+            info_min_rssi: 0,
+            tune_preview: true,
+            home_layout: HomeLayout::Buttons,
+            preset_volume: false,
+            seek_threshold: 35,
+            seek_reverse: false,
+            signal_meter: SignalMeter::Off,
+            volume_trim: 0,
+            boot_search: BootSearch::Off,
+            home_timeout: 0,
+            max_volume: 15,
+            encoder_action: EncoderAction::Navigate,
+            brightness: 2,
+            startup_ramp: false,
+            seek_buttons: SeekButtons::Seek,
+            stereo_mode: StereoMode::Auto,
+            seek_confirm: 0,
+            auto_brightness: true,
+            info_line: InfoLine::RadioText,
+            volume_step: 1,
+            readout: Readout::Mhz,
+            mute_hold: false,
+            auto_off: 0,
+            confirm_recall: false,
+            full_presets: FullPresets::Refuse,
+            band: Band::Wide,
+            spacing: Spacing::Khz100,
+            deemphasis: Deemphasis::Us75,
+        }
+    }
+}
+
+impl Setting {
+    /// All settings, in the order in which they are listed
+    pub const ALL: [Setting; 43] = [
+        Setting::ScanCriteria,
+        Setting::InvertEncoder,
+        Setting::EncoderAction,
+        Setting::Rds,
+        Setting::InfoMinRssi,
+        Setting::InfoLine,
+        Setting::Readout,
+        Setting::StereoMode,
+        Setting::Band,
+        Setting::Spacing,
+        Setting::Deemphasis,
+        Setting::TunePreview,
+        Setting::HomeLayout,
+        Setting::SignalMeter,
+        Setting::CalibrateMeter,
+        Setting::Brightness,
+        Setting::AutoBrightness,
+        Setting::PresetVolume,
+        Setting::ConfirmRecall,
+        Setting::FullPresets,
+        Setting::QuickSave,
+        Setting::VolumeTrim,
+        Setting::MaxVolume,
+        Setting::VolumeStep,
+        Setting::MuteButton,
+        Setting::LearnRemote,
+        Setting::StartupRamp,
+        Setting::SeekButtons,
+        Setting::SeekThreshold,
+        Setting::SeekReverse,
+        Setting::SeekConfirm,
+        Setting::BootSearch,
+        Setting::HomeTimeout,
+        Setting::AutoOff,
+        Setting::Favorites,
+        Setting::Recent,
+        Setting::Spectrum,
+        Setting::RdsStats,
+        Setting::TextHistory,
+        Setting::About,
+        Setting::Help,
+        Setting::SleepTimer,
+        Setting::Lock,
+    ];
+
+    /// Stored settings, in the order of their values in the settings blob,
+    /// new settings must only be appended, so that older blobs still load
+    const STORED: [Setting; 31] = [
+        Setting::ScanCriteria,
+        Setting::InvertEncoder,
+        Setting::Rds,
+        Setting::InfoMinRssi,
+        Setting::TunePreview,
+        Setting::HomeLayout,
+        Setting::PresetVolume,
+        Setting::SeekThreshold,
+        Setting::SeekReverse,
+        Setting::SignalMeter,
+        Setting::VolumeTrim,
+        Setting::BootSearch,
+        Setting::HomeTimeout,
+        Setting::MaxVolume,
+        Setting::EncoderAction,
+        Setting::Brightness,
+        Setting::StartupRamp,
+        Setting::SeekButtons,
+        Setting::StereoMode,
+        Setting::SeekConfirm,
+        Setting::AutoBrightness,
+        Setting::InfoLine,
+        Setting::VolumeStep,
+        Setting::Readout,
+        Setting::MuteButton,
+        Setting::AutoOff,
+        Setting::ConfirmRecall,
+        Setting::FullPresets,
+        Setting::Band,
+        Setting::Spacing,
+        Setting::Deemphasis,
+    ];
+
+    /// name of the setting shown on the settings screen
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::ScanCriteria => "Scan",
+            Setting::InvertEncoder => "Invert encoder",
+            Setting::Rds => "RDS",
+            Setting::InfoMinRssi => "RDS min RSSI",
+            Setting::TunePreview => "Tune preview",
+            Setting::HomeLayout => "Home",
+            Setting::SignalMeter => "Signal meter",
+            Setting::VolumeTrim => "Volume trim",
+            Setting::BootSearch => "Boot search",
+            Setting::HomeTimeout => "Return home",
+            Setting::MaxVolume => "Max volume",
+            Setting::EncoderAction => "Encoder",
+            Setting::Brightness => "Brightness",
+            Setting::StartupRamp => "Fade in",
+            Setting::SeekButtons => "Seek buttons",
+            Setting::StereoMode => "Stereo",
+            Setting::SeekConfirm => "Seek confirm",
+            Setting::AutoBrightness => "Auto bright",
+            Setting::InfoLine => "Info line",
+            Setting::VolumeStep => "Volume step",
+            Setting::Readout => "Readout",
+            Setting::MuteButton => "Mute button",
+            Setting::AutoOff => "Auto off",
+            Setting::ConfirmRecall => "Confirm preset",
+            Setting::FullPresets => "Presets full",
+            Setting::Band => "Band",
+            Setting::Spacing => "Channel step",
+            Setting::Deemphasis => "De-emphasis",
+            Setting::PresetVolume => "Preset volume",
+            Setting::SeekThreshold => "Seek threshold",
+            Setting::SeekReverse => "Seek band end",
+            Setting::Favorites => "Favorites",
+            Setting::Recent => "Strong stations",
+            Setting::Spectrum => "Band activity",
+            Setting::RdsStats => "RDS stats",
+            Setting::TextHistory => "Radio texts",
+            Setting::CalibrateMeter => "Calibrate meter",
+            Setting::LearnRemote => "Learn remote",
+            Setting::About => "About",
+            Setting::Help => "Help",
+            Setting::SleepTimer => "Sleep timer",
+            Setting::Lock => "Lock controls",
+            Setting::QuickSave => "Quick save",
+        }
+    }
+
+    /// name of the NVS variable, in which the setting was stored
+    /// before all settings were stored in one blob
+    fn legacy_key(self) -> Option<&'static str> {
+        match self {
+            Setting::ScanCriteria => Some("scan_criteria"),
+            Setting::InvertEncoder => Some("invert_encoder"),
+            Setting::Rds => Some("rds"),
+            Setting::InfoMinRssi => Some("info_min_rssi"),
+            Setting::TunePreview => Some("tune_preview"),
+            Setting::HomeLayout => Some("home_layout"),
+            Setting::PresetVolume => Some("preset_volume"),
+            Setting::SeekThreshold
+            | Setting::SeekReverse
+            | Setting::SignalMeter
+            | Setting::VolumeTrim
+            | Setting::BootSearch
+            | Setting::HomeTimeout
+            | Setting::MaxVolume
+            | Setting::EncoderAction
+            | Setting::Brightness
+            | Setting::StartupRamp
+            | Setting::SeekButtons
+            | Setting::StereoMode
+            | Setting::SeekConfirm
+            | Setting::AutoBrightness
+            | Setting::InfoLine
+            | Setting::VolumeStep
+            | Setting::Readout
+            | Setting::MuteButton
+            | Setting::AutoOff
+            | Setting::ConfirmRecall
+            | Setting::FullPresets
+            | Setting::Band
+            | Setting::Spacing
+            | Setting::Deemphasis
+            | Setting::Favorites
+            | Setting::Recent
+            | Setting::Spectrum
+            | Setting::RdsStats
+            | Setting::TextHistory
+            | Setting::CalibrateMeter
+            | Setting::LearnRemote
+            | Setting::About
+            | Setting::Help
+            | Setting::SleepTimer
+            | Setting::Lock
+            | Setting::QuickSave => None,
+        }
+    }
+}
+
+impl ScanCriteria {
+    /// returns the next criteria in a loop
+    fn next(self) -> Self {
+        match self {
+            ScanCriteria::Rssi => ScanCriteria::RssiStereo,
+            ScanCriteria::RssiStereo => ScanCriteria::RssiRds,
+            ScanCriteria::RssiRds => ScanCriteria::Rssi,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ScanCriteria::Rssi => "RSSI",
+            ScanCriteria::RssiStereo => "RSSI+ST",
+            ScanCriteria::RssiRds => "RSSI+RDS",
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(ScanCriteria::Rssi),
+            1 => Some(ScanCriteria::RssiStereo),
+            2 => Some(ScanCriteria::RssiRds),
+            _ => None,
+        }
+    }
+}
+
+/// formats a boolean setting for the settings screen
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+impl AppState {
+    /// Returns the current value of the setting, formatted for the settings screen.
+    pub fn setting_value(&self, setting: Setting) -> String {
+        match setting {
+            Setting::ScanCriteria => self.settings.scan_criteria.label().to_string(),
+            Setting::InvertEncoder => on_off(self.settings.invert_encoder).to_string(),
+            Setting::Rds => on_off(self.settings.rds_enabled).to_string(),
+            Setting::InfoMinRssi => match self.settings.info_min_rssi {
+                0 => "off".to_string(),
+                rssi => rssi.to_string(),
+            },
+            Setting::TunePreview => on_off(self.settings.tune_preview).to_string(),
+            Setting::HomeLayout => match self.settings.home_layout {
+                HomeLayout::Buttons => "buttons".to_string(),
+                HomeLayout::Dial => "dial".to_string(),
+                HomeLayout::Minimal => "minimal".to_string(),
+            },
+            Setting::SignalMeter => match self.settings.signal_meter {
+                SignalMeter::Off => "off".to_string(),
+                SignalMeter::Bars => "bars".to_string(),
+                SignalMeter::Arc => "arc".to_string(),
+            },
+            Setting::VolumeTrim => match self.settings.volume_trim {
+                0 => "0".to_string(),
+                trim => format!("{trim:+}"),
+            },
+            Setting::BootSearch => match self.settings.boot_search {
+                BootSearch::Off => "off".to_string(),
+                BootSearch::Seek => "seek".to_string(),
+                BootSearch::Scan => "scan".to_string(),
+            },
+            Setting::HomeTimeout => match self.settings.home_timeout {
+                0 => "off".to_string(),
+                seconds => format!("{seconds} s"),
+            },
+            Setting::AutoOff => match self.settings.auto_off {
+                0 => "off".to_string(),
+                minutes => format!("{minutes} min"),
+            },
+            Setting::MaxVolume => self.settings.max_volume.to_string(),
+            Setting::EncoderAction => match self.settings.encoder_action {
+                EncoderAction::Navigate => "navigate".to_string(),
+                EncoderAction::Volume => "volume".to_string(),
+            },
+            Setting::Brightness => (self.settings.brightness + 1).to_string(),
+            Setting::StartupRamp => on_off(self.settings.startup_ramp).to_string(),
+            Setting::SeekButtons => match self.settings.seek_buttons {
+                SeekButtons::Seek => "seek".to_string(),
+                SeekButtons::Step => "step".to_string(),
+                SeekButtons::Nearest => "nearest".to_string(),
+            },
+            Setting::StereoMode => match self.settings.stereo_mode {
+                StereoMode::Auto => "auto".to_string(),
+                StereoMode::ForceMono => "mono".to_string(),
+                StereoMode::ForceStereo => "stereo".to_string(),
+            },
+            Setting::SeekConfirm => {
+                match SEEK_CONFIRM_DURATIONS[self.settings.seek_confirm as usize] {
+                    duration if duration.is_zero() => "off".to_string(),
+                    duration => format!("{} ms", duration.as_millis()),
+                }
+            }
+            Setting::AutoBrightness => on_off(self.settings.auto_brightness).to_string(),
+            Setting::InfoLine => match self.settings.info_line {
+                InfoLine::RadioText => "text".to_string(),
+                InfoLine::ProgramType => "type".to_string(),
+                InfoLine::Clock => "clock".to_string(),
+                InfoLine::TunedTime => "tuned".to_string(),
+            },
+            Setting::VolumeStep => self.settings.volume_step.to_string(),
+            Setting::Readout => match self.settings.readout {
+                Readout::Mhz => "MHz".to_string(),
+                Readout::Channel => "channel".to_string(),
+                Readout::Both => "both".to_string(),
+            },
+            Setting::MuteButton => match self.settings.mute_hold {
+                false => "toggle".to_string(),
+                true => "hold".to_string(),
+            },
+            Setting::PresetVolume => on_off(self.settings.preset_volume).to_string(),
+            Setting::ConfirmRecall => on_off(self.settings.confirm_recall).to_string(),
+            Setting::FullPresets => match self.settings.full_presets {
+                FullPresets::Refuse => "refuse".to_string(),
+                FullPresets::Oldest => "oldest".to_string(),
+                FullPresets::Weakest => "weakest".to_string(),
+            },
+            Setting::Band => {
+                let (start, end) = self.settings.band.range_khz();
+                format!("{}-{}", start / 1000, end / 1000)
+            }
+            Setting::Spacing => format!("{} kHz", self.settings.spacing.khz()),
+            Setting::Deemphasis => match self.settings.deemphasis {
+                Deemphasis::Us50 => "50 us".to_string(),
+                Deemphasis::Us75 => "75 us".to_string(),
+            },
+            Setting::SeekThreshold => self.settings.seek_threshold.to_string(),
+            Setting::SeekReverse => match self.settings.seek_reverse {
+                false => "wrap".to_string(),
+                true => "reverse".to_string(),
+            },
+            Setting::Favorites => self.favorites.len().to_string(),
+            Setting::Recent => self.recent_stations.stations().len().to_string(),
+            Setting::Spectrum => self.stations.len().to_string(),
+            Setting::TextHistory => self.text_history.len().to_string(),
+            Setting::CalibrateMeter => format!("{}-{}", self.meter_range.0, self.meter_range.1),
+            Setting::LearnRemote => format!("{}/{}", self.remote_keys_learned(), REMOTE_KEYS),
+            Setting::SleepTimer => match self.sleep_remaining() {
+                Some(remaining) => format!("{} min", remaining.as_secs().div_ceil(60)),
+                None => "off".to_string(),
+            },
+            Setting::RdsStats
+            | Setting::About
+            | Setting::Help
+            | Setting::Lock
+            | Setting::QuickSave => String::new(),
+        }
+    }
+
+    /// Returns the current value of the setting, as it is stored in NVS.
+    fn setting_raw(&self, setting: Setting) -> u8 {
+        match setting {
+            Setting::ScanCriteria => self.settings.scan_criteria as u8,
+            Setting::InvertEncoder => self.settings.invert_encoder as u8,
+            Setting::Rds => self.settings.rds_enabled as u8,
+            Setting::InfoMinRssi => self.settings.info_min_rssi,
+            Setting::TunePreview => self.settings.tune_preview as u8,
+            Setting::HomeLayout => self.settings.home_layout as u8,
+            Setting::SignalMeter => self.settings.signal_meter as u8,
+            Setting::VolumeTrim => self.settings.volume_trim as u8,
+            Setting::BootSearch => self.settings.boot_search as u8,
+            Setting::HomeTimeout => self.settings.home_timeout,
+            Setting::MaxVolume => self.settings.max_volume,
+            Setting::EncoderAction => self.settings.encoder_action as u8,
+            Setting::Brightness => self.settings.brightness,
+            Setting::StartupRamp => self.settings.startup_ramp as u8,
+            Setting::SeekButtons => self.settings.seek_buttons as u8,
+            Setting::StereoMode => self.settings.stereo_mode as u8,
+            Setting::SeekConfirm => self.settings.seek_confirm,
+            Setting::AutoBrightness => self.settings.auto_brightness as u8,
+            Setting::InfoLine => self.settings.info_line as u8,
+            Setting::VolumeStep => self.settings.volume_step,
+            Setting::Readout => self.settings.readout as u8,
+            Setting::MuteButton => self.settings.mute_hold as u8,
+            Setting::AutoOff => self.settings.auto_off,
+            Setting::ConfirmRecall => self.settings.confirm_recall as u8,
+            Setting::FullPresets => self.settings.full_presets as u8,
+            Setting::Band => self.settings.band as u8,
+            Setting::Spacing => self.settings.spacing as u8,
+            Setting::Deemphasis => self.settings.deemphasis as u8,
+            Setting::PresetVolume => self.settings.preset_volume as u8,
+            Setting::SeekThreshold => self.settings.seek_threshold,
+            Setting::SeekReverse => self.settings.seek_reverse as u8,
+            Setting::Favorites
+            | Setting::Recent
+            | Setting::Spectrum
+            | Setting::RdsStats
+            | Setting::TextHistory
+            | Setting::CalibrateMeter
+            | Setting::LearnRemote
+            | Setting::About
+            | Setting::Help
+            | Setting::SleepTimer
+            | Setting::Lock
+            | Setting::QuickSave => 0,
+        }
+    }
+
+    /// Sets the setting from its stored value, invalid values are ignored.
+    fn set_setting_raw(&mut self, setting: Setting, value: u8) {
+        match setting {
+            Setting::ScanCriteria => {
+                if let Some(criteria) = ScanCriteria::from_u8(value) {
+                    self.settings.scan_criteria = criteria;
+                }
+            }
+            Setting::InvertEncoder => self.settings.invert_encoder = value != 0,
+            Setting::Rds => self.settings.rds_enabled = value != 0,
+            Setting::InfoMinRssi => self.settings.info_min_rssi = value.min(MAX_INFO_MIN_RSSI),
+            Setting::TunePreview => self.settings.tune_preview = value != 0,
+            Setting::HomeLayout => {
+                self.settings.home_layout = match value {
+                    0 => HomeLayout::Buttons,
+                    1 => HomeLayout::Dial,
+                    _ => HomeLayout::Minimal,
+                }
+            }
+            Setting::SignalMeter => {
+                self.settings.signal_meter = match value {
+                    0 => SignalMeter::Off,
+                    1 => SignalMeter::Bars,
+                    _ => SignalMeter::Arc,
+                }
+            }
+            Setting::VolumeTrim => {
+                self.settings.volume_trim = (value as i8).clamp(-MAX_VOLUME_TRIM, MAX_VOLUME_TRIM)
+            }
+            Setting::BootSearch => {
+                self.settings.boot_search = match value {
+                    0 => BootSearch::Off,
+                    1 => BootSearch::Seek,
+                    _ => BootSearch::Scan,
+                }
+            }
+            Setting::HomeTimeout => {
+                if HOME_TIMEOUTS.contains(&value) {
+                    self.settings.home_timeout = value;
+                }
+            }
+            Setting::MaxVolume => {
+                if MAX_VOLUMES.contains(&value) {
+                    self.settings.max_volume = value;
+                }
+            }
+            Setting::EncoderAction => {
+                self.settings.encoder_action = match value {
+                    0 => EncoderAction::Navigate,
+                    _ => EncoderAction::Volume,
+                }
+            }
+            Setting::Brightness => {
+                if (value as usize) < BRIGHTNESS_LEVELS.len() {
+                    self.settings.brightness = value;
+                }
+            }
+            Setting::PresetVolume => self.settings.preset_volume = value != 0,
+            Setting::SeekThreshold => {
+                let (min, max) = SEEK_THRESHOLD_RANGE;
+                self.settings.seek_threshold = value.clamp(min, max);
+            }
+            Setting::SeekReverse => self.settings.seek_reverse = value != 0,
+            Setting::StartupRamp => self.settings.startup_ramp = value != 0,
+            Setting::SeekButtons => {
+                self.settings.seek_buttons = match value {
+                    0 => SeekButtons::Seek,
+                    1 => SeekButtons::Step,
+                    _ => SeekButtons::Nearest,
+                }
+            }
+            Setting::StereoMode => {
+                self.settings.stereo_mode = match value {
+                    0 => StereoMode::Auto,
+                    1 => StereoMode::ForceMono,
+                    _ => StereoMode::ForceStereo,
+                }
+            }
+            Setting::SeekConfirm => {
+                if (value as usize) < SEEK_CONFIRM_DURATIONS.len() {
+                    self.settings.seek_confirm = value;
+                }
+            }
+            Setting::AutoBrightness => self.settings.auto_brightness = value != 0,
+            Setting::InfoLine => {
+                self.settings.info_line = match value {
+                    0 => InfoLine::RadioText,
+                    1 => InfoLine::ProgramType,
+                    2 => InfoLine::Clock,
+                    _ => InfoLine::TunedTime,
+                }
+            }
+            Setting::VolumeStep => {
+                if (1..=MAX_VOLUME_STEP).contains(&value) {
+                    self.settings.volume_step = value;
+                }
+            }
+            Setting::Readout => {
+                self.settings.readout = match value {
+                    0 => Readout::Mhz,
+                    1 => Readout::Channel,
+                    _ => Readout::Both,
+                }
+            }
+            Setting::MuteButton => self.settings.mute_hold = value != 0,
+            Setting::AutoOff => {
+                if AUTO_OFF_LIMITS.contains(&value) {
+                    self.settings.auto_off = value;
+                }
+            }
+            Setting::ConfirmRecall => self.settings.confirm_recall = value != 0,
+            Setting::FullPresets => {
+                self.settings.full_presets = match value {
+                    0 => FullPresets::Refuse,
+                    1 => FullPresets::Oldest,
+                    _ => FullPresets::Weakest,
+                }
+            }
+            Setting::Band => {
+                if let Some(band) = Band::from_u8(value) {
+                    self.settings.band = band;
+                }
+            }
+            Setting::Spacing => {
+                if let Some(spacing) = Spacing::from_u8(value) {
+                    self.settings.spacing = spacing;
+                }
+            }
+            Setting::Deemphasis => {
+                self.settings.deemphasis = match value {
+                    0 => Deemphasis::Us50,
+                    _ => Deemphasis::Us75,
+                }
+            }
+            Setting::Favorites
+            | Setting::Recent
+            | Setting::Spectrum
+            | Setting::RdsStats
+            | Setting::TextHistory
+            | Setting::CalibrateMeter
+            | Setting::LearnRemote
+            | Setting::About
+            | Setting::Help
+            | Setting::SleepTimer
+            | Setting::Lock
+            | Setting::QuickSave => (),
+        }
+    }
+
+    /// Changes the setting to its next possible value, applies and stores it.
+    fn change_setting(
+        &mut self,
+        setting: Setting,
+        command: &Sender<OutputCommand>,
+        nvs: &mut EspNvs<NvsDefault>,
+    ) {
+        match setting {
+            Setting::ScanCriteria => {
+                self.settings.scan_criteria = self.settings.scan_criteria.next()
+            }
+            Setting::InvertEncoder => self.settings.invert_encoder = !self.settings.invert_encoder,
+            Setting::Rds => {
+                self.settings.rds_enabled = !self.settings.rds_enabled;
+                send_command(command, OutputCommand::SetRds(self.settings.rds_enabled));
+            }
+            Setting::InfoMinRssi => {
+                self.settings.info_min_rssi = if self.settings.info_min_rssi >= MAX_INFO_MIN_RSSI {
+                    0
+                } else {
+                    self.settings.info_min_rssi + INFO_MIN_RSSI_STEP
+                }
+            }
+            Setting::TunePreview => self.settings.tune_preview = !self.settings.tune_preview,
+            Setting::HomeLayout => {
+                self.settings.home_layout = match self.settings.home_layout {
+                    HomeLayout::Buttons => HomeLayout::Dial,
+                    HomeLayout::Dial => HomeLayout::Minimal,
+                    HomeLayout::Minimal => HomeLayout::Buttons,
+                }
+            }
+            Setting::SignalMeter => {
+                self.settings.signal_meter = match self.settings.signal_meter {
+                    SignalMeter::Off => SignalMeter::Bars,
+                    SignalMeter::Bars => SignalMeter::Arc,
+                    SignalMeter::Arc => SignalMeter::Off,
+                }
+            }
+            Setting::VolumeTrim => {
+                self.settings.volume_trim = if self.settings.volume_trim >= MAX_VOLUME_TRIM {
+                    -MAX_VOLUME_TRIM
+                } else {
+                    self.settings.volume_trim + 1
+                };
+                send_command(
+                    command,
+                    OutputCommand::SetVolumeTrim(self.settings.volume_trim),
+                );
+            }
+            Setting::BootSearch => {
+                self.settings.boot_search = match self.settings.boot_search {
+                    BootSearch::Off => BootSearch::Seek,
+                    BootSearch::Seek => BootSearch::Scan,
+                    BootSearch::Scan => BootSearch::Off,
+                }
+            }
+            Setting::HomeTimeout => {
+                let index = HOME_TIMEOUTS
+                    .iter()
+                    .position(|&timeout| timeout == self.settings.home_timeout)
+                    .unwrap_or(0);
+                self.settings.home_timeout = HOME_TIMEOUTS[(index + 1) % HOME_TIMEOUTS.len()];
+            }
+            Setting::MaxVolume => {
+                let index = MAX_VOLUMES
+                    .iter()
+                    .position(|&volume| volume == self.settings.max_volume)
+                    .unwrap_or(0);
+                self.settings.max_volume = MAX_VOLUMES[(index + 1) % MAX_VOLUMES.len()];
+
+                // a lower ceiling applies right away
+                if self.volume > self.settings.max_volume {
+                    self.volume = self.settings.max_volume;
+                    send_command(command, OutputCommand::SetVolume(self.output_volume()));
+                }
+            }
+            Setting::EncoderAction => {
+                self.settings.encoder_action = match self.settings.encoder_action {
+                    EncoderAction::Navigate => EncoderAction::Volume,
+                    EncoderAction::Volume => EncoderAction::Navigate,
+                };
+                self.reset_encoder_action();
+            }
+            Setting::Brightness => {
+                self.settings.brightness =
+                    (self.settings.brightness + 1) % BRIGHTNESS_LEVELS.len() as u8
+            }
+            Setting::PresetVolume => self.settings.preset_volume = !self.settings.preset_volume,
+            Setting::SeekThreshold => {
+                let (min, max) = SEEK_THRESHOLD_RANGE;
+                self.settings.seek_threshold = if self.settings.seek_threshold >= max {
+                    min
+                } else {
+                    self.settings.seek_threshold + SEEK_THRESHOLD_STEP
+                };
+                send_command(
+                    command,
+                    OutputCommand::SetSeekThreshold(self.settings.seek_threshold),
+                );
+            }
+            Setting::SeekReverse => self.settings.seek_reverse = !self.settings.seek_reverse,
+            Setting::StartupRamp => self.settings.startup_ramp = !self.settings.startup_ramp,
+            Setting::SeekButtons => {
+                self.settings.seek_buttons = match self.settings.seek_buttons {
+                    SeekButtons::Seek => SeekButtons::Step,
+                    SeekButtons::Step => SeekButtons::Nearest,
+                    SeekButtons::Nearest => SeekButtons::Seek,
+                }
+            }
+            Setting::StereoMode => {
+                self.settings.stereo_mode = match self.settings.stereo_mode {
+                    StereoMode::Auto => StereoMode::ForceMono,
+                    StereoMode::ForceMono => StereoMode::ForceStereo,
+                    StereoMode::ForceStereo => StereoMode::Auto,
+                };
+                send_command(
+                    command,
+                    OutputCommand::SetStereoMode(self.settings.stereo_mode),
+                );
+            }
+            Setting::SeekConfirm => {
+                self.settings.seek_confirm =
+                    (self.settings.seek_confirm + 1) % SEEK_CONFIRM_DURATIONS.len() as u8;
+                set_seek_confirm(SEEK_CONFIRM_DURATIONS[self.settings.seek_confirm as usize]);
+            }
+            Setting::AutoBrightness => {
+                self.settings.auto_brightness = !self.settings.auto_brightness
+            }
+            Setting::InfoLine => {
+                self.settings.info_line = match self.settings.info_line {
+                    InfoLine::RadioText => InfoLine::ProgramType,
+                    InfoLine::ProgramType => InfoLine::Clock,
+                    InfoLine::Clock => InfoLine::TunedTime,
+                    InfoLine::TunedTime => InfoLine::RadioText,
+                }
+            }
+            Setting::VolumeStep => {
+                self.settings.volume_step = self.settings.volume_step % MAX_VOLUME_STEP + 1
+            }
+            Setting::Readout => {
+                self.settings.readout = match self.settings.readout {
+                    Readout::Mhz => Readout::Channel,
+                    Readout::Channel => Readout::Both,
+                    Readout::Both => Readout::Mhz,
+                }
+            }
+            Setting::MuteButton => self.settings.mute_hold = !self.settings.mute_hold,
+            Setting::AutoOff => {
+                let index = AUTO_OFF_LIMITS
+                    .iter()
+                    .position(|&limit| limit == self.settings.auto_off)
+                    .unwrap_or(0);
+                self.settings.auto_off = AUTO_OFF_LIMITS[(index + 1) % AUTO_OFF_LIMITS.len()];
+            }
+            Setting::ConfirmRecall => self.settings.confirm_recall = !self.settings.confirm_recall,
+            Setting::FullPresets => {
+                self.settings.full_presets = match self.settings.full_presets {
+                    FullPresets::Refuse => FullPresets::Oldest,
+                    FullPresets::Oldest => FullPresets::Weakest,
+                    FullPresets::Weakest => FullPresets::Refuse,
+                }
+            }
+            Setting::Band => {
+                self.settings.band = match self.settings.band {
+                    Band::Wide => Band::Western,
+                    Band::Western => Band::Japan,
+                    Band::Japan => Band::Wide,
+                };
+                self.change_band_plan(command);
+            }
+            Setting::Spacing => {
+                self.settings.spacing = match self.settings.spacing {
+                    Spacing::Khz100 => Spacing::Khz200,
+                    Spacing::Khz200 => Spacing::Khz50,
+                    Spacing::Khz50 => Spacing::Khz100,
+                };
+                self.change_band_plan(command);
+            }
+            Setting::Deemphasis => {
+                self.settings.deemphasis = match self.settings.deemphasis {
+                    Deemphasis::Us50 => Deemphasis::Us75,
+                    Deemphasis::Us75 => Deemphasis::Us50,
+                };
+                send_command(
+                    command,
+                    OutputCommand::SetDeemphasis(self.settings.deemphasis),
+                );
+            }
+            Setting::Favorites => {
+                self.favorites_cursor = 0;
+                self.screen = Screen::Favorites;
+            }
+            Setting::Recent => {
+                self.recent_cursor = 0;
+                self.screen = Screen::Recent;
+            }
+            Setting::Spectrum => {
+                self.spectrum_cursor = 0;
+                self.screen = Screen::Spectrum;
+            }
+            Setting::RdsStats => {
+                self.rds_stats_cursor = 0;
+                self.screen = Screen::RdsStats;
+            }
+            Setting::TextHistory => {
+                self.text_history_cursor = 0;
+                self.screen = Screen::TextHistory;
+            }
+            Setting::CalibrateMeter => self.start_calibration(),
+            Setting::LearnRemote => self.start_remote_learning(),
+            Setting::About => self.screen = Screen::About,
+            Setting::Help => {
+                self.screen = Screen::Home;
+                self.show_help = true;
+            }
+            Setting::SleepTimer => self.extend_sleep_timer(),
+            Setting::Lock => self.lock(),
+            Setting::QuickSave => self.quick_save(nvs),
+        }
+
+        // settings are clicked through several values in a row, only the last one is written
+        if Setting::STORED.contains(&setting) {
+            self.save_settings();
+        }
+    }
+
+    /// Applies a new band or channel spacing, the tuned frequency moves
+    /// to the nearest channel of the new grid.
+    fn change_band_plan(&mut self, command: &Sender<OutputCommand>) {
+        set_band_plan(self.settings.band, self.settings.spacing);
+        send_command(command, OutputCommand::SetBand(self.settings.band));
+        send_command(command, OutputCommand::SetSpacing(self.settings.spacing));
+
+        self.frequency = Frequency::from_channel(self.frequency.channel());
+        send_command(command, OutputCommand::SetFrequency(self.frequency));
+    }
+
+    /// Sends the settings, which are applied by the tuner, to it,
+    /// the frequency is to be tuned after them, so that it lies in the set band.
+    pub fn apply_settings(&self, command: &Sender<OutputCommand>) {
+        send_command(command, OutputCommand::SetBand(self.settings.band));
+        send_command(command, OutputCommand::SetSpacing(self.settings.spacing));
+        send_command(
+            command,
+            OutputCommand::SetDeemphasis(self.settings.deemphasis),
+        );
+        send_command(command, OutputCommand::SetRds(self.settings.rds_enabled));
+        send_command(
+            command,
+            OutputCommand::SetSeekThreshold(self.settings.seek_threshold),
+        );
+        send_command(
+            command,
+            OutputCommand::SetVolumeTrim(self.settings.volume_trim),
+        );
+        send_command(
+            command,
+            OutputCommand::SetStereoMode(self.settings.stereo_mode),
+        );
+        set_seek_confirm(SEEK_CONFIRM_DURATIONS[self.settings.seek_confirm as usize]);
+    }
+
+    /// Stages all settings to be stored at once, as a blob starting with its version.
+    fn save_settings(&mut self) {
+        let blob = std::iter::once(SETTINGS_VERSION)
+            .chain(Setting::STORED.map(|setting| self.setting_raw(setting)))
+            .collect::<Vec<_>>();
+
+        self.pending_writes.stage(SETTINGS_KEY, blob);
+    }
+
+    /// Loads all stored settings, settings which were never stored keep their default value.
+    ///
+    /// A blob stored by an older version only contains the settings which existed back then,
+    /// the newer ones keep their defaults. Without any blob, the settings are migrated
+    /// from their separate NVS variables.
+    pub fn load_settings(&mut self, nvs: &mut EspNvs<NvsDefault>) {
+        // room for settings added by newer versions
+        let mut buffer = [0; 64];
+
+        match nvs.get_raw(SETTINGS_KEY, &mut buffer) {
+            Ok(Some([version, values @ ..])) if *version <= SETTINGS_VERSION => {
+                for (&setting, &value) in Setting::STORED.iter().zip(values.iter()) {
+                    self.set_setting_raw(setting, value);
+                }
+            }
+            Ok(Some(_)) => log::warn!("Stored settings have an unknown version, using defaults"),
+            Err(err) => log::warn!("Stored settings could not be read, using defaults: {err:?}"),
+            Ok(None) => {
+                for setting in Setting::STORED {
+                    let Some(key) = setting.legacy_key() else {
+                        continue;
+                    };
+                    if let Ok(Some(value)) = nvs.get_u8(key) {
+                        self.set_setting_raw(setting, value);
+                    }
+                }
+                self.save_settings();
+            }
+        }
+
+        // the frequencies restored next are limited to the band
+        set_band_plan(self.settings.band, self.settings.spacing);
+    }
+
+    /// Processes user input on the settings screen.
+    pub fn process_settings_input(
+        &mut self,
+        event: InputEvent,
+        command: &Sender<OutputCommand>,
+        nvs: &mut EspNvs<NvsDefault>,
+    ) {
+        let count = Setting::ALL.len();
+
+        use InputEvent as I;
+        match event {
+            // scrolling through settings in a loop
+            I::ScrollDown => self.settings_cursor = (self.settings_cursor + count - 1) % count,
+            I::ScrollUp => self.settings_cursor = (self.settings_cursor + 1) % count,
+
+            I::ShortPress => self.change_setting(Setting::ALL[self.settings_cursor], command, nvs),
+
+            // return back to the home screen
+            I::LongPress | I::VeryLongPress => self.screen = Screen::Home,
+
+            I::DoublePress | I::Hold => (),
+
+            // events from radio are processed before reaching the screen
+            event => log::debug!("{event:?} ignored on the settings screen"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use esp_idf_svc::nvs::EspDefaultNvsPartition;
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::mpsc,
+    };
+
+    use super::*;
+    use crate::state::tests::user_inputs;
+
+    #[test]
+    fn user_inputs_are_handled() {
+        let partition = EspDefaultNvsPartition::take().unwrap();
+
+        for event in user_inputs() {
+            let mut state = AppState::new();
+            let mut nvs = EspNvs::new(partition.clone(), "test", true).unwrap();
+            let (command, _commands) = mpsc::channel();
+            state.screen = Screen::Settings;
+
+            let handled = panic::catch_unwind(AssertUnwindSafe(|| {
+                state.process_settings_input(event.clone(), &command, &mut nvs)
+            }));
+            assert!(handled.is_ok(), "{event:?} not handled");
+        }
+    }
+
+    #[test]
+    fn tuner_settings_are_stored() {
+        let mut state = AppState::new();
+        state.settings.band = Band::Japan;
+        state.settings.spacing = Spacing::Khz50;
+        state.settings.deemphasis = Deemphasis::Us50;
+        state.settings.seek_threshold = 45;
+
+        let mut loaded = AppState::new();
+        for setting in Setting::STORED {
+            loaded.set_setting_raw(setting, state.setting_raw(setting));
+        }
+        assert_eq!(loaded.settings.band, Band::Japan);
+        assert_eq!(loaded.settings.spacing, Spacing::Khz50);
+        assert_eq!(loaded.settings.deemphasis, Deemphasis::Us50);
+        assert_eq!(loaded.settings.seek_threshold, 45);
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+use crate::InputEvent;
+
+/// RSSI, below which a received station is considered lost
+pub const SIGNAL_LOST_RSSI: u8 = 10;
+/// RSSI, from which a lost station is considered received again
+pub const SIGNAL_ACQUIRED_RSSI: u8 = 16;
+/// How long the RSSI must stay across a threshold, before the change is reported
+pub const SIGNAL_DWELL: Duration = Duration::from_millis(1500);
+
+/// Tracks whether the tuned station is received, from the RSSI measured by the tuner.
+///
+/// A change is reported only after the RSSI crosses the threshold for the other state
+/// and stays there for the dwell time, the gap between the thresholds and the dwell time
+/// keep a fading station from flapping between both states.
+pub struct SignalMonitor {
+    lost_rssi: u8,
+    acquired_rssi: u8,
+    dwell: Duration,
+    /// whether the station is currently considered received
+    received: bool,
+    /// since when the RSSI is across the threshold for the other state
+    crossed_at: Option<Instant>,
+}
+
+impl SignalMonitor {
+    pub fn new(lost_rssi: u8, acquired_rssi: u8, dwell: Duration) -> Self {
+        SignalMonitor {
+            lost_rssi,
+            acquired_rssi,
+            dwell,
+            received: true,
+            crossed_at: None,
+        }
+    }
+
+    /// Restarts the dwell time, after the tuner moved to another frequency.
+    pub fn restart(&mut self) {
+        self.crossed_at = None;
+    }
+
+    /// Notes a new RSSI measurement, returns `SignalLost` or `SignalAcquired`
+    /// when the station was lost or received again.
+    pub fn update(&mut self, rssi: u8) -> Option<InputEvent> {
+        let crossed = if self.received {
+            rssi < self.lost_rssi
+        } else {
+            rssi >= self.acquired_rssi
+        };
+        if !crossed {
+            self.crossed_at = None;
+            return None;
+        }
+
+        let crossed_at = *self.crossed_at.get_or_insert_with(Instant::now);
+        if crossed_at.elapsed() < self.dwell {
+            return None;
+        }
+
+        self.received = !self.received;
+        self.crossed_at = None;
+        Some(if self.received {
+            InputEvent::SignalAcquired
+        } else {
+            InputEvent::SignalLost
+        })
+    }
+}
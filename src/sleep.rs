@@ -0,0 +1,91 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+
+use crate::{tuner::send_command, AppState, InputEvent, OutputCommand, Screen, Setting};
+
+/// Time added to the sleep timer each time it is selected in settings
+const SLEEP_TIMER_STEP: Duration = Duration::from_secs(15 * 60);
+/// Longest time, to which the sleep timer can be extended
+const MAX_SLEEP_TIMER: Duration = Duration::from_secs(120 * 60);
+/// How long before muting the warning is shown, any input then cancels the timer
+const SLEEP_WARNING: Duration = Duration::from_secs(60);
+
+impl AppState {
+    /// Arms the sleep timer, or extends the armed one, up to `MAX_SLEEP_TIMER`.
+    pub fn extend_sleep_timer(&mut self) {
+        let now = Instant::now();
+        let deadline = self.sleep_at.unwrap_or(now).max(now) + SLEEP_TIMER_STEP;
+        self.sleep_at = Some(deadline.min(now + MAX_SLEEP_TIMER));
+
+        let minutes = self
+            .sleep_remaining()
+            .unwrap_or_default()
+            .as_secs()
+            .div_ceil(60);
+        self.notify(format!("Sleep in {minutes} min"));
+    }
+
+    /// Disarms the sleep timer.
+    pub fn cancel_sleep_timer(&mut self) {
+        self.sleep_at = None;
+        self.sleep_countdown_shown = None;
+        self.notify("Sleep timer off");
+    }
+
+    /// Returns the time left until the radio mutes, if the sleep timer is armed.
+    pub fn sleep_remaining(&self) -> Option<Duration> {
+        self.sleep_at
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Returns whether the last minute warning should be shown.
+    pub fn sleep_warning(&self) -> bool {
+        self.sleep_remaining()
+            .is_some_and(|remaining| remaining <= SLEEP_WARNING)
+    }
+
+    /// Returns the remaining time as shown by the GUI, whole minutes rounded up,
+    /// or the seconds left during the last minute warning.
+    pub fn sleep_countdown(&self) -> Option<String> {
+        let remaining = self.sleep_remaining()?;
+        if remaining <= SLEEP_WARNING {
+            Some(format!("Sleep in {} s", remaining.as_secs()))
+        } else {
+            Some(format!("{}m", remaining.as_secs().div_ceil(60)))
+        }
+    }
+
+    /// Whether the input arms the sleep timer, a press on its entry in settings.
+    pub fn is_sleep_gesture(&self, event: &InputEvent) -> bool {
+        self.screen == Screen::Settings
+            && Setting::ALL[self.settings_cursor] == Setting::SleepTimer
+            && *event == InputEvent::ShortPress
+    }
+
+    /// Mutes the radio, once the sleep timer runs out,
+    /// returns whether the countdown or the volume changed and should be redrawn.
+    pub fn update_sleep_timer(&mut self, command: &Sender<OutputCommand>) -> bool {
+        if self
+            .sleep_remaining()
+            .is_some_and(|remaining| remaining.is_zero())
+        {
+            self.sleep_at = None;
+            self.sleep_countdown_shown = None;
+            self.volume = 0;
+            self.volume_ramp = None;
+            send_command(command, OutputCommand::SetVolume(0));
+            self.pending_writes.request_flush();
+            self.notify("Good night");
+            return true;
+        }
+
+        let countdown = self.sleep_countdown();
+        if countdown == self.sleep_countdown_shown {
+            return false;
+        }
+        self.sleep_countdown_shown = countdown;
+        true
+    }
+}
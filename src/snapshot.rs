@@ -0,0 +1,59 @@
+use std::{sync::Mutex, time::Duration};
+
+use crate::{frequency::Frequency, AppState, Screen};
+
+/// Last state published by the event loop, `None` until the first publish
+static SNAPSHOT: Mutex<Option<StateSnapshot>> = Mutex::new(None);
+
+/// Copy of the user-visible application state.
+///
+/// Only the event loop owns `AppState`, other threads like the console
+/// read this copy instead, so they never race the UI or re-derive the state
+/// from the event stream.
+#[derive(Clone, Debug)]
+pub struct StateSnapshot {
+    pub frequency: Frequency,
+    pub volume: u8,
+    pub rssi: u8,
+    pub stereo: bool,
+    pub signal_lost: bool,
+    /// Program Service name from RDS
+    pub station_name: String,
+    /// Radio Text from RDS
+    pub station_info: String,
+    pub seeking: bool,
+    pub locked: bool,
+    pub screen: Screen,
+    /// Time left until the sleep timer mutes the radio, if it is armed
+    pub sleep_remaining: Option<Duration>,
+}
+
+impl AppState {
+    /// Returns a copy of the user-visible state.
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            frequency: self.frequency,
+            volume: self.volume,
+            rssi: self.rssi,
+            stereo: self.tuner_status.stereo,
+            signal_lost: self.signal_lost,
+            station_name: self.station_name.clone(),
+            station_info: self.station_info.clone(),
+            seeking: self.seek_from.is_some(),
+            locked: self.locked,
+            screen: self.screen,
+            sleep_remaining: self.sleep_remaining(),
+        }
+    }
+
+    /// Publishes the current state for other threads, called by the event loop
+    /// whenever the state changed.
+    pub fn publish_snapshot(&self) {
+        *SNAPSHOT.lock().unwrap() = Some(self.snapshot());
+    }
+}
+
+/// Returns the state last published by the event loop, can be called from any thread.
+pub fn current_snapshot() -> Option<StateSnapshot> {
+    SNAPSHOT.lock().unwrap().clone()
+}
@@ -0,0 +1,34 @@
+use std::sync::mpsc::Sender;
+
+use crate::{AppState, InputEvent, OutputCommand, Screen};
+
+impl AppState {
+    /// Processes user input on the spectrum screen with scanned stations.
+    pub fn process_spectrum_input(&mut self, event: InputEvent, command: &Sender<OutputCommand>) {
+        let count = self.stations.len();
+
+        use InputEvent as I;
+        match event {
+            // moving between the bars of scanned stations in a loop
+            I::ScrollDown if count > 0 => {
+                self.spectrum_cursor = (self.spectrum_cursor + count - 1) % count
+            }
+            I::ScrollUp if count > 0 => self.spectrum_cursor = (self.spectrum_cursor + 1) % count,
+
+            // tune the selected station and return to the home screen
+            I::ShortPress if count > 0 => {
+                self.tune_station(self.stations[self.spectrum_cursor].0, command);
+                self.screen = Screen::Home;
+            }
+
+            // return back to the home screen
+            I::LongPress | I::VeryLongPress => self.screen = Screen::Home,
+
+            // ignore all other user inputs
+            I::ScrollDown | I::ScrollUp | I::ShortPress | I::DoublePress | I::Hold => (),
+
+            // events from radio are processed before reaching the screen
+            _ => unreachable!(),
+        }
+    }
+}
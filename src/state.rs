@@ -1,47 +1,415 @@
 use esp_idf_svc::nvs::{EspNvs, NvsDefault};
-use std::sync::mpsc::Sender;
+use std::{collections::VecDeque, sync::mpsc::Sender, time::Instant};
 
-use crate::{AppState, InputEvent, OutputCommand, UIElement, NUM_PRESETS};
+use crate::{
+    calibration::DEFAULT_METER_RANGE,
+    frequency::{channel_spacing_khz, Frequency},
+    nvs_writer::DebouncedWriter,
+    rds::RdsReception,
+    recent::{RecentStations, RECENT_MIN_RSSI},
+    remote::REMOTE_KEYS,
+    screensaver::RSSI_HISTORY_LEN,
+    storage::{
+        complete_station_name, save_stations, MAX_STORED_FREQUENCIES, PRESET_KEYS,
+        PRESET_NAME_KEYS, PRESET_VOLUME_KEYS,
+    },
+    tuner::send_command,
+    verbosity::log_event,
+    AppState, BootSearch, Direction, EncoderAction, HomeLayout, InputEvent, OutputCommand, Screen,
+    SeekButtons, Settings, TunerStatus, UIElement, CONTROLS_TIMEOUT, NOTIFICATION_DURATION,
+    NUM_PRESETS, STARTUP_RAMP_DURATION, STARTUP_VOLUME, TUNE_DEBOUNCE,
+};
+
+/// All UI elements of the home screen, in the order in which the cursor visits them.
+///
+/// Both directions of navigation are derived from this single list,
+/// so that they always visit every element and stay inverse to each other.
+pub const UI_ELEMENT_RING: [UIElement; NUM_PRESETS as usize + 4] = {
+    let mut ring = [UIElement::SeekDown; NUM_PRESETS as usize + 4];
+    ring[1] = UIElement::FreqControl;
+    ring[2] = UIElement::SeekUp;
+
+    let mut preset = 0;
+    while preset < NUM_PRESETS {
+        ring[3 + preset as usize] = UIElement::Preset(preset);
+        preset += 1;
+    }
+
+    ring[NUM_PRESETS as usize + 3] = UIElement::VolumeControl;
+    ring
+};
 
 impl UIElement {
+    /// position of the element in `UI_ELEMENT_RING`
+    fn position(self) -> usize {
+        UI_ELEMENT_RING
+            .iter()
+            .position(|&element| element == self)
+            .unwrap()
+    }
+
     /// returns the previous UI element in a loop
     fn prev(self) -> Self {
-        use UIElement as U;
-
-        match self {
-            U::SeekDown => U::VolumeControl,
-            U::FreqControl => U::SeekDown,
-            U::SeekUp => U::FreqControl,
-            U::Preset(0) => U::SeekUp,
-            U::Preset(x) => U::Preset(x - 1),
-            U::VolumeControl => U::Preset(NUM_PRESETS - 1),
-        }
+        let len = UI_ELEMENT_RING.len();
+        UI_ELEMENT_RING[(self.position() + len - 1) % len]
     }
 
     /// returns the next UI element in a loop
     fn next(self) -> Self {
-        use UIElement as U;
+        UI_ELEMENT_RING[(self.position() + 1) % UI_ELEMENT_RING.len()]
+    }
+}
+
+impl Direction {
+    /// returns the opposite direction
+    fn reversed(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+        }
+    }
+}
 
+impl Screen {
+    /// returns the screen, which this one was opened from
+    fn parent(self) -> Screen {
         match self {
-            U::SeekDown => U::FreqControl,
-            U::FreqControl => U::SeekUp,
-            U::SeekUp => U::Preset(0),
-            U::Preset(x) if x < NUM_PRESETS - 1 => U::Preset(x + 1),
-            U::Preset(_) => U::VolumeControl,
-            U::VolumeControl => U::SeekDown,
+            Screen::Home | Screen::Settings => Screen::Home,
+            Screen::Favorites
+            | Screen::Recent
+            | Screen::About
+            | Screen::RdsStats
+            | Screen::TextHistory
+            | Screen::Calibration
+            | Screen::RemoteLearning
+            | Screen::Spectrum => Screen::Settings,
         }
     }
 }
 
+impl InputEvent {
+    /// whether the event comes from the user, rather than from the tuner
+    pub fn is_user_input(&self) -> bool {
+        use InputEvent as I;
+        matches!(
+            self,
+            I::ShortPress
+                | I::LongPress
+                | I::VeryLongPress
+                | I::DoublePress
+                | I::Hold
+                | I::ScrollDown
+                | I::ScrollUp
+                | I::Back
+                | I::Help
+                | I::QuickSave
+                | I::RemoteCode(_)
+                | I::RemoteRepeat
+        )
+    }
+}
+
 impl AppState {
     pub fn new() -> AppState {
         AppState {
-            freq_khz: 100_000,
+            frequency: Frequency::from_khz(100_000),
             volume: 0,
+            muted: false,
             station_info: "".to_string(),
+            station_name: "".to_string(),
+            station_name_complete: false,
             rssi: 0,
+            tuner_status: TunerStatus::default(),
+            signal_lost: false,
+            sampling_from: None,
+            sleep_at: None,
+            sleep_countdown_shown: None,
+            auto_off_countdown_shown: None,
+            seek_from: None,
+            prev_frequency: None,
+            volume_ramp: None,
+            ambient_brightness: None,
+            program_type: 0,
+            rds_reception: RdsReception::Waiting,
+            meter_range: DEFAULT_METER_RANGE,
+            calibration: None,
+            text_history: Vec::new(),
+            clock: None,
+            tuner_missing: false,
+            show_help: false,
+            info_scroll_from: Instant::now(),
+            tuned_since: None,
+            info_shown: Default::default(),
+            presets: [None; NUM_PRESETS as usize],
+            preset_volumes: [None; NUM_PRESETS as usize],
+            preset_names: Default::default(),
+            preset_name_saved: None,
+            preset_uses: [0; NUM_PRESETS as usize],
+            remote_codes: [0; REMOTE_KEYS],
+            remote_held: None,
+            remote_learning: None,
+            stations: Vec::new(),
+            recent_stations: RecentStations::new(),
+            favorites: Vec::new(),
+            settings: Settings::default(),
+            pending_writes: DebouncedWriter::new(),
+            stored_station: (Frequency::from_khz(100_000), 0),
+            controls_hidden: true,
+            pending_tune: None,
+            screen: Screen::Home,
             cursor_at: UIElement::SeekDown,
             element_is_active: false,
+            volume_jump_from: None,
+            volume_direction: None,
+            boot_scan: false,
+            last_seek: None,
+            seek_reversed: false,
+            locked: false,
+            unlock_progress: 0,
+            tune_indicator: None,
+            notification: None,
+            screensaver: false,
+            last_input: Instant::now(),
+            last_frame: Instant::now(),
+            about_refreshed: Instant::now(),
+            rssi_history: VecDeque::with_capacity(RSSI_HISTORY_LEN),
+            settings_cursor: 0,
+            favorites_cursor: 0,
+            recent_cursor: 0,
+            rds_stats_cursor: 0,
+            text_history_cursor: 0,
+            spectrum_cursor: 0,
+        }
+    }
+
+    /// Shows a short message over the GUI for a while.
+    pub fn notify(&mut self, message: impl Into<String>) {
+        self.notification = Some((message.into(), Instant::now() + NOTIFICATION_DURATION));
+    }
+
+    /// Removes all transient indicators, expired notifications and controls of the minimal
+    /// home layout after inactivity from the GUI, returns whether there was any to remove.
+    pub fn clear_transients(&mut self) -> bool {
+        let expired = self
+            .notification
+            .as_ref()
+            .is_some_and(|(_, until)| Instant::now() >= *until);
+        if expired {
+            self.notification = None;
+        }
+
+        let hide_controls = self.settings.home_layout == HomeLayout::Minimal
+            && !self.controls_hidden
+            && self.last_input.elapsed() >= CONTROLS_TIMEOUT;
+        if hide_controls {
+            self.controls_hidden = true;
+            self.element_is_active = false;
+        }
+
+        let reset_encoder = self.last_input.elapsed() >= CONTROLS_TIMEOUT
+            && !self.locked
+            && self.reset_encoder_action();
+
+        self.tune_indicator.take().is_some() || expired || hide_controls || reset_encoder
+    }
+
+    /// Returns the brightness of the display, an index into `BRIGHTNESS_LEVELS`,
+    /// which follows the ambient light, if enabled and a light sensor reported it.
+    pub fn display_brightness(&self) -> u8 {
+        match self.ambient_brightness {
+            Some(level) if self.settings.auto_brightness => level,
+            _ => self.settings.brightness,
+        }
+    }
+
+    /// Returns the encoder to its default action on the home screen, with the volume
+    /// as the default, the volume control is selected, so that scrolling changes
+    /// the volume and a press starts navigation. Returns whether anything changed.
+    pub fn reset_encoder_action(&mut self) -> bool {
+        let volume_selected = self.cursor_at == UIElement::VolumeControl && self.element_is_active;
+        if self.settings.encoder_action == EncoderAction::Navigate || volume_selected {
+            return false;
+        }
+
+        self.cursor_at = UIElement::VolumeControl;
+        self.element_is_active = true;
+        self.volume_jump_from = None;
+        true
+    }
+
+    /// Tunes to the current frequency, with tune preview the frequency
+    /// is only sent to the tuner after scrolling pauses.
+    fn tune(&mut self, command: &Sender<OutputCommand>) {
+        if self.settings.tune_preview {
+            self.pending_tune = Some(Instant::now() + TUNE_DEBOUNCE);
+        } else {
+            send_command(command, OutputCommand::SetFrequency(self.frequency));
+        }
+    }
+
+    /// Sends the previewed frequency to the tuner, if scrolling paused for long enough.
+    pub fn send_pending_tune(&mut self, command: &Sender<OutputCommand>) {
+        if self
+            .pending_tune
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.flush_pending_tune(command);
+        }
+    }
+
+    /// Immediately sends the previewed frequency to the tuner, if there is one.
+    fn flush_pending_tune(&mut self, command: &Sender<OutputCommand>) {
+        if self.pending_tune.take().is_some() {
+            send_command(command, OutputCommand::SetFrequency(self.frequency));
+        }
+    }
+
+    /// Changes the volume by one step, up to the highest volume allowed in settings.
+    pub fn change_volume(&mut self, direction: Direction, command: &Sender<OutputCommand>) {
+        // a larger step stops at the bounds, instead of overshooting them
+        let step = self.settings.volume_step;
+        let volume = match direction {
+            Direction::Up if self.volume < self.settings.max_volume => {
+                (self.volume + step).min(self.settings.max_volume)
+            }
+            Direction::Down if self.volume > 0 => self.volume.saturating_sub(step),
+            _ => return,
+        };
+        // turning the volume ends the mute
+        self.volume = volume;
+        self.volume_ramp = None;
+        self.muted = false;
+        send_command(command, OutputCommand::SetVolume(self.volume));
+    }
+
+    /// Starts fading the volume in from mute, if enabled in settings.
+    pub fn start_volume_ramp(&mut self, command: &Sender<OutputCommand>) {
+        if !self.settings.startup_ramp {
+            return;
+        }
+        self.volume = 0;
+        self.volume_ramp = Some(Instant::now());
+        send_command(command, OutputCommand::SetVolume(0));
+    }
+
+    /// Raises the volume along the running fade in,
+    /// returns whether the volume changed and should be redrawn.
+    pub fn update_volume_ramp(&mut self, command: &Sender<OutputCommand>) -> bool {
+        let Some(started) = self.volume_ramp else {
+            return false;
+        };
+
+        let target = STARTUP_VOLUME.min(self.settings.max_volume);
+        let elapsed = started.elapsed();
+        let volume = if elapsed >= STARTUP_RAMP_DURATION {
+            self.volume_ramp = None;
+            target
+        } else {
+            (target as u128 * elapsed.as_millis() / STARTUP_RAMP_DURATION.as_millis()) as u8
+        };
+        if volume == self.volume {
+            return false;
+        }
+
+        self.volume = volume;
+        send_command(command, OutputCommand::SetVolume(self.output_volume()));
+        true
+    }
+
+    /// Starts the search for a station selected in settings, right after boot.
+    pub fn start_boot_search(&mut self, command: &Sender<OutputCommand>) {
+        match self.settings.boot_search {
+            BootSearch::Off => (),
+            BootSearch::Seek => self.seek(Direction::Up, command),
+            BootSearch::Scan => {
+                self.boot_scan = true;
+                send_command(
+                    command,
+                    OutputCommand::ScanBand(self.settings.scan_criteria),
+                );
+            }
+        }
+    }
+
+    /// Tunes the strongest of the scanned stations.
+    fn tune_strongest(&mut self, stations: &[(Frequency, u8)], command: &Sender<OutputCommand>) {
+        if let Some(&(freq, _)) = stations.iter().max_by_key(|(_, rssi)| rssi) {
+            self.frequency = freq;
+            send_command(command, OutputCommand::SetFrequency(freq));
+        }
+    }
+
+    /// Tunes a station picked by the user, the station tuned before is remembered,
+    /// so that the user can flip back to it.
+    pub fn tune_station(&mut self, freq: Frequency, command: &Sender<OutputCommand>) {
+        if freq != self.frequency {
+            self.prev_frequency = Some(self.frequency);
+        }
+        self.frequency = freq;
+        send_command(command, OutputCommand::SetFrequency(freq));
+    }
+
+    /// Flips between the tuned station and the one tuned before it.
+    fn swap_station(&mut self, command: &Sender<OutputCommand>) {
+        match self.prev_frequency {
+            Some(freq) => {
+                self.tune_station(freq, command);
+                self.notify(format!("Back to {}", self.frequency_text(freq)));
+            }
+            None => self.notify("No previous station"),
+        }
+    }
+
+    /// Starts a seek from the selected seek button and remembers its direction,
+    /// or steps by one channel, when the seek buttons are set to step.
+    pub fn start_seek(&mut self, direction: Direction, command: &Sender<OutputCommand>) {
+        // a seek continuing from the band end, or held on, leaves the same station
+        if self.seek_from.is_none() {
+            self.prev_frequency = Some(self.frequency);
+        }
+
+        if self.settings.seek_buttons == SeekButtons::Step {
+            let frequency = match direction {
+                Direction::Up => self.frequency.stepped_up(channel_spacing_khz()),
+                Direction::Down => self.frequency.stepped_down(channel_spacing_khz()),
+            };
+            if frequency != self.frequency {
+                self.frequency = frequency;
+                self.tune_indicator = Some(direction);
+                send_command(command, OutputCommand::SetFrequency(frequency));
+            }
+            return;
+        }
+
+        // both buttons search on both sides
+        if self.settings.seek_buttons == SeekButtons::Nearest {
+            self.notify("Finding nearest");
+            send_command(command, OutputCommand::FindNearest);
+            return;
+        }
+
+        self.last_seek = Some((self.cursor_at, direction));
+        self.seek_reversed = false;
+        self.seek(direction, command);
+    }
+
+    /// Sends a seek to the tuner, which wraps around at the band end, unless it should reverse.
+    fn seek(&mut self, direction: Direction, command: &Sender<OutputCommand>) {
+        // a seek continuing from the band end still returns to where the first one started
+        self.seek_from.get_or_insert(self.frequency);
+
+        let wrap = !self.settings.seek_reverse;
+        let seek = match direction {
+            Direction::Up => OutputCommand::SeekUp(wrap),
+            Direction::Down => OutputCommand::SeekDown(wrap),
+        };
+        send_command(command, seek);
+    }
+
+    /// Asks the tuner to abort the running seek and return to the frequency it started from.
+    fn cancel_seek(&mut self, command: &Sender<OutputCommand>) {
+        if let Some(freq) = self.seek_from {
+            send_command(command, OutputCommand::CancelSeek(freq));
         }
     }
 
@@ -52,86 +420,569 @@ impl AppState {
         command: &Sender<OutputCommand>,
         nvs: &mut EspNvs<NvsDefault>,
     ) {
-        // names for NVS variables
-        const PRESET_NAMES: [&str; 4] = ["preset1", "preset2", "preset3", "preset4"];
+        use InputEvent as I;
+
+        log_event(&event);
+
+        // events may keep coming, without scrolling
+        self.send_pending_tune(command);
+
+        // swap the encoder direction, if the user wishes so
+        let event = match event {
+            I::ScrollDown if self.settings.invert_encoder => I::ScrollUp,
+            I::ScrollUp if self.settings.invert_encoder => I::ScrollDown,
+            event => event,
+        };
+
+        match event {
+            // events from radio are processed on every screen
+            I::ChangeFrequency(freq) => self.frequency = freq,
+            I::ChangeStationInfo(info) => self.station_info = info,
+            I::ChangeTextHistory(history) => self.text_history = history,
+            I::ChangeProgramType(code) => self.program_type = code,
+            I::ChangeRdsReception(reception) => self.rds_reception = reception,
+            I::ChangeClockTime(minutes) => self.clock = Some((minutes, Instant::now())),
+            I::ChangeStationName(name, complete) => {
+                // the tuner clears the name, whenever the station changes
+                if name.trim().is_empty() {
+                    self.restart_info_scroll();
+                }
+                self.station_name = name;
+                self.station_name_complete = complete;
+                self.refresh_preset_name(nvs);
+            }
+            I::ChangeRSSI(rssi) => {
+                self.rssi = rssi;
+                // the RSSI, which follows a change of frequency, is measured on the new one,
+                // frequencies passed by a seek and weak ones are not kept
+                if self.seek_from.is_none() && rssi >= RECENT_MIN_RSSI {
+                    self.recent_stations.record(self.frequency, self.rssi);
+                }
+            }
+            I::ChangeStatus(status) => {
+                self.tuner_status = status;
+                if status.seek_complete && !status.seek_failed {
+                    self.seek_from = None;
+                }
+            }
+            I::SeekCancelled(freq) => {
+                self.seek_from = None;
+                self.frequency = freq;
+                self.notify("Seek cancelled");
+            }
+            I::ScanFinished(mut stations) => {
+                if std::mem::take(&mut self.boot_scan) {
+                    self.tune_strongest(&stations, command);
+                }
 
+                stations.truncate(MAX_STORED_FREQUENCIES);
+                match save_stations(nvs, &stations) {
+                    Ok(()) => self.stations = stations,
+                    Err(err) => self.save_failed(err),
+                }
+            }
+            I::SeekFailed => match self.last_seek {
+                // the seek reached the band end, continue back from it once
+                Some((element, direction)) if self.settings.seek_reverse && !self.seek_reversed => {
+                    self.last_seek = Some((element, direction.reversed()));
+                    self.seek_reversed = true;
+                    self.seek(direction.reversed(), command);
+                }
+                _ => {
+                    self.seek_from = None;
+                    self.notify("No station found");
+                }
+            },
+            I::SignalLost => {
+                log::info!("signal lost at {} kHz", self.frequency.as_khz());
+                self.signal_lost = true;
+                self.notify("Signal lost");
+            }
+            I::SamplingProgress(sampled, total) => self.sampling_progress(sampled, total),
+            I::SamplingFinished(freq, rssi) => self.sampling_finished(freq, rssi),
+            I::NearestFound(freq, direction) => {
+                self.frequency = freq;
+                self.tune_indicator = Some(direction);
+                self.notify(match direction {
+                    Direction::Up => "Nearest is above",
+                    Direction::Down => "Nearest is below",
+                });
+            }
+            I::NoStationNearby => self.notify("No station nearby"),
+            I::TunerNotFound => self.tuner_missing = true,
+            I::SignalAcquired => {
+                log::info!("signal acquired at {} kHz", self.frequency.as_khz());
+                self.signal_lost = false;
+            }
+            I::AmbientLight(level) => self.ambient_brightness = Some(level),
+            I::RssiMeasured(rssi) => self.calibration_measured(rssi, command),
+            // the event loop replays them with `replay_events`, which draws every frame
+            I::Replay(events) => {
+                for event in events {
+                    self.process_event(event, command, nvs);
+                }
+            }
+
+            // the mute button works at once, even over the screensaver or locked controls
+            I::MuteDown => self.mute_down(command),
+            I::MuteUp => self.mute_up(command),
+
+            // the input which wakes up the screensaver is not processed
+            _ if self.wake_up() => self.resync(command),
+
+            // any input closes the help, the help gesture itself toggles it
+            I::Help => self.toggle_help(),
+            _ if self.dismiss_help(&event) => (),
+
+            // any input stops sampling presets
+            _ if self.sampling_from.is_some() => self.cancel_sampling(command),
+
+            // any input during the last minute cancels the sleep timer, except extending it
+            _ if self.sleep_warning() && !self.is_sleep_gesture(&event) => {
+                self.cancel_sleep_timer()
+            }
+
+            // while locked, user inputs only count towards unlocking
+            _ if self.locked => self.process_locked_input(event),
+
+            // a short press on the home screen aborts the running seek
+            I::ShortPress if self.screen == Screen::Home && self.seek_from.is_some() => {
+                self.cancel_seek(command)
+            }
+
+            // the back button works the same on every screen
+            I::Back => self.go_back(command),
+            I::QuickSave => self.quick_save(nvs),
+
+            // the remote works on every screen, except while its keys are learned
+            I::RemoteCode(code) if self.screen == Screen::RemoteLearning => {
+                self.learn_remote_code(code)
+            }
+            I::RemoteRepeat if self.screen == Screen::RemoteLearning => (),
+            I::RemoteCode(code) => self.process_remote_code(code, command),
+            I::RemoteRepeat => self.process_remote_repeat(command),
+
+            // user inputs are processed by the current screen
+            _ => match self.screen {
+                Screen::Home => self.process_home_input(event, command, nvs),
+                Screen::Settings => self.process_settings_input(event, command, nvs),
+                Screen::Favorites => self.process_favorites_input(event, command, nvs),
+                Screen::Recent => self.process_recent_input(event, command),
+                Screen::About => self.process_about_input(event),
+                Screen::RdsStats => self.process_rds_stats_input(event),
+                Screen::TextHistory => self.process_text_history_input(event),
+                Screen::Calibration => self.process_calibration_input(event, command),
+                Screen::RemoteLearning => self.process_remote_learning_input(event),
+                Screen::Spectrum => self.process_spectrum_input(event, command),
+            },
+        }
+    }
+
+    /// Returns to the screen, which the current one was opened from,
+    /// on the home screen only deselects the selected element.
+    fn go_back(&mut self, command: &Sender<OutputCommand>) {
+        if self.screen == Screen::Calibration {
+            self.cancel_calibration(command);
+        }
+        // the codes learned so far are dropped, as when leaving with a long press
+        if self.screen == Screen::RemoteLearning {
+            self.remote_learning = None;
+        }
+        if self.screen != Screen::Home {
+            self.screen = self.screen.parent();
+            return;
+        }
+
+        self.flush_pending_tune(command);
+        self.element_is_active = false;
+        if let Some(element) = self.volume_jump_from.take() {
+            self.cursor_at = element;
+        }
+    }
+
+    /// Tunes the preset, with its volume, if presets keep their volumes.
+    pub fn recall_preset(&mut self, preset: usize, command: &Sender<OutputCommand>) {
+        if let Some(freq) = self.presets[preset] {
+            self.mark_preset_used(preset);
+            self.tune_station(freq, command);
+            if let Some(name) = self.preset_names[preset].clone() {
+                self.notify(name);
+            }
+        }
+        if let (true, Some(volume)) = (self.settings.preset_volume, self.preset_volumes[preset]) {
+            self.volume = volume.min(self.settings.max_volume);
+            self.volume_ramp = None;
+            send_command(command, OutputCommand::SetVolume(self.output_volume()));
+        }
+    }
+
+    /// Stores the tuned station, with its name and volume, into the preset,
+    /// returns whether all of it was stored, failures are shown to the user.
+    pub fn store_preset(&mut self, preset: usize, nvs: &mut EspNvs<NvsDefault>) -> bool {
+        if let Err(err) = nvs.set_u32(PRESET_KEYS[preset], self.frequency.as_khz()) {
+            self.save_failed(err);
+            return false;
+        }
+        self.presets[preset] = Some(self.frequency);
+        self.mark_preset_used(preset);
+        let mut stored = true;
+
+        // the name of the previous station is replaced, or dropped until one arrives
+        let name = complete_station_name(&self.station_name, self.station_name_complete);
+        let name_key = PRESET_NAME_KEYS[preset];
+        let saved = match &name {
+            Some(name) => nvs.set_str(name_key, name),
+            None => nvs.remove(name_key).map(|_| ()),
+        };
+        match saved {
+            Ok(()) => self.preset_names[preset] = name,
+            Err(err) => {
+                self.preset_names[preset] = None;
+                nvs.remove(name_key).unwrap_or(false);
+                self.save_failed(err);
+                stored = false;
+            }
+        }
+
+        // a volume stored with the previous station would not fit the new one
+        let volume_key = PRESET_VOLUME_KEYS[preset];
+        let volume = self.settings.preset_volume.then_some(self.volume);
+        let saved = match volume {
+            Some(volume) => nvs.set_u8(volume_key, volume),
+            None => nvs.remove(volume_key).map(|_| ()),
+        };
+        match saved {
+            Ok(()) => self.preset_volumes[preset] = volume,
+            Err(err) => {
+                // the station is stored, only without its volume
+                self.preset_volumes[preset] = None;
+                nvs.remove(volume_key).unwrap_or(false);
+                self.save_failed(err);
+                stored = false;
+            }
+        }
+        stored
+    }
+
+    /// Processes user input on the home screen.
+    fn process_home_input(
+        &mut self,
+        event: InputEvent,
+        command: &Sender<OutputCommand>,
+        nvs: &mut EspNvs<NvsDefault>,
+    ) {
         use InputEvent as I;
+
+        // the first input only reveals the hidden controls
+        if self.settings.home_layout == HomeLayout::Minimal && self.controls_hidden {
+            self.controls_hidden = false;
+            return;
+        }
+
+        // other inputs expect the tuner to be at the previewed frequency,
+        // e.g. a seek would otherwise be interrupted by the delayed tune
+        let tuning = self.cursor_at == UIElement::FreqControl
+            && self.element_is_active
+            && matches!(event, I::ScrollDown | I::ScrollUp);
+        if !tuning {
+            self.flush_pending_tune(command);
+        }
+
         match (self.cursor_at, self.element_is_active, event) {
             // scrolling through UI elements
             (_, false, I::ScrollDown) => self.cursor_at = self.cursor_at.prev(),
             (_, false, I::ScrollUp) => self.cursor_at = self.cursor_at.next(),
 
-            // events from radio
-            (_, _, I::ChangeFrequency(freq)) => self.freq_khz = freq,
-            (_, _, I::ChangeStationInfo(info)) => self.station_info = info,
-            (_, _, I::ChangeRSSI(rssi)) => self.rssi = rssi,
-
             // seek down
             (UIElement::SeekDown, false, I::ShortPress) => {
-                command.send(OutputCommand::SeekDown).unwrap()
+                self.start_seek(Direction::Down, command)
             }
 
             // de/selecting frequency or volume control
+            (UIElement::VolumeControl, true, I::ShortPress | I::DoublePress) => {
+                self.element_is_active = false;
+
+                // return to where the user jumped to volume control from
+                if let Some(element) = self.volume_jump_from.take() {
+                    self.cursor_at = element;
+                }
+            }
             (UIElement::FreqControl | UIElement::VolumeControl, _, I::ShortPress) => {
-                self.element_is_active = !self.element_is_active
+                self.element_is_active = !self.element_is_active;
+
+                // manual tuning leaves the station tuned before it
+                if self.cursor_at == UIElement::FreqControl && self.element_is_active {
+                    self.prev_frequency = Some(self.frequency);
+                }
             }
 
             // frequency control
             (UIElement::FreqControl, true, I::ScrollDown) => {
-                if self.freq_khz > 76_000 {
-                    self.freq_khz -= 100;
-                    command
-                        .send(OutputCommand::SetFrequency(self.freq_khz))
-                        .unwrap();
+                let frequency = self.frequency.stepped_down(channel_spacing_khz());
+                if frequency != self.frequency {
+                    self.frequency = frequency;
+                    self.tune_indicator = Some(Direction::Down);
+                    self.tune(command);
                 }
             }
             (UIElement::FreqControl, true, I::ScrollUp) => {
-                if self.freq_khz < 108_000 {
-                    self.freq_khz = self.freq_khz + 100;
-                    command
-                        .send(OutputCommand::SetFrequency(self.freq_khz))
-                        .unwrap();
+                let frequency = self.frequency.stepped_up(channel_spacing_khz());
+                if frequency != self.frequency {
+                    self.frequency = frequency;
+                    self.tune_indicator = Some(Direction::Up);
+                    self.tune(command);
                 }
             }
 
+            // keep seeking while the button is held, in the direction of the last seek
+            // started from this button, which may have reversed at the band end
+            (UIElement::SeekDown | UIElement::SeekUp, false, I::Hold) => {
+                let direction = match self.last_seek {
+                    Some((element, direction)) if element == self.cursor_at => direction,
+                    _ if self.cursor_at == UIElement::SeekUp => Direction::Up,
+                    _ => Direction::Down,
+                };
+                self.start_seek(direction, command);
+            }
+
+            // scan the whole band
+            (UIElement::FreqControl, false, I::LongPress | I::VeryLongPress) => send_command(
+                command,
+                OutputCommand::ScanBand(self.settings.scan_criteria),
+            ),
+
             // seek up
-            (UIElement::SeekUp, false, I::ShortPress) => {
-                command.send(OutputCommand::SeekUp).unwrap()
+            (UIElement::SeekUp, false, I::ShortPress) => self.start_seek(Direction::Up, command),
+
+            // select preset, when recalls are confirmed, the first press only picks it
+            (UIElement::Preset(preset), false, I::ShortPress)
+                if self.settings.confirm_recall && self.presets[preset as usize].is_some() =>
+            {
+                self.element_is_active = true
+            }
+            (UIElement::Preset(preset), _, I::ShortPress) => {
+                self.element_is_active = false;
+                self.recall_preset(preset as usize, command);
+            }
+            // turning away from the picked preset keeps the tuned station
+            (UIElement::Preset(_), true, I::ScrollDown | I::ScrollUp) => {
+                self.element_is_active = false
+            }
+            // set preset, only with a very long press to avoid overwriting it by accident
+            (UIElement::Preset(_), false, I::LongPress) => self.notify("Hold longer to store"),
+            (UIElement::Preset(preset), false, I::VeryLongPress) => {
+                self.store_preset(preset as usize, nvs);
             }
 
-            // select preset
-            (UIElement::Preset(preset), false, I::ShortPress) => {
-                if let Ok(Some(freq)) = nvs.get_u32(PRESET_NAMES[preset as usize]) {
-                    self.freq_khz = freq;
-                    command
-                        .send(OutputCommand::SetFrequency(self.freq_khz))
-                        .unwrap();
-                }
+            // open settings
+            (UIElement::VolumeControl, false, I::LongPress | I::VeryLongPress) => {
+                self.screen = Screen::Settings
             }
-            // set preset
-            (UIElement::Preset(preset), false, I::LongPress) => nvs
-                .set_u32(PRESET_NAMES[preset as usize], self.freq_khz)
-                .unwrap(),
 
             // volume control
             (UIElement::VolumeControl, true, I::ScrollDown) => {
-                if self.volume > 0 {
-                    self.volume -= 1;
-                    command.send(OutputCommand::SetVolume(self.volume)).unwrap();
-                }
+                self.volume_direction = Some(Direction::Down);
+                self.change_volume(Direction::Down, command);
             }
             (UIElement::VolumeControl, true, I::ScrollUp) => {
-                if self.volume < 15 {
-                    self.volume += 1;
-                    command.send(OutputCommand::SetVolume(self.volume)).unwrap();
+                self.volume_direction = Some(Direction::Up);
+                self.change_volume(Direction::Up, command);
+            }
+            // keep changing the volume in the last scrolled direction while the button is held
+            (UIElement::VolumeControl, true, I::Hold) => {
+                if let Some(direction) = self.volume_direction {
+                    self.change_volume(direction, command);
                 }
             }
 
+            // mark or unmark the current station as favorite
+            (UIElement::FreqControl, _, I::DoublePress) => self.toggle_favorite(nvs),
+
+            // tune whichever preset is the strongest right now
+            (UIElement::Preset(_), false, I::DoublePress) => self.tune_strongest_preset(command),
+
+            // flip back to the previously tuned station, like the last channel button of a TV
+            (UIElement::SeekDown | UIElement::SeekUp, false, I::DoublePress) => {
+                self.swap_station(command)
+            }
+
+            // jump straight to the selected volume control
+            (_, _, I::DoublePress) => {
+                self.volume_jump_from = Some(self.cursor_at);
+                self.cursor_at = UIElement::VolumeControl;
+                self.element_is_active = true;
+            }
+
+            // the seek buttons keep seeking on hold instead
+            (UIElement::SeekDown | UIElement::SeekUp, _, I::LongPress | I::VeryLongPress) => (),
+
+            // an active control is left with a short press first
+            (
+                UIElement::FreqControl | UIElement::VolumeControl | UIElement::Preset(_),
+                true,
+                I::LongPress | I::VeryLongPress,
+            ) => (),
+
             // ignore all other user inputs
-            (_, _, I::LongPress) => (),
+            (_, _, I::Hold) => (),
 
-            // any other combinations should be unreachable
-            _ => unreachable!(),
+            // nothing else is bound on the home screen, e.g. to an active seek button
+            (element, active, event) => {
+                log::debug!("{event:?} ignored on {element:?}, active {active}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use esp_idf_svc::nvs::EspDefaultNvsPartition;
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::mpsc,
+    };
+
+    use super::*;
+
+    /// Every UI element of the home screen, fails to compile when an element is added,
+    /// so that the list is kept complete
+    fn all_elements() -> Vec<UIElement> {
+        let elements = [
+            UIElement::SeekDown,
+            UIElement::FreqControl,
+            UIElement::SeekUp,
+            UIElement::VolumeControl,
+        ];
+        for element in elements {
+            match element {
+                UIElement::SeekDown
+                | UIElement::FreqControl
+                | UIElement::SeekUp
+                | UIElement::Preset(_)
+                | UIElement::VolumeControl => {}
+            }
+        }
+        elements
+            .into_iter()
+            .chain((0..NUM_PRESETS).map(UIElement::Preset))
+            .collect()
+    }
+
+    /// Every input of the user, whichever screen it reaches
+    pub fn user_inputs() -> Vec<InputEvent> {
+        let inputs = vec![
+            InputEvent::ShortPress,
+            InputEvent::LongPress,
+            InputEvent::VeryLongPress,
+            InputEvent::DoublePress,
+            InputEvent::Hold,
+            InputEvent::ScrollDown,
+            InputEvent::ScrollUp,
+            InputEvent::Back,
+            InputEvent::Help,
+            InputEvent::QuickSave,
+            InputEvent::RemoteCode(0xF708FB04),
+            InputEvent::RemoteRepeat,
+        ];
+        assert!(inputs.iter().all(InputEvent::is_user_input));
+        inputs
+            .into_iter()
+            .chain([InputEvent::MuteDown, InputEvent::MuteUp])
+            .collect()
+    }
+
+    #[test]
+    fn next_and_prev_are_inverse() {
+        for element in UI_ELEMENT_RING {
+            assert!(element.next().prev() == element);
+            assert!(element.prev().next() == element);
+        }
+    }
+
+    #[test]
+    fn full_cycle_visits_every_element_once() {
+        let mut visited = Vec::new();
+        let mut element = UI_ELEMENT_RING[0];
+        for _ in 0..UI_ELEMENT_RING.len() {
+            assert!(!visited.contains(&element), "element visited twice");
+            visited.push(element);
+            element = element.next();
+        }
+        assert!(element == UI_ELEMENT_RING[0], "cycle does not close");
+
+        let mut element = UI_ELEMENT_RING[0];
+        for _ in 0..UI_ELEMENT_RING.len() {
+            element = element.prev();
+            assert!(visited.contains(&element));
+        }
+        assert!(element == UI_ELEMENT_RING[0]);
+    }
+
+    #[test]
+    fn every_element_is_reachable() {
+        let elements = all_elements();
+        assert_eq!(elements.len(), UI_ELEMENT_RING.len());
+        for element in elements {
+            assert!(
+                UI_ELEMENT_RING.contains(&element),
+                "element missing from the ring"
+            );
+        }
+    }
+
+    #[test]
+    fn user_inputs_are_handled_on_every_element() {
+        let partition = EspDefaultNvsPartition::take().unwrap();
+        let frequency = Frequency::from_khz(100_500);
+
+        for element in UI_ELEMENT_RING {
+            for active in [false, true] {
+                for event in user_inputs() {
+                    let mut state = AppState::new();
+                    let mut nvs = EspNvs::new(partition.clone(), "test", true).unwrap();
+                    let (command, commands) = mpsc::channel();
+                    state.cursor_at = element;
+                    state.element_is_active = active;
+                    state.frequency = frequency;
+
+                    let handled = panic::catch_unwind(AssertUnwindSafe(|| {
+                        state.process_home_input(event.clone(), &command, &mut nvs)
+                    }));
+                    assert!(
+                        handled.is_ok(),
+                        "{event:?} not handled on {element:?}, active {active}"
+                    );
+
+                    let scanned = commands
+                        .try_iter()
+                        .any(|command| matches!(command, OutputCommand::ScanBand(_)));
+                    match (element, active, event) {
+                        (UIElement::Preset(preset), false, InputEvent::VeryLongPress) => {
+                            let preset = preset as usize;
+                            assert_eq!(state.presets[preset], Some(frequency));
+                            assert_eq!(
+                                nvs.get_u32(PRESET_KEYS[preset]).unwrap(),
+                                Some(frequency.as_khz())
+                            );
+                        }
+                        (
+                            UIElement::FreqControl,
+                            false,
+                            InputEvent::LongPress | InputEvent::VeryLongPress,
+                        ) => assert!(scanned),
+                        (
+                            UIElement::VolumeControl,
+                            false,
+                            InputEvent::LongPress | InputEvent::VeryLongPress,
+                        ) => assert_eq!(state.screen, Screen::Settings),
+                        (_, _, InputEvent::LongPress | InputEvent::VeryLongPress) => {
+                            assert!(!scanned);
+                            assert_eq!(state.screen, Screen::Home);
+                        }
+                        _ => (),
+                    }
+                }
+            }
         }
     }
 }
@@ -0,0 +1,45 @@
+use crate::StereoMode;
+
+/// RSSI, below which the automatic mode switches to mono
+pub const MONO_BELOW_RSSI: u8 = 20;
+/// RSSI, from which the automatic mode switches back to stereo
+pub const STEREO_FROM_RSSI: u8 = 26;
+
+/// Decides, whether the tuner outputs mono or stereo, from the selected mode
+/// and the RSSI of the tuned station.
+///
+/// In the automatic mode, weak stations are forced to mono, whose noise is much lower.
+/// The gap between the thresholds keeps a station near them from toggling.
+pub struct StereoSelector {
+    mode: StereoMode,
+    mono_below_rssi: u8,
+    stereo_from_rssi: u8,
+    /// whether mono output is currently selected
+    mono: bool,
+}
+
+impl StereoSelector {
+    pub fn new(mono_below_rssi: u8, stereo_from_rssi: u8) -> Self {
+        StereoSelector {
+            mode: StereoMode::Auto,
+            mono_below_rssi,
+            stereo_from_rssi,
+            mono: false,
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: StereoMode) {
+        self.mode = mode;
+    }
+
+    /// Notes a new RSSI measurement, returns whether the output should be mono.
+    pub fn update(&mut self, rssi: u8) -> bool {
+        self.mono = match self.mode {
+            StereoMode::ForceMono => true,
+            StereoMode::ForceStereo => false,
+            StereoMode::Auto if self.mono => rssi < self.stereo_from_rssi,
+            StereoMode::Auto => rssi < self.mono_below_rssi,
+        };
+        self.mono
+    }
+}
@@ -0,0 +1,306 @@
+use esp_idf_svc::{
+    nvs::{EspNvs, NvsDefault},
+    sys::EspError,
+};
+use std::time::{Duration, Instant};
+
+use crate::{
+    double_buffer::read_double_buffered, frequency::Frequency, remote::REMOTE_KEYS, AppState,
+    NUM_PRESETS,
+};
+
+/// Maximum number of frequencies in a list kept in nonvolatile storage
+pub const MAX_STORED_FREQUENCIES: usize = 32;
+
+/// names of the NVS variables with preset stations
+pub const PRESET_KEYS: [&str; NUM_PRESETS as usize] = ["preset1", "preset2", "preset3", "preset4"];
+
+/// names of the NVS variables with volumes stored along with preset stations
+pub const PRESET_VOLUME_KEYS: [&str; NUM_PRESETS as usize] =
+    ["preset1_vol", "preset2_vol", "preset3_vol", "preset4_vol"];
+
+/// names of the NVS variables with RDS names of preset stations
+pub const PRESET_NAME_KEYS: [&str; NUM_PRESETS as usize] = [
+    "preset1_name",
+    "preset2_name",
+    "preset3_name",
+    "preset4_name",
+];
+
+/// Length of the buffer for a stored preset name, eight characters,
+/// each up to three bytes in UTF-8, and the terminating NUL
+const PRESET_NAME_BUFFER_LEN: usize = 8 * 3 + 1;
+
+/// Shortest time between two updates of a stored preset name, stations which scroll
+/// text through their name would otherwise keep rewriting the flash
+const PRESET_NAME_REFRESH: Duration = Duration::from_secs(10 * 60);
+
+/// name of the NVS blob with scanned stations and their RSSI
+const STATIONS_KEY: &str = "scan";
+
+/// name of the NVS blob with scanned stations, stored without RSSI by older versions
+const LEGACY_STATIONS_KEY: &str = "stations";
+
+/// name of the NVS blob with favorite stations
+pub const FAVORITES_KEY: &str = "favorites";
+
+/// name of the NVS blob with all settings
+pub const SETTINGS_KEY: &str = "settings";
+
+/// name of the NVS blob with the last tuned frequency, in kHz
+pub const LAST_FREQUENCY_KEY: &str = "last_freq";
+
+/// name of the NVS blob with the last volume
+pub const LAST_VOLUME_KEY: &str = "last_volume";
+
+/// names of the two NVS blobs, which keep copies of a value staged under the first name,
+/// for values which must survive a power loss during their write
+const DOUBLE_BUFFERED_KEYS: [(&str, [&str; 2]); 2] = [
+    (LAST_FREQUENCY_KEY, ["last_freq_a", "last_freq_b"]),
+    (LAST_VOLUME_KEY, ["last_volume_a", "last_volume_b"]),
+];
+
+/// Returns the names of the two copies of the value staged under the key,
+/// if it is kept double buffered.
+pub fn double_buffered_keys(key: &str) -> Option<[&'static str; 2]> {
+    DOUBLE_BUFFERED_KEYS
+        .iter()
+        .find(|(name, _)| *name == key)
+        .map(|(_, keys)| *keys)
+}
+
+/// name of the NVS blob with the calibrated range of the signal meter
+pub const METER_RANGE_KEY: &str = "meter_range";
+
+/// name of the NVS blob with the last use of each preset
+pub const PRESET_USES_KEY: &str = "preset_uses";
+
+/// name of the NVS blob with the codes of the keys of the IR remote
+pub const REMOTE_CODES_KEY: &str = "remote_codes";
+
+/// Loads all preset stations, presets which were never stored are empty.
+pub fn load_presets(nvs: &EspNvs<NvsDefault>) -> [Option<Frequency>; NUM_PRESETS as usize] {
+    PRESET_KEYS.map(|key| nvs.get_u32(key).ok().flatten().map(Frequency::from_khz))
+}
+
+/// Loads volumes of all preset stations, presets stored without a volume have none.
+pub fn load_preset_volumes(nvs: &EspNvs<NvsDefault>) -> [Option<u8>; NUM_PRESETS as usize] {
+    PRESET_VOLUME_KEYS.map(|key| nvs.get_u8(key).ok().flatten())
+}
+
+/// Loads RDS names of all preset stations, presets stored without a name have none.
+pub fn load_preset_names(nvs: &EspNvs<NvsDefault>) -> [Option<String>; NUM_PRESETS as usize] {
+    let mut buffer = [0; PRESET_NAME_BUFFER_LEN];
+    PRESET_NAME_KEYS.map(|key| {
+        nvs.get_str(key, &mut buffer)
+            .ok()
+            .flatten()
+            .map(str::to_string)
+    })
+}
+
+/// Returns the received station name, trimmed, if all of its segments arrived.
+pub fn complete_station_name(name: &str, complete: bool) -> Option<String> {
+    let name = name.trim();
+    (complete && !name.is_empty()).then(|| name.to_string())
+}
+
+/// Stores a list of frequencies as a blob of little endian numbers.
+///
+/// Only the first `MAX_STORED_FREQUENCIES` frequencies are stored.
+pub fn save_frequencies(
+    nvs: &mut EspNvs<NvsDefault>,
+    key: &str,
+    frequencies: &[Frequency],
+) -> Result<(), EspError> {
+    let blob = frequencies
+        .iter()
+        .take(MAX_STORED_FREQUENCIES)
+        .flat_map(|freq| freq.as_khz().to_le_bytes())
+        .collect::<Vec<_>>();
+
+    nvs.set_raw(key, &blob).map(|_| ())
+}
+
+/// Loads a list of frequencies. If there is no stored list,
+/// or the stored blob is corrupt, an empty list is returned.
+pub fn load_frequencies(nvs: &EspNvs<NvsDefault>, key: &str) -> Vec<Frequency> {
+    let mut buffer = [0; MAX_STORED_FREQUENCIES * 4];
+
+    let Ok(Some(blob)) = nvs.get_raw(key, &mut buffer) else {
+        return Vec::new();
+    };
+
+    if blob.len() % 4 != 0 {
+        return Vec::new();
+    }
+
+    let frequencies = blob
+        .chunks_exact(4)
+        .map(|bytes| Frequency::from_khz(u32::from_le_bytes(bytes.try_into().unwrap())))
+        .collect::<Vec<_>>();
+
+    // lists are always stored in increasing order, all within the bands of the tuner
+    let valid = frequencies.iter().all(|freq| freq.is_supported())
+        && frequencies.windows(2).all(|pair| pair[0] < pair[1]);
+
+    if valid {
+        frequencies
+    } else {
+        Vec::new()
+    }
+}
+
+/// Stores the scanned stations as a blob of little endian frequencies, each followed
+/// by its RSSI. Only the first `MAX_STORED_FREQUENCIES` stations are stored.
+pub fn save_stations(
+    nvs: &mut EspNvs<NvsDefault>,
+    stations: &[(Frequency, u8)],
+) -> Result<(), EspError> {
+    let blob = stations
+        .iter()
+        .take(MAX_STORED_FREQUENCIES)
+        .flat_map(|&(freq, rssi)| {
+            let [a, b, c, d] = freq.as_khz().to_le_bytes();
+            [a, b, c, d, rssi]
+        })
+        .collect::<Vec<_>>();
+
+    nvs.set_raw(STATIONS_KEY, &blob).map(|_| ())
+}
+
+/// Loads the scanned stations with their RSSI, stations scanned by older versions
+/// have zero RSSI. If there are none, or the stored blob is corrupt, an empty list is returned.
+pub fn load_stations(nvs: &EspNvs<NvsDefault>) -> Vec<(Frequency, u8)> {
+    let mut buffer = [0; MAX_STORED_FREQUENCIES * 5];
+
+    let blob = match nvs.get_raw(STATIONS_KEY, &mut buffer) {
+        Ok(Some(blob)) if blob.len() % 5 == 0 => blob,
+        Ok(None) => {
+            return load_frequencies(nvs, LEGACY_STATIONS_KEY)
+                .into_iter()
+                .map(|freq| (freq, 0))
+                .collect()
+        }
+        _ => return Vec::new(),
+    };
+
+    let stations = blob
+        .chunks_exact(5)
+        .map(|bytes| {
+            let freq = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+            (Frequency::from_khz(freq), bytes[4])
+        })
+        .collect::<Vec<_>>();
+
+    // stations are always stored in increasing order, all within the bands of the tuner
+    let valid = stations.iter().all(|(freq, _)| freq.is_supported())
+        && stations.windows(2).all(|pair| pair[0].0 < pair[1].0);
+
+    if valid {
+        stations
+    } else {
+        Vec::new()
+    }
+}
+
+/// Loads the value staged under the key, from its two copies when it is double
+/// buffered, falling back to the single blob written by older versions.
+fn load_critical(nvs: &EspNvs<NvsDefault>, key: &str) -> Option<Vec<u8>> {
+    let mut buffer = [0; 4];
+    double_buffered_keys(key)
+        .and_then(|keys| read_double_buffered(nvs, keys))
+        .or_else(|| {
+            nvs.get_raw(key, &mut buffer)
+                .ok()
+                .flatten()
+                .map(<[u8]>::to_vec)
+        })
+}
+
+/// Loads the frequency and volume tuned before the last power off, if they were stored.
+pub fn load_last_station(nvs: &EspNvs<NvsDefault>) -> (Option<Frequency>, Option<u8>) {
+    let frequency = match load_critical(nvs, LAST_FREQUENCY_KEY).as_deref() {
+        Some(&[a, b, c, d]) => Some(Frequency::from_khz(u32::from_le_bytes([a, b, c, d]))),
+        _ => None,
+    };
+    let volume = match load_critical(nvs, LAST_VOLUME_KEY).as_deref() {
+        Some(&[volume]) => Some(volume),
+        _ => None,
+    };
+    (frequency, volume)
+}
+
+/// Loads when each preset was last used, presets never used since this was stored are the oldest.
+pub fn load_preset_uses(nvs: &EspNvs<NvsDefault>) -> [u32; NUM_PRESETS as usize] {
+    let mut buffer = [0; NUM_PRESETS as usize * 4];
+    let mut uses = [0; NUM_PRESETS as usize];
+    if let Ok(Some(blob)) = nvs.get_raw(PRESET_USES_KEY, &mut buffer) {
+        for (uses, bytes) in uses.iter_mut().zip(blob.chunks_exact(4)) {
+            *uses = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+    }
+    uses
+}
+
+/// Loads the codes of the keys of the IR remote, keys which were never learned are zero.
+pub fn load_remote_codes(nvs: &EspNvs<NvsDefault>) -> [u32; REMOTE_KEYS] {
+    let mut buffer = [0; REMOTE_KEYS * 4];
+    let mut codes = [0; REMOTE_KEYS];
+    if let Ok(Some(blob)) = nvs.get_raw(REMOTE_CODES_KEY, &mut buffer) {
+        for (code, bytes) in codes.iter_mut().zip(blob.chunks_exact(4)) {
+            *code = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+    }
+    codes
+}
+
+/// Loads the calibrated range of the signal meter, as the noise floor and a strong RSSI.
+pub fn load_meter_range(nvs: &EspNvs<NvsDefault>) -> Option<(u8, u8)> {
+    let mut buffer = [0; 2];
+    match nvs.get_raw(METER_RANGE_KEY, &mut buffer) {
+        Ok(Some(&[floor, ceiling])) if floor < ceiling => Some((floor, ceiling)),
+        _ => None,
+    }
+}
+
+impl AppState {
+    /// Reports a failed write to nonvolatile storage, the radio keeps running
+    /// with the state, which was stored before.
+    pub fn save_failed(&mut self, err: EspError) {
+        log::error!("Saving to NVS failed: {err:?}");
+        self.notify("Save failed");
+    }
+
+    /// Updates the stored name of the preset with the tuned station, once the station
+    /// broadcasts a different name, at most once per `PRESET_NAME_REFRESH`.
+    pub fn refresh_preset_name(&mut self, nvs: &mut EspNvs<NvsDefault>) {
+        let Some(name) = complete_station_name(&self.station_name, self.station_name_complete)
+        else {
+            return;
+        };
+        let Some(preset) = self
+            .presets
+            .iter()
+            .position(|&freq| freq == Some(self.frequency))
+        else {
+            return;
+        };
+        if self.preset_names[preset].as_deref() == Some(name.as_str())
+            || self
+                .preset_name_saved
+                .is_some_and(|saved| saved.elapsed() < PRESET_NAME_REFRESH)
+        {
+            return;
+        }
+
+        self.preset_name_saved = Some(Instant::now());
+        match nvs.set_str(PRESET_NAME_KEYS[preset], &name) {
+            Ok(()) => {
+                log::info!("preset {}: name updated to {name}", preset + 1);
+                self.preset_names[preset] = Some(name);
+            }
+            Err(err) => self.save_failed(err),
+        }
+    }
+}
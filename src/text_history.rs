@@ -0,0 +1,40 @@
+use crate::{AppState, InputEvent, Screen};
+
+impl AppState {
+    /// Returns the Radio Text messages, which can be scrolled through, newest first,
+    /// the one being received comes first, unless it repeats the last finished one.
+    pub fn radio_texts(&self) -> Vec<&str> {
+        let current = self.station_info.trim();
+        let current = (!current.is_empty()
+            && self.text_history.first().map(String::as_str) != Some(current))
+        .then_some(current);
+
+        current
+            .into_iter()
+            .chain(self.text_history.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Processes user input on the screen with recent Radio Text messages.
+    pub fn process_text_history_input(&mut self, event: InputEvent) {
+        // the history may have shrunk since the cursor moved, when the station changed
+        let count = self.radio_texts().len();
+        self.text_history_cursor = self.text_history_cursor.min(count.saturating_sub(1));
+
+        use InputEvent as I;
+        match event {
+            // scrolling back to older messages, and forward to newer ones
+            I::ScrollUp if self.text_history_cursor + 1 < count => self.text_history_cursor += 1,
+            I::ScrollDown if self.text_history_cursor > 0 => self.text_history_cursor -= 1,
+
+            // return back to the home screen
+            I::LongPress | I::VeryLongPress => self.screen = Screen::Home,
+
+            // ignore all other user inputs
+            I::ScrollDown | I::ScrollUp | I::ShortPress | I::DoublePress | I::Hold => (),
+
+            // events from radio are processed before reaching the screen
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use esp_idf_svc::sys::esp_timer_get_time;
+use std::{collections::VecDeque, sync::Mutex};
+
+use crate::InputEvent;
+
+/// Number of the last events kept in the trace
+const EVENT_TRACE_LEN: usize = 64;
+
+/// Last events processed by the event loop, with the milliseconds since boot they came at
+static EVENT_TRACE: Mutex<VecDeque<(u64, InputEvent)>> = Mutex::new(VecDeque::new());
+
+/// Records the event in the trace, called by the event loop before processing it.
+pub fn record_event(event: &InputEvent) {
+    // only reads the system timer
+    let millis = unsafe { esp_timer_get_time() } as u64 / 1000;
+
+    let mut trace = EVENT_TRACE.lock().unwrap();
+    if trace.len() == EVENT_TRACE_LEN {
+        trace.pop_front();
+    }
+    trace.push_back((millis, event.clone()));
+}
+
+/// Returns the trace, one event per line, oldest first, as the seconds since boot
+/// and the event in the format of its `Debug` output.
+///
+/// Without the times, the lines can be replayed with the `replay` console command.
+pub fn dump_events() -> String {
+    EVENT_TRACE
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(millis, event)| format!("{:>6}.{:03} {event:?}", millis / 1000, millis % 1000))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
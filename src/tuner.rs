@@ -1,117 +1,1271 @@
-use esp_idf_svc::hal::{
-    gpio::{InputPin, OutputPin},
-    i2c::{I2cConfig, I2cDriver, I2C0},
-    units::KiloHertz,
+use esp_idf_svc::{
+    hal::{
+        gpio::{InputPin, Level, Output, OutputPin, PinDriver},
+        i2c::{I2cConfig, I2cDriver, I2C0},
+        units::KiloHertz,
+    },
+    sys::EspError,
 };
-use rda5807m::{Address, Rda5708m};
+use rda5807m::Address;
 use std::{
-    sync::mpsc::{Receiver, Sender},
+    fmt::Debug,
+    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use crate::{InputEvent, OutputCommand};
+use crate::{
+    boot::boot_step,
+    chip::{AdaptiveTimeout, Rda5807, TunerChip, MAX_I2C_TIMEOUT},
+    confirm::confirm_seek,
+    error::Error,
+    frequency::Frequency,
+    panic::{panicked, register_amp_pin},
+    rds::{group_name, RdsDecoder, RdsReception, RdsUpdate},
+    rds_stats::{record_rds_decoded, record_rds_group, record_rds_station, reset_rds_stats},
+    sampling::{sampling_cancelled, start_sampling},
+    signal::{SignalMonitor, SIGNAL_ACQUIRED_RSSI, SIGNAL_DWELL, SIGNAL_LOST_RSSI},
+    stereo::{StereoSelector, MONO_BELOW_RSSI, STEREO_FROM_RSSI},
+    watchdog::Heartbeat,
+    CommandResult, Direction, InputEvent, OutputCommand, ScanCriteria, TunerStatus,
+};
+
+/// Highest I2C bus speed supported by the RDA5807M (fast mode)
+const MAX_BAUDRATE: KiloHertz = KiloHertz(400);
+
+/// How many consecutive status reads must agree, before the reported stereo state
+/// changes, this keeps the indicator from blinking on marginal signals
+const STEREO_DEBOUNCE_READS: u32 = 5;
+
+/// Interval, in which the tuner loop polls the tuner
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long to wait for a stereo pilot on a found station during scan
+const SCAN_STEREO_TIMEOUT: Duration = Duration::from_millis(500);
+/// How long to wait for RDS data on a found station during scan
+const SCAN_RDS_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// How long to wait for the tuner to tune a sampled frequency
+const SAMPLE_TUNE_TIMEOUT: Duration = Duration::from_millis(300);
+/// How long the RSSI takes to settle after tuning a sampled frequency
+const SAMPLE_SETTLE: Duration = Duration::from_millis(150);
+
+/// Number of readings averaged by `MeasureRssi`, and the time between them
+const MEASURE_SAMPLES: u32 = 10;
+const MEASURE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Farthest distance from the tuned frequency, at which `FindNearest` accepts a station
+const NEAREST_MAX_DISTANCE_KHZ: u32 = 2_000;
+/// How long a seek probing for the nearest station may take, before it is stopped
+const NEAREST_SEEK_TIMEOUT: Duration = Duration::from_millis(3000);
+
+/// Repeatedly checks `condition`, until it holds or `timeout` runs out,
+/// a failed check ends the polling with its error.
+fn poll_until<E>(
+    timeout: Duration,
+    mut condition: impl FnMut() -> Result<bool, E>,
+) -> Result<bool, E> {
+    let start = Instant::now();
+
+    while start.elapsed() < timeout {
+        if condition()? {
+            return Ok(true);
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    Ok(false)
+}
+
+/// Seeks through the whole band from its lower end, and returns the frequencies
+/// of all found stations, which satisfy the given criteria, with their RSSI.
+///
+/// Each found station is reported to the event loop as a frequency change,
+/// so that the user can see the scan progress.
+fn scan_band<T: TunerChip>(
+    tuner: &mut T,
+    criteria: ScanCriteria,
+    event_sender: &Sender<InputEvent>,
+    heartbeat: &Heartbeat,
+) -> Result<Vec<(Frequency, u8)>, Error> {
+    let mut stations = Vec::new();
+
+    tuner
+        .set_frequency(Frequency::band_start().as_khz())
+        .map_err(Error::i2c)?;
+
+    loop {
+        // the scan blocks the tuner loop for a long time
+        heartbeat.feed();
+
+        // seek without wrapping, the tuner reports a failed seek at the end of the band
+        tuner.seek_up(false).map_err(Error::i2c)?;
+        poll_until(Duration::MAX, || {
+            tuner.get_status().map(|status| status.stc)
+        })
+        .map_err(Error::i2c)?;
+
+        if tuner.get_status().map_err(Error::i2c)?.sf {
+            break;
+        }
+
+        let freq = Frequency::from_khz(tuner.get_frequency().map_err(Error::i2c)?);
+        if stations.last().is_some_and(|&(last, _)| last >= freq) {
+            break;
+        }
+
+        let rssi = tuner.get_rssi().map_err(Error::i2c)?;
+        event_sender.send(InputEvent::ChangeFrequency(freq))?;
+        event_sender.send(InputEvent::ChangeRSSI(rssi))?;
+
+        let accepted = match criteria {
+            ScanCriteria::Rssi => true,
+            ScanCriteria::RssiStereo => poll_until(SCAN_STEREO_TIMEOUT, || {
+                tuner.get_status().map(|status| status.st)
+            })
+            .map_err(Error::i2c)?,
+            ScanCriteria::RssiRds => poll_until(SCAN_RDS_TIMEOUT, || {
+                tuner.get_status().map(|status| status.rdss)
+            })
+            .map_err(Error::i2c)?,
+        };
+
+        if accepted {
+            stations.push((freq, rssi));
+        }
+    }
+
+    Ok(stations)
+}
 
-/// Spawns a new thread, in which the RDA5708 tuner is initialized and
-/// tuned to a default frequency.
+/// Tunes each of the frequencies and measures its RSSI, then stays on the strongest
+/// and returns it with its RSSI, or `None` if the sampling was cancelled.
+///
+/// The progress is reported to the event loop after each frequency.
+fn sample_frequencies<T: TunerChip>(
+    tuner: &mut T,
+    frequencies: &[Frequency],
+    event_sender: &Sender<InputEvent>,
+    heartbeat: &Heartbeat,
+) -> Result<Option<(Frequency, u8)>, Error> {
+    start_sampling();
+
+    let mut strongest: Option<(Frequency, u8)> = None;
+    for (index, &freq) in frequencies.iter().enumerate() {
+        heartbeat.feed();
+        if sampling_cancelled() {
+            return Ok(None);
+        }
+
+        tuner.set_frequency(freq.as_khz()).map_err(Error::i2c)?;
+        poll_until(SAMPLE_TUNE_TIMEOUT, || {
+            tuner.get_status().map(|status| status.stc)
+        })
+        .map_err(Error::i2c)?;
+        thread::sleep(SAMPLE_SETTLE);
+
+        let rssi = tuner.get_rssi().map_err(Error::i2c)?;
+        if strongest.map_or(true, |(_, strongest)| rssi > strongest) {
+            strongest = Some((freq, rssi));
+        }
+
+        event_sender.send(InputEvent::SamplingProgress(index + 1, frequencies.len()))?;
+    }
+
+    let Some((freq, rssi)) = strongest else {
+        return Ok(None);
+    };
+    if sampling_cancelled() {
+        return Ok(None);
+    }
+    tuner.set_frequency(freq.as_khz()).map_err(Error::i2c)?;
+    Ok(Some((freq, rssi)))
+}
+
+/// Averages the RSSI of the tuned frequency over `MEASURE_SAMPLES` readings,
+/// so that a single fluctuation does not skew it.
+fn measure_rssi<T: TunerChip>(tuner: &mut T, heartbeat: &Heartbeat) -> Result<u8, T::Error> {
+    let mut sum = 0;
+    for _ in 0..MEASURE_SAMPLES {
+        heartbeat.feed();
+        thread::sleep(MEASURE_INTERVAL);
+        sum += tuner.get_rssi()? as u32;
+    }
+    Ok((sum / MEASURE_SAMPLES) as u8)
+}
+
+/// Seeks from the frequency in the direction without wrapping around, returns the found
+/// station, if it is at most `NEAREST_MAX_DISTANCE_KHZ` away.
+fn probe_seek<T: TunerChip>(
+    tuner: &mut T,
+    from: u32,
+    direction: Direction,
+) -> Result<Option<u32>, T::Error> {
+    tuner.set_frequency(from)?;
+    poll_until(SAMPLE_TUNE_TIMEOUT, || Ok(tuner.get_status()?.stc))?;
+
+    match direction {
+        Direction::Up => tuner.seek_up(false)?,
+        Direction::Down => tuner.seek_down(false)?,
+    }
+    if !poll_until(NEAREST_SEEK_TIMEOUT, || Ok(tuner.get_status()?.stc))? {
+        tuner.stop_seek(from)?;
+        return Ok(None);
+    }
+    if tuner.get_status()?.sf {
+        return Ok(None);
+    }
+
+    let found = tuner.get_frequency()?;
+    Ok((found != from && found.abs_diff(from) <= NEAREST_MAX_DISTANCE_KHZ).then_some(found))
+}
+
+/// Probes for a station above and below the tuned frequency and tunes the closer one,
+/// returns it with the direction it was found in. Without any, the frequency is tuned back.
+fn find_nearest<T: TunerChip>(
+    tuner: &mut T,
+    heartbeat: &Heartbeat,
+) -> Result<Option<(Frequency, Direction)>, T::Error> {
+    let from = tuner.get_frequency()?;
+    let up = probe_seek(tuner, from, Direction::Up)?;
+    heartbeat.feed();
+    let down = probe_seek(tuner, from, Direction::Down)?;
+    heartbeat.feed();
+
+    // the station above wins a tie
+    let nearest = match (up, down) {
+        (Some(up), Some(down)) if from - down < up - from => Some((down, Direction::Down)),
+        (Some(up), _) => Some((up, Direction::Up)),
+        (None, Some(down)) => Some((down, Direction::Down)),
+        (None, None) => None,
+    };
+
+    tuner.set_frequency(nearest.map_or(from, |(freq, _)| freq))?;
+    Ok(nearest.map(|(freq, direction)| (Frequency::from_khz(freq), direction)))
+}
+
+/// Checks that the tuner answers at its configured address, by reading its status,
+/// before the driver starts it, so that a wiring fault is reported as such,
+/// instead of as a failed register access.
+fn probe_tuner(tuner: &mut impl TunerChip, address: String) -> Result<(), Error> {
+    tuner
+        .get_status()
+        .map(|_| ())
+        .map_err(|_| Error::TunerNotFound(address))
+}
+
+/// Applies the volume trim, a zero volume stays muted and any other stays audible.
+fn trimmed_volume(volume: u8, trim: i8) -> u8 {
+    if volume == 0 {
+        return 0;
+    }
+    (volume as i8 + trim).clamp(1, 15) as u8
+}
+
+/// Executes a single command from the event loop on the tuner.
+fn execute_command<T: TunerChip>(
+    tuner: &mut T,
+    command: OutputCommand,
+    event_sender: &Sender<InputEvent>,
+    heartbeat: &Heartbeat,
+) -> Result<(), Error> {
+    let result = match command {
+        OutputCommand::SetFrequency(freq) => tuner.set_frequency(freq.as_khz()),
+        OutputCommand::SetVolume(volume) => tuner.set_volume(volume),
+        OutputCommand::SeekUp(wrap) => tuner.seek_up(wrap),
+        OutputCommand::SeekDown(wrap) => tuner.seek_down(wrap),
+        OutputCommand::CancelSeek(freq) => tuner.stop_seek(freq.as_khz()),
+        OutputCommand::SetRds(true) => tuner.enable_rds(),
+        OutputCommand::SetRds(false) => tuner.disable_rds(),
+        // only change what the tuner loop logs and selects
+        OutputCommand::SetRdsDump(_) | OutputCommand::SetStereoMode(_) => Ok(()),
+        OutputCommand::Resync => unreachable!("resync is handled by the caller"),
+        OutputCommand::SetSeekThreshold(threshold) => tuner.set_seek_threshold(threshold),
+        OutputCommand::SetBand(band) => tuner.set_band(band),
+        OutputCommand::SetSpacing(spacing) => tuner.set_spacing(spacing),
+        OutputCommand::SetDeemphasis(deemphasis) => tuner.set_deemphasis(deemphasis),
+        OutputCommand::SetVolumeTrim(_) => unreachable!("trim is applied to volume by the caller"),
+        OutputCommand::ScanBand(criteria) => {
+            let freq = tuner.get_frequency().map_err(Error::i2c)?;
+            let stations = scan_band(tuner, criteria, event_sender, heartbeat)?;
+
+            event_sender.send(InputEvent::ChangeFrequency(Frequency::from_khz(freq)))?;
+            event_sender.send(InputEvent::ScanFinished(stations))?;
+
+            // return to the station tuned before the scan
+            tuner.set_frequency(freq)
+        }
+        OutputCommand::TuneStrongest(frequencies) => {
+            if let Some((freq, rssi)) =
+                sample_frequencies(tuner, &frequencies, event_sender, heartbeat)?
+            {
+                event_sender.send(InputEvent::SamplingFinished(freq, rssi))?;
+            }
+            Ok(())
+        }
+        OutputCommand::FindNearest => {
+            let event = match find_nearest(tuner, heartbeat).map_err(Error::i2c)? {
+                Some((freq, direction)) => InputEvent::NearestFound(freq, direction),
+                None => InputEvent::NoStationNearby,
+            };
+            event_sender.send(event)?;
+            Ok(())
+        }
+        OutputCommand::MeasureRssi => {
+            let rssi = measure_rssi(tuner, heartbeat).map_err(Error::i2c)?;
+            event_sender.send(InputEvent::RssiMeasured(rssi))?;
+            Ok(())
+        }
+        OutputCommand::GetFrequency(reply) => {
+            // the caller may have already stopped waiting for the frequency
+            tuner
+                .get_frequency()
+                .map(|freq| reply.send(Frequency::from_khz(freq)).unwrap_or(()))
+        }
+        OutputCommand::WithReply(..) => unreachable!("replies are handled by the caller"),
+    };
+    result.map_err(Error::i2c)
+}
+
+/// Sends a command to the tuner, without waiting for it.
+///
+/// The tuner thread stops only on a failure, after which the watchdog restarts
+/// the radio, until then the command is dropped with a warning.
+pub fn send_command(command_sender: &Sender<OutputCommand>, command: OutputCommand) {
+    if command_sender.send(command).is_err() {
+        log::warn!("Command dropped, the tuner thread is not running");
+    }
+}
+
+/// Sends a command to the tuner and waits until it is executed.
+///
+/// Unlike commands sent directly, whose failures are only logged by the tuner thread,
+/// the failure is returned to the caller, `Error::Channel` if the tuner thread is not running.
+pub fn request(command_sender: &Sender<OutputCommand>, command: OutputCommand) -> CommandResult {
+    let (reply, result) = channel();
+
+    command_sender.send(OutputCommand::WithReply(Box::new(command), reply))?;
+
+    result.recv()?
+}
+
+/// Spawns a new thread, in which the RDA5708 tuner is initialized and muted,
+/// the seek threshold, the band and the frequency are then set from the settings.
 ///
 /// Then, commands from the event loop are periodically processed, new data from
 /// the tuner is fetched and sent back to the event loop.
+///
+/// `baudrate` is the I2C bus speed, up to 400 kHz. Faster buses make seeking and RDS
+/// more responsive, but long or poorly wired buses may only work reliably at 100 kHz.
+///
+/// `address` selects the I2C address, which the driver talks to the tuner at,
+/// boards with non-default wiring can select another than `Address::default()`.
+///
+/// `timeout` limits how long a single I2C transaction may take, without it the tuner
+/// would occasionally time out. Too short timeouts fail at slower bus speeds,
+/// so failed transactions raise the timeout, up to `MAX_I2C_TIMEOUT`, and are retried.
+///
+/// `amp_enable` is an optional enable pin of an external amplifier, driven to
+/// `amp_active_level` only while the tuner is audible, that is when the volume
+/// is not zero and no seek or scan is running, which suppresses the seek noise.
+/// In deep sleep the pin floats, so it should be pulled to its inactive level externally.
+///
+/// The `heartbeat` is fed on every iteration, so that a wedged tuner is noticed.
+///
+/// Returns `Error::InvalidConfig`, if the bus speed or the timeout is out of range,
+/// the thread is not spawned then.
+#[allow(clippy::too_many_arguments)] // peripherals are moved into the thread one by one
 pub fn spawn_tuner_thread(
     i2c: I2C0,
     sda: impl InputPin + OutputPin,
     scl: impl InputPin + OutputPin,
+    baudrate: KiloHertz,
+    timeout: Duration,
+    address: Address,
+    amp_enable: Option<impl OutputPin>,
+    amp_active_level: Level,
+    heartbeat: Heartbeat,
     event_sender: Sender<InputEvent>,
     command_receiver: Receiver<OutputCommand>,
-) {
+) -> Result<(), Error> {
+    if baudrate.0 == 0 || baudrate.0 > MAX_BAUDRATE.0 {
+        return Err(Error::InvalidConfig(format!(
+            "I2C bus speed must be between 1 and {} kHz, not {}",
+            MAX_BAUDRATE.0, baudrate.0
+        )));
+    }
+    if timeout.is_zero() || timeout > MAX_I2C_TIMEOUT {
+        return Err(Error::InvalidConfig(format!(
+            "I2C timeout must be between 1 and {} us, not {}",
+            MAX_I2C_TIMEOUT.as_micros(),
+            timeout.as_micros()
+        )));
+    }
+
     thread::spawn(move || {
-        let mut config = I2cConfig::new().baudrate(KiloHertz(100).into());
+        let setup = || {
+            // keep the amplifier off until the tuner is ready
+            let mut amp = amp_enable
+                .map(|pin| {
+                    boot_step(
+                        "amplifier pin set",
+                        PinDriver::output(pin).map_err(Error::Gpio),
+                    )
+                })
+                .transpose()?;
+            if let Some(amp) = amp.as_mut() {
+                amp.set_level(!amp_active_level).map_err(Error::Gpio)?;
+                register_amp_pin(amp.pin(), !amp_active_level);
+            }
+
+            let mut config = I2cConfig::new().baudrate(baudrate.into());
+            config.timeout = Some(timeout.into());
+            let i2c_driver = boot_step(
+                &format!("I2C created ({} kHz)", baudrate.0),
+                I2cDriver::new(i2c, sda, scl, &config).map_err(Error::i2c),
+            )?;
 
-        // without setting a timeout value, the tuner would occasionally timeout
-        config.timeout = Some(Duration::from_millis(10).into());
-        let i2c_driver = I2cDriver::new(i2c, sda, scl, &config).unwrap();
+            // the tuner is on I2C0
+            let address_text = format!("{address:?}");
+            let mut chip = Rda5807::new(i2c_driver, address);
+            boot_step("tuner found", probe_tuner(&mut chip, address_text))?;
+            let mut tuner = AdaptiveTimeout::new(chip, 0, timeout);
 
-        let mut tuner = Rda5708m::new(i2c_driver, Address::default());
+            boot_step("tuner started", tuner.start().map_err(Error::i2c))?;
+            std::thread::sleep(Duration::from_millis(100));
 
-        tuner.start().unwrap();
-        std::thread::sleep(Duration::from_millis(100));
+            // stay muted, the settings and the restored station and volume
+            // are sent by the event loop
+            boot_step("tuner volume set", tuner.set_volume(0).map_err(Error::i2c))?;
 
-        // set default values
-        tuner.set_seek_threshold(35).unwrap();
-        tuner.set_frequency(100_000).unwrap();
-        tuner.set_volume(0).unwrap(); // TODO: set to 5
+            Ok::<_, Error>((tuner, amp))
+        };
+
+        // without a tuner the radio is useless, the heartbeat stops
+        // and the watchdog restarts the device
+        match setup() {
+            Ok((mut tuner, amp)) => {
+                // the heartbeat stops with the loop, so the watchdog restarts the device
+                if let Err(err) = run_tuner(
+                    &mut tuner,
+                    amp,
+                    amp_active_level,
+                    &heartbeat,
+                    &event_sender,
+                    &command_receiver,
+                ) {
+                    log::error!("Tuner loop stopped: {err}");
+                }
+            }
+            // restarting would not help a tuner, which is not wired, so the error
+            // is shown instead, and the heartbeat is kept, so the device keeps running
+            Err(err @ Error::TunerNotFound(_)) => {
+                log::error!("Tuner could not be set up: {err}");
+                if event_sender.send(InputEvent::TunerNotFound).is_err() {
+                    return;
+                }
+                loop {
+                    heartbeat.feed();
+                    // commands are dropped, so that callers waiting for a reply give up
+                    while command_receiver.try_recv().is_ok() {}
+                    thread::sleep(Duration::from_millis(500));
+                }
+            }
+            Err(err) => log::error!("Tuner could not be set up: {err}"),
+        }
+    });
+
+    Ok(())
+}
+
+/// Enable pin of the external amplifier, so that the tuner loop can also drive a fake one.
+trait Amplifier {
+    type Error: Debug;
+
+    fn set_level(&mut self, level: Level) -> Result<(), Self::Error>;
+}
+
+impl<P: OutputPin> Amplifier for PinDriver<'_, P, Output> {
+    type Error = EspError;
+
+    fn set_level(&mut self, level: Level) -> Result<(), Self::Error> {
+        PinDriver::set_level(self, level)
+    }
+}
+
+/// Polls the tuner and executes commands from the event loop, reports changes
+/// of the tuned station and its RDS data back to it, and drives the amplifier.
+///
+/// Returns once all command senders are dropped, or with `Error::Channel`
+/// once the event loop stopped receiving the events.
+fn run_tuner<T: TunerChip>(
+    tuner: &mut T,
+    mut amp: Option<impl Amplifier>,
+    amp_active_level: Level,
+    heartbeat: &Heartbeat,
+    event_sender: &Sender<InputEvent>,
+    command_receiver: &Receiver<OutputCommand>,
+) -> Result<(), Error> {
+    let mut prev_freq = 0;
+    let mut prev_rssi = 0;
+    let mut prev_status = TunerStatus::default();
+
+    // debounced stereo state, and the number of consecutive reads which disagree with it
+    let mut stereo = false;
+    let mut stereo_changed_reads = 0;
+
+    // whether a seek was started and its result was not yet reported
+    let mut seeking = false;
+
+    // last volume set by the user, the amplifier is off at zero volume
+    let mut volume = 0;
+    let mut volume_trim = 0;
+
+    // whether RDS data should be fetched from the tuner
+    let mut rds_enabled = true;
+
+    // whether every received RDS group is logged, and the last read group,
+    // the registers keep the same group until the next one is received
+    let mut dump_rds = false;
+    let mut prev_blocks = [0; 4];
+
+    let mut rds = RdsDecoder::new();
+    let mut prev_program_type = 0;
+    let mut prev_reception = RdsReception::Waiting;
+
+    let mut signal = SignalMonitor::new(SIGNAL_LOST_RSSI, SIGNAL_ACQUIRED_RSSI, SIGNAL_DWELL);
+
+    // whether the tuner outputs mono, as last selected from the stereo mode
+    let mut stereo_selector = StereoSelector::new(MONO_BELOW_RSSI, STEREO_FROM_RSSI);
+    let mut mono = false;
+
+    // whether the next reads are reported, even if they did not change
+    let mut resync = false;
+
+    loop {
+        heartbeat.feed();
+
+        // another thread panicked, leave the tuner muted until the hook aborts
+        if panicked() {
+            tuner.set_volume(0).unwrap_or(());
+            if let Some(amp) = amp.as_mut() {
+                amp.set_level(!amp_active_level).unwrap_or(());
+            }
+            loop {
+                thread::park();
+            }
+        }
 
-        let mut prev_freq = 0;
-        let mut prev_rssi = 0;
+        let command = match command_receiver.try_recv() {
+            Ok(command) => Some(command),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => return Ok(()),
+        };
 
-        const EMPTY_TEXT: [char; 64] = [' '; 64];
-        let mut radio_text = EMPTY_TEXT;
+        if let Some(command) = command {
+            // commands with a reply report their result to the caller, failures of others are logged
+            let (command, reply) = match command {
+                OutputCommand::WithReply(command, reply) => (*command, Some(reply)),
+                command => (command, None),
+            };
 
-        loop {
-            let status = tuner.get_status().unwrap();
-            let (blera, blerb) = tuner.get_block_errors().unwrap();
+            // an abort, which came after the seek ended, is ignored
+            let cancel_to = match command {
+                OutputCommand::CancelSeek(freq) if seeking => Some(freq),
+                _ => None,
+            };
 
-            if let Ok(command) = command_receiver.try_recv() {
-                // if the command changes tuner frequency, reset the radio text
-                if let OutputCommand::SetVolume(_) = command {
-                } else {
-                    radio_text = EMPTY_TEXT;
-                    let info = radio_text.iter().collect::<String>();
-                    event_sender
-                        .send(InputEvent::ChangeStationInfo(info))
-                        .unwrap();
+            // if the command changes tuner frequency, reset the radio text,
+            // an ignored abort keeps the station tuned by the seek
+            let keeps_station = match command {
+                OutputCommand::SetVolume(_)
+                | OutputCommand::GetFrequency(_)
+                | OutputCommand::SetSeekThreshold(_)
+                | OutputCommand::SetVolumeTrim(_)
+                | OutputCommand::SetRdsDump(_)
+                | OutputCommand::SetStereoMode(_)
+                | OutputCommand::SetBand(_)
+                | OutputCommand::SetSpacing(_)
+                | OutputCommand::SetDeemphasis(_)
+                | OutputCommand::MeasureRssi
+                | OutputCommand::Resync => true,
+                OutputCommand::CancelSeek(_) => cancel_to.is_none(),
+                _ => false,
+            };
+            if !keeps_station {
+                rds.reset();
+                reset_rds_stats();
+                signal.restart();
+                event_sender.send(InputEvent::ChangeStationInfo(rds.radio_text()))?;
+                event_sender.send(InputEvent::ChangeTextHistory(Vec::new()))?;
+                event_sender.send(InputEvent::ChangeStationName(
+                    rds.station_name(),
+                    rds.station_name_complete(),
+                ))?;
+                prev_program_type = rds.program_type();
+                event_sender.send(InputEvent::ChangeProgramType(prev_program_type))?;
+            }
+
+            match command {
+                OutputCommand::CancelSeek(_) => seeking = false,
+                OutputCommand::SeekUp(_) | OutputCommand::SeekDown(_) => {
+                    // mute the static swept through during the seek,
+                    // the volume is restored once the seek completes
+                    if !seeking && volume > 0 {
+                        log_write("muting for the seek", tuner.set_volume(0));
+                    }
+                    seeking = true;
+                }
+                OutputCommand::SetRds(enabled) => rds_enabled = enabled,
+                OutputCommand::SetRdsDump(enabled) => dump_rds = enabled,
+                OutputCommand::SetStereoMode(mode) => stereo_selector.set_mode(mode),
+                OutputCommand::SetVolume(level) => volume = level,
+                OutputCommand::SetVolumeTrim(trim) => volume_trim = trim,
+                _ => (),
+            }
+
+            // the tuner is set to the trimmed volume, a new trim is applied immediately
+            let command = match command {
+                OutputCommand::SetVolume(_) | OutputCommand::SetVolumeTrim(_) => {
+                    OutputCommand::SetVolume(trimmed_volume(volume, volume_trim))
+                }
+                command => command,
+            };
+
+            // seeks, the scan and sampling, which block this loop, sweep through noise,
+            // so the amplifier is turned off before they start
+            let sweeping = matches!(
+                command,
+                OutputCommand::SeekUp(_)
+                    | OutputCommand::SeekDown(_)
+                    | OutputCommand::ScanBand(_)
+                    | OutputCommand::TuneStrongest(_)
+                    | OutputCommand::FindNearest
+            );
+            if let (true, Some(amp)) = (sweeping, amp.as_mut()) {
+                log_write(
+                    "turning off the amplifier",
+                    amp.set_level(!amp_active_level),
+                );
+            }
+
+            // process command from event loop
+            let result = match command {
+                // changing volume would unmute the seek, the new volume is set after it
+                OutputCommand::SetVolume(_) if seeking => Ok(()),
+                // the volume muted for a running seek is written once it completes
+                OutputCommand::Resync if seeking => {
+                    resync = true;
+                    Ok(())
                 }
+                OutputCommand::Resync => {
+                    resync = true;
+                    tuner
+                        .set_volume(trimmed_volume(volume, volume_trim))
+                        .map_err(Error::i2c)
+                }
+                OutputCommand::CancelSeek(_) if cancel_to.is_none() => Ok(()),
+                command => execute_command(tuner, command, event_sender, heartbeat),
+            };
+            // the events of the command have nowhere to go
+            if let Err(Error::Channel) = result {
+                return Err(Error::Channel);
+            }
+            match reply {
+                // the caller may have already stopped waiting for the result
+                Some(reply) => reply.send(result).unwrap_or(()),
+                None => log_write("command", result),
+            }
 
-                // process command from event loop
-                match command {
-                    OutputCommand::SetFrequency(freq) => tuner.set_frequency(freq).unwrap(),
-                    OutputCommand::SetVolume(volume) => tuner.set_volume(volume).unwrap(),
-                    OutputCommand::SeekUp => tuner.seek_up(true).unwrap(),
-                    OutputCommand::SeekDown => tuner.seek_down(true).unwrap(),
+            // the volume muted for the seek is restored, as when the seek completes
+            if let Some(freq) = cancel_to {
+                if volume > 0 {
+                    log_write(
+                        "restoring the volume",
+                        tuner.set_volume(trimmed_volume(volume, volume_trim)),
+                    );
                 }
+                event_sender.send(InputEvent::SeekCancelled(freq))?;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
 
-                thread::sleep(Duration::from_millis(10));
+        // the tuner sets the seek fail flag, when it went
+        // through the whole band without finding a station
+        // read after the commands, so that a just started seek is not seen as complete
+        let status = match tuner.get_status() {
+            Ok(status) => status,
+            Err(err) => {
+                poll_failed("status", err);
+                continue;
             }
+        };
 
-            // update RSSI
-            let rssi = tuner.get_rssi().unwrap();
-            if rssi.abs_diff(prev_rssi) > 5 {
-                event_sender.send(InputEvent::ChangeRSSI(rssi)).unwrap();
-                prev_rssi = rssi;
+        // the chip may have missed a write, while the bus was recovering
+        if tuner.take_bus_recovered() {
+            log::info!("tuner: resyncing after the I2C bus recovered");
+            resync = true;
+            if !seeking {
+                log_write(
+                    "restoring the volume",
+                    tuner.set_volume(trimmed_volume(volume, volume_trim)),
+                );
             }
+        }
 
-            let freq = tuner.get_frequency().unwrap();
+        // the chip may reach the found station only as it completes the seek
+        let seek_completed = seeking && status.stc;
+        if seek_completed {
+            seeking = false;
+            if volume > 0 {
+                log_write(
+                    "restoring the volume",
+                    tuner.set_volume(trimmed_volume(volume, volume_trim)),
+                );
+            }
+            if status.sf {
+                event_sender.send(InputEvent::SeekFailed)?;
+            } else {
+                confirm_seek();
+            }
+        }
 
-            // only send frequency updates when seeking
-            if prev_freq != freq && !status.stc {
-                event_sender
-                    .send(InputEvent::ChangeFrequency(freq))
-                    .unwrap();
-                prev_freq = freq;
+        // update status flags
+        if status.st == stereo {
+            stereo_changed_reads = 0;
+        } else {
+            stereo_changed_reads += 1;
+            if stereo_changed_reads >= STEREO_DEBOUNCE_READS {
+                stereo = status.st;
+                stereo_changed_reads = 0;
             }
+        }
+        let flags = TunerStatus {
+            stereo,
+            rds_synchronized: status.rdss,
+            seek_complete: status.stc,
+            seek_failed: status.sf,
+            mono,
+        };
+        if flags != prev_status || resync {
+            event_sender.send(InputEvent::ChangeStatus(flags))?;
+            prev_status = flags;
+        }
 
-            // update Radio Text
-            if status.rdss {
-                let [_, block_b, block_c, block_d] = tuner.get_rds_registers().unwrap();
-                let group_type = (block_b >> 12) & 0xF;
+        // mute the amplifier while seeking or at zero volume
+        if let Some(amp) = amp.as_mut() {
+            let level = if volume > 0 && !seeking {
+                amp_active_level
+            } else {
+                !amp_active_level
+            };
+            log_write("switching the amplifier", amp.set_level(level));
+        }
 
-                // only display characters if there are no detected errors
-                if group_type == 2 && blera == 0 && blerb == 0 {
-                    let offset = block_b & 0xF;
+        // update RSSI
+        let rssi = match tuner.get_rssi() {
+            Ok(rssi) => rssi,
+            Err(err) => {
+                poll_failed("RSSI", err);
+                continue;
+            }
+        };
+        if rssi.abs_diff(prev_rssi) > 5 || resync {
+            event_sender.send(InputEvent::ChangeRSSI(rssi))?;
+            prev_rssi = rssi;
+        }
 
-                    radio_text[(offset * 4 + 0) as usize] = (block_c >> 8) as u8 as char;
-                    radio_text[(offset * 4 + 1) as usize] = (block_c & 0xFF) as u8 as char;
-                    radio_text[(offset * 4 + 2) as usize] = (block_d >> 8) as u8 as char;
-                    radio_text[(offset * 4 + 3) as usize] = (block_d & 0xFF) as u8 as char;
+        // the RSSI swept through during a seek says nothing about the found station
+        if seeking {
+            signal.restart();
+        } else if let Some(event) = signal.update(rssi) {
+            event_sender.send(event)?;
+        }
 
-                    let info = radio_text.iter().collect::<String>();
-                    event_sender
-                        .send(InputEvent::ChangeStationInfo(info))
-                        .unwrap();
+        // the output mode follows the found station, not the RSSI swept through
+        if !seeking {
+            let selected = stereo_selector.update(rssi);
+            if selected != mono {
+                match tuner.set_mono(selected) {
+                    Ok(()) => mono = selected,
+                    // retried with the next poll
+                    Err(err) => log::warn!("tuner: output mode not set: {err:?}"),
                 }
             }
+        }
+
+        let freq = match tuner.get_frequency() {
+            Ok(freq) => freq,
+            Err(err) => {
+                poll_failed("frequency", err);
+                continue;
+            }
+        };
 
-            thread::sleep(Duration::from_millis(100));
+        // only send frequency updates when seeking
+        if (prev_freq != freq && (!status.stc || seek_completed)) || resync {
+            event_sender.send(InputEvent::ChangeFrequency(Frequency::from_khz(freq)))?;
+            prev_freq = freq;
         }
-    });
+        resync = false;
+
+        // a station without RDS is told apart from one, whose RDS is not decoded yet,
+        // its grace period starts only once a seek lands, it would run out during the sweep
+        if rds_enabled && !seeking {
+            let reception = rds.reception();
+            if reception != prev_reception {
+                event_sender.send(InputEvent::ChangeRdsReception(reception))?;
+                prev_reception = reception;
+            }
+        }
+
+        // update Radio Text and station name
+        if rds_enabled && status.rdss {
+            let read = tuner
+                .get_block_errors()
+                .and_then(|errors| Ok((errors, tuner.get_rds_registers()?)));
+            let ((blera, blerb), blocks) = match read {
+                Ok(read) => read,
+                Err(err) => {
+                    poll_failed("RDS group", err);
+                    continue;
+                }
+            };
+
+            // groups are counted once, the tuner may still hold the last one
+            let new_group = blocks != prev_blocks;
+            prev_blocks = blocks;
+            if new_group {
+                record_rds_group((blera, blerb));
+            }
+
+            if dump_rds && new_group {
+                log::info!(
+                    "rds: group {:<3} {:04X} {:04X} {:04X} {:04X} errors {blera}/{blerb}",
+                    group_name(blocks[1]),
+                    blocks[0],
+                    blocks[1],
+                    blocks[2],
+                    blocks[3],
+                );
+            }
+
+            // only display characters if there are no detected errors
+            if blera == 0 && blerb == 0 {
+                let update = rds.process_group(blocks);
+                if new_group {
+                    record_rds_decoded(group_name(blocks[1]), rds.station_name_percent());
+                    record_rds_station(rds.pi(), rds.ecc());
+                }
+
+                let event = match update {
+                    Some(RdsUpdate::RadioText(info)) => Some(InputEvent::ChangeStationInfo(info)),
+                    Some(RdsUpdate::StationName(name)) => Some(InputEvent::ChangeStationName(
+                        name,
+                        rds.station_name_complete(),
+                    )),
+                    Some(RdsUpdate::ClockTime(minutes)) => {
+                        Some(InputEvent::ChangeClockTime(minutes))
+                    }
+                    None => None,
+                };
+                if let Some(event) = event {
+                    event_sender.send(event)?;
+                }
+
+                if let Some(history) = rds.take_text_history() {
+                    event_sender.send(InputEvent::ChangeTextHistory(history))?;
+                }
+
+                if rds.program_type() != prev_program_type {
+                    prev_program_type = rds.program_type();
+                    event_sender.send(InputEvent::ChangeProgramType(prev_program_type))?;
+                }
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Logs a failed read of the polled tuner state, the loop then waits for its next poll.
+fn poll_failed(what: &str, err: impl Debug) {
+    log::warn!("tuner: {what} not read, skipping this poll: {err:?}");
+    thread::sleep(POLL_INTERVAL);
+}
+
+/// Logs a failed write, which the tuner loop can do without, a lasting fault
+/// shows as failed reads, which make the loop skip its polls.
+fn log_write(what: &str, result: Result<(), impl Debug>) {
+    if let Err(err) = result {
+        log::warn!("tuner: {what} failed: {err:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{Arc, Mutex},
+        thread::JoinHandle,
+    };
+
+    use super::*;
+    use crate::{
+        fake_chip::FakeChip,
+        frequency::{Band, Spacing, BAND_END_KHZ},
+        Deemphasis,
+    };
+
+    /// Frequency, at which the tuner loop starts
+    const START: u32 = 90_000;
+    /// Station, which the seeks find
+    const STATION: u32 = 100_500;
+
+    /// Longest wait for an event of the tuner loop
+    const EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Amplifier, which records every level it is switched to
+    #[derive(Clone, Default)]
+    struct FakeAmp(Arc<Mutex<Vec<Level>>>);
+
+    impl Amplifier for FakeAmp {
+        type Error = Infallible;
+
+        fn set_level(&mut self, level: Level) -> Result<(), Self::Error> {
+            self.0.lock().unwrap().push(level);
+            Ok(())
+        }
+    }
+
+    /// Tuner loop running on a fake chip in its own thread, with an amplifier active high
+    struct TunerLoop {
+        commands: Sender<OutputCommand>,
+        events: Receiver<InputEvent>,
+        amp: FakeAmp,
+        thread: JoinHandle<FakeChip>,
+    }
+
+    impl TunerLoop {
+        fn start(mut chip: FakeChip) -> Self {
+            let (commands, command_receiver) = channel();
+            let (event_sender, events) = channel();
+            let amp = FakeAmp::default();
+            let loop_amp = amp.clone();
+
+            let thread = thread::spawn(move || {
+                run_tuner(
+                    &mut chip,
+                    Some(loop_amp),
+                    Level::High,
+                    &Heartbeat::new(),
+                    &event_sender,
+                    &command_receiver,
+                )
+                .unwrap();
+                chip
+            });
+
+            TunerLoop {
+                commands,
+                events,
+                amp,
+                thread,
+            }
+        }
+
+        fn send(&self, command: OutputCommand) {
+            self.commands.send(command).unwrap();
+        }
+
+        /// Waits for the first event, which matches, returns the events received before it.
+        fn wait_for(&self, mut matches: impl FnMut(&InputEvent) -> bool) -> Vec<InputEvent> {
+            let mut before = Vec::new();
+            loop {
+                match self.events.recv_timeout(EVENT_TIMEOUT) {
+                    Ok(event) if matches(&event) => return before,
+                    Ok(event) => before.push(event),
+                    Err(err) => panic!("expected event not received ({err}), got {before:?}"),
+                }
+            }
+        }
+
+        /// Waits until the loop executed every command sent before.
+        fn sync(&self) {
+            let (reply, frequency) = channel();
+            self.send(OutputCommand::GetFrequency(reply));
+            frequency.recv_timeout(EVENT_TIMEOUT).unwrap();
+        }
+
+        /// Stops the loop, returns the chip and the levels the amplifier was switched to.
+        fn stop(self) -> (FakeChip, Vec<Level>) {
+            drop(self.commands);
+            let chip = self.thread.join().unwrap();
+            let levels = self.amp.0.lock().unwrap().clone();
+            (chip, levels)
+        }
+    }
+
+    fn chip(stations: Vec<u32>) -> FakeChip {
+        let mut chip = FakeChip::new(stations);
+        chip.frequency = START;
+        chip
+    }
+
+    #[test]
+    fn seek_is_muted_until_the_station_is_found() {
+        let tuner = TunerLoop::start(chip(vec![STATION]));
+        tuner.send(OutputCommand::SetVolume(10));
+        tuner.send(OutputCommand::SeekUp(false));
+
+        let before = tuner
+            .wait_for(|event| *event == InputEvent::ChangeFrequency(Frequency::from_khz(STATION)));
+        // the sweep is shown, before the seek completes
+        assert!(before.iter().any(|event| matches!(
+            event,
+            InputEvent::ChangeFrequency(freq) if (START + 1..STATION).contains(&freq.as_khz())
+        )));
+        assert!(before.iter().any(|event| matches!(
+            event,
+            InputEvent::ChangeStatus(status) if !status.seek_complete
+        )));
+        tuner.sync();
+
+        let (chip, levels) = tuner.stop();
+        assert_eq!(chip.frequency, STATION);
+        assert_eq!(chip.volume_writes, [10, 0, 10]);
+
+        // the amplifier goes off for the seek and back on after it
+        let off = levels
+            .iter()
+            .position(|&level| level == Level::Low)
+            .unwrap();
+        assert!(levels[..off].contains(&Level::High));
+        assert_eq!(levels.last(), Some(&Level::High));
+    }
+
+    #[test]
+    fn failed_seek_is_reported() {
+        let tuner = TunerLoop::start(chip(Vec::new()));
+        tuner.send(OutputCommand::SeekUp(false));
+
+        let before = tuner.wait_for(|event| *event == InputEvent::SeekFailed);
+        assert!(
+            !before.iter().any(|event| matches!(
+                event,
+                InputEvent::ChangeFrequency(freq) if freq.as_khz() > BAND_END_KHZ
+            )),
+            "{before:?}"
+        );
+        // the seek stops at the band end, which is then shown
+        let mut landed = None;
+        tuner.wait_for(|event| match event {
+            InputEvent::ChangeFrequency(freq) => {
+                landed = Some(freq.as_khz());
+                true
+            }
+            _ => false,
+        });
+        assert_eq!(landed, Some(BAND_END_KHZ));
+
+        let (chip, _) = tuner.stop();
+        assert_eq!(chip.frequency, BAND_END_KHZ);
+        assert_eq!(chip.seeks, 1);
+    }
+
+    #[test]
+    fn rssi_is_reported_once_it_changes_by_more_than_five() {
+        let mut chip = chip(vec![STATION]);
+        chip.rssi = [30, 33, 36, 40, 42].into();
+        let tuner = TunerLoop::start(chip);
+
+        let before = tuner.wait_for(|event| *event == InputEvent::ChangeRSSI(42));
+        let reported: Vec<_> = before
+            .iter()
+            .filter_map(|event| match event {
+                InputEvent::ChangeRSSI(rssi) => Some(*rssi),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reported, [30, 36]);
+        tuner.stop();
+    }
+
+    #[test]
+    fn stereo_is_reported_after_the_debounce() {
+        let mut chip = chip(vec![STATION]);
+        chip.stereo = true;
+        // every poll reports a new RSSI, which counts the status reads,
+        // all of them strong enough for stereo
+        chip.rssi = (0..20).map(|poll| STEREO_FROM_RSSI + poll * 10).collect();
+        let tuner = TunerLoop::start(chip);
+
+        let before = tuner
+            .wait_for(|event| matches!(event, InputEvent::ChangeStatus(status) if status.stereo));
+        let polls = before
+            .iter()
+            .filter(|event| matches!(event, InputEvent::ChangeRSSI(_)))
+            .count();
+        // the status of a poll is reported before its RSSI
+        assert_eq!(polls as u32, STEREO_DEBOUNCE_READS - 1);
+        tuner.stop();
+    }
+
+    #[test]
+    fn rds_groups_are_shown_as_station_info() {
+        const PI: u16 = 0x2204;
+        let name = [b"RA", b"DI", b"O ", b"  "]
+            .iter()
+            .enumerate()
+            .map(|(segment, chars)| [PI, segment as u16, 0, u16::from_be_bytes(**chars)]);
+        let text =
+            [b"NEWS", b" AT ", b"NINE"]
+                .iter()
+                .enumerate()
+                .map(|(segment, &&[a, b, c, d])| {
+                    let block_b = 0x2000 | segment as u16;
+                    [
+                        PI,
+                        block_b,
+                        u16::from_be_bytes([a, b]),
+                        u16::from_be_bytes([c, d]),
+                    ]
+                });
+
+        let mut chip = chip(vec![STATION]);
+        chip.rds_groups = name.chain(text).collect();
+        let tuner = TunerLoop::start(chip);
+        tuner.send(OutputCommand::SetRds(true));
+
+        tuner.wait_for(|event| {
+            *event == InputEvent::ChangeStationName("RADIO   ".to_string(), true)
+        });
+        tuner.wait_for(|event| {
+            matches!(event, InputEvent::ChangeStationInfo(info) if info.starts_with("NEWS AT NINE"))
+        });
+        tuner.stop();
+    }
+
+    #[test]
+    fn cancelled_seek_tunes_back() {
+        let mut chip = chip(vec![STATION]);
+        chip.seek_polls = u32::MAX;
+        let tuner = TunerLoop::start(chip);
+        tuner.send(OutputCommand::SetVolume(10));
+        tuner.send(OutputCommand::SeekUp(false));
+        tuner.wait_for(
+            |event| matches!(event, InputEvent::ChangeStatus(status) if !status.seek_complete),
+        );
+
+        tuner.send(OutputCommand::CancelSeek(Frequency::from_khz(START)));
+        tuner.wait_for(|event| *event == InputEvent::SeekCancelled(Frequency::from_khz(START)));
+
+        let (chip, levels) = tuner.stop();
+        assert!(!chip.is_seeking());
+        assert_eq!(chip.frequency, START);
+        assert_eq!(chip.volume_writes, [10, 0, 10]);
+        assert_eq!(levels.last(), Some(&Level::High));
+    }
+
+    #[test]
+    fn late_cancel_keeps_the_found_station() {
+        let tuner = TunerLoop::start(chip(vec![STATION]));
+        tuner.send(OutputCommand::SetVolume(10));
+        tuner.send(OutputCommand::SeekUp(false));
+        tuner.wait_for(|event| *event == InputEvent::ChangeFrequency(Frequency::from_khz(STATION)));
+        tuner.sync();
+        tuner.events.try_iter().for_each(drop);
+
+        // the abort was sent, before the event loop saw the seek complete
+        tuner.send(OutputCommand::CancelSeek(Frequency::from_khz(START)));
+        tuner.sync();
+        let after: Vec<_> = tuner.events.try_iter().collect();
+        assert!(
+            !after.iter().any(|event| matches!(
+                event,
+                InputEvent::SeekCancelled(_)
+                    | InputEvent::ChangeStationName(..)
+                    | InputEvent::ChangeStationInfo(_)
+            )),
+            "{after:?}"
+        );
+
+        let (chip, _) = tuner.stop();
+        assert_eq!(chip.frequency, STATION);
+        assert_eq!(chip.volume_writes, [10, 0, 10]);
+    }
+
+    #[test]
+    fn band_plan_is_set_on_the_chip() {
+        let tuner = TunerLoop::start(chip(vec![STATION]));
+        tuner.send(OutputCommand::SetBand(Band::Japan));
+        tuner.send(OutputCommand::SetSpacing(Spacing::Khz200));
+        tuner.send(OutputCommand::SetDeemphasis(Deemphasis::Us50));
+        tuner.sync();
+
+        let (chip, _) = tuner.stop();
+        assert_eq!(chip.band, Band::Japan);
+        assert_eq!(chip.spacing, Spacing::Khz200);
+        assert_eq!(chip.deemphasis, Deemphasis::Us50);
+    }
+
+    #[test]
+    fn amplifier_stays_off_at_zero_volume() {
+        let tuner = TunerLoop::start(chip(vec![STATION]));
+        tuner.send(OutputCommand::SetVolume(0));
+        tuner.send(OutputCommand::SeekUp(false));
+        tuner.wait_for(
+            |event| matches!(event, InputEvent::ChangeStatus(status) if status.seek_complete),
+        );
+        tuner.sync();
+
+        let (chip, levels) = tuner.stop();
+        assert_eq!(chip.volume_writes, [0]);
+        assert!(!levels.is_empty());
+        assert!(levels.iter().all(|&level| level == Level::Low));
+    }
+
+    #[test]
+    fn loop_stops_without_the_event_loop() {
+        let (commands, command_receiver) = channel();
+        let (event_sender, events) = channel();
+        drop(events);
+
+        commands
+            .send(OutputCommand::SetFrequency(Frequency::from_khz(STATION)))
+            .unwrap();
+        let result = run_tuner(
+            &mut chip(vec![STATION]),
+            None::<FakeAmp>,
+            Level::High,
+            &Heartbeat::new(),
+            &event_sender,
+            &command_receiver,
+        );
+        assert!(matches!(result, Err(Error::Channel)), "{result:?}");
+    }
+
+    #[test]
+    fn scan_waits_for_each_seek() {
+        let stations = vec![88_000, STATION];
+        let mut chip = chip(stations.clone());
+        chip.rssi = [30].into();
+        let tuner = TunerLoop::start(chip);
+        tuner.send(OutputCommand::ScanBand(ScanCriteria::Rssi));
+
+        let mut found = Vec::new();
+        tuner.wait_for(|event| match event {
+            InputEvent::ScanFinished(stations) => {
+                found = stations.iter().map(|&(freq, _)| freq.as_khz()).collect();
+                true
+            }
+            _ => false,
+        });
+        assert_eq!(found, stations);
+
+        let (chip, _) = tuner.stop();
+        assert_eq!(chip.frequency, START);
+        assert_eq!(chip.seeks, 3);
+    }
 }
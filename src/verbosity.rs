@@ -0,0 +1,52 @@
+use esp_idf_svc::log::EspLogger;
+use log::LevelFilter;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::InputEvent;
+
+/// How much of the processed events is logged, set at runtime from the serial console.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Nothing, the console stays quiet
+    Off,
+    /// User inputs, logged at the info level
+    Info,
+    /// User inputs and all events from the tuner, which are logged at the debug level
+    Debug,
+}
+
+/// Current verbosity of the event log
+static EVENT_VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Off as u8);
+
+/// Changes, which events are logged.
+///
+/// Debug events are only printed with the level of this module raised in the ESP-IDF log,
+/// the other modules keep their level from sdkconfig.
+pub fn set_event_verbosity(verbosity: Verbosity) {
+    EVENT_VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+
+    let level = match verbosity {
+        Verbosity::Debug => LevelFilter::Debug,
+        Verbosity::Off | Verbosity::Info => LevelFilter::Info,
+    };
+    if let Err(err) = EspLogger::new().set_target_level(module_path!(), level) {
+        log::warn!("Log level of the events not set: {err}");
+    }
+}
+
+/// Logs the event processed by the event loop, according to the verbosity.
+///
+/// Events are logged in the format of their `Debug` output,
+/// so that a copied log can be replayed with the `replay` console command.
+pub fn log_event(event: &InputEvent) {
+    let verbosity = EVENT_VERBOSITY.load(Ordering::Relaxed);
+    if verbosity == Verbosity::Off as u8 {
+        return;
+    }
+
+    if event.is_user_input() {
+        log::info!("event: {event:?}");
+    } else if verbosity == Verbosity::Debug as u8 {
+        log::debug!("event: {event:?}");
+    }
+}
@@ -0,0 +1,63 @@
+use esp_idf_svc::sys::esp_restart;
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::panic::turn_off_amp;
+
+/// How long a worker thread may go without feeding the watchdog,
+/// longer than the slowest step of a band scan
+const WATCHDOG_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Liveness of a single worker thread, which has to feed it
+/// regularly to show that it is still responding.
+#[derive(Clone)]
+pub struct Heartbeat(Arc<AtomicU32>);
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Heartbeat(Arc::new(AtomicU32::new(0)))
+    }
+
+    /// Shows that the thread is still responding.
+    pub fn feed(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn beats(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Spawns a new thread, which checks that all given worker threads keep feeding
+/// their heartbeats. If one of them stalls, e.g. the tuner thread wedged on I2C,
+/// the amplifier is turned off and the device is restarted.
+///
+/// Threads which block on interrupts, like input listeners, cannot be watched.
+pub fn spawn_watchdog(threads: Vec<(&'static str, Heartbeat)>) {
+    thread::spawn(move || {
+        let mut last_beats = threads
+            .iter()
+            .map(|(_, heartbeat)| heartbeat.beats())
+            .collect::<Vec<_>>();
+
+        loop {
+            thread::sleep(WATCHDOG_TIMEOUT);
+
+            for ((name, heartbeat), last) in threads.iter().zip(&mut last_beats) {
+                let beats = heartbeat.beats();
+                if beats == *last {
+                    log::error!("watchdog: {name} thread stopped responding, restarting");
+                    turn_off_amp();
+                    unsafe { esp_restart() };
+                }
+                *last = beats;
+            }
+        }
+    });
+}